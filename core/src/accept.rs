@@ -0,0 +1,57 @@
+/// Check whether an `Accept` header value allows the given media type.
+///
+/// A missing or empty header accepts everything. Ranges such as `*/*` and
+/// `application/*` match, and entries with `q=0` are treated as exclusions.
+pub fn accepts(accept: Option<&str>, media_type: &str) -> bool {
+    let Some(accept) = accept.map(str::trim).filter(|a| !a.is_empty()) else {
+        return true;
+    };
+    let (main_type, _) = media_type.split_once('/').unwrap_or((media_type, ""));
+
+    accept.split(',').any(|entry| {
+        let mut parts = entry.split(';').map(str::trim);
+        let range = parts.next().unwrap_or("").to_ascii_lowercase();
+        let excluded = parts.any(|param| {
+            param
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+
+        !excluded
+            && (range == "*/*"
+                || range == media_type
+                || range.strip_suffix("/*") == Some(main_type))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_header_accepts() {
+        assert!(accepts(None, "application/gzip"));
+        assert!(accepts(Some(""), "application/gzip"));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(accepts(Some("*/*"), "application/gzip"));
+        assert!(accepts(Some("application/*"), "application/gzip"));
+        assert!(accepts(
+            Some("text/html, application/gzip;q=0.9"),
+            "application/gzip"
+        ));
+    }
+
+    #[test]
+    fn test_excluded() {
+        assert!(!accepts(Some("application/json"), "application/gzip"));
+        assert!(!accepts(Some("text/*"), "application/gzip"));
+        assert!(!accepts(
+            Some("application/json, */*;q=0"),
+            "application/gzip"
+        ));
+    }
+}
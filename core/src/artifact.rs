@@ -0,0 +1,100 @@
+use crate::path::normalize_platform;
+use crate::storage::RegistryStorage;
+use anyhow::{bail, Context, Result};
+use lib_plugin_registry::PlatformBuild;
+use tokio::fs::File;
+
+/// An open artifact, ready to be streamed to another sink without buffering it in memory.
+#[derive(Debug)]
+pub struct ArtifactReader {
+    /// The artifact file, positioned at the start.
+    pub reader: File,
+    /// Size of the file on disk, in bytes.
+    pub size_bytes: u64,
+    /// SHA-256 recorded at publish time.
+    pub checksum: String,
+}
+
+impl RegistryStorage {
+    /// Open a stored artifact for reading. `kind` is `packages` or `plugins`.
+    ///
+    /// Fails if the version does not list the platform or its file is missing.
+    pub async fn open_artifact_reader(
+        &self,
+        kind: &str,
+        id: &str,
+        version: &str,
+        platform: &str,
+    ) -> Result<ArtifactReader> {
+        let platform = normalize_platform(platform);
+        let (platforms, path): (Vec<PlatformBuild>, _) = match kind {
+            "packages" => (
+                self.get_package_info(id, version).await?.platforms,
+                self.package_artifact_path(id, version, &platform),
+            ),
+            "plugins" => (
+                self.get_plugin_info(id, version).await?.platforms,
+                self.plugin_artifact_path(id, version, &platform),
+            ),
+            _ => bail!("Unknown artifact kind: {}", kind),
+        };
+        let build = platforms
+            .into_iter()
+            .find(|b| b.platform == platform)
+            .context("Platform not found")?;
+
+        let reader = File::open(&path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let size_bytes = reader.metadata().await?.len();
+        Ok(ArtifactReader {
+            reader,
+            size_bytes,
+            checksum: build.checksum,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_artifact_reader_yields_stored_bytes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        let data = b"plugin binary \x00\x01\x02";
+        storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                data,
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let mut artifact = storage
+            .open_artifact_reader("plugins", "adi.tasks", "1.0.0", "Linux-X86_64")
+            .await
+            .unwrap();
+        let mut read = Vec::new();
+        artifact.reader.read_to_end(&mut read).await.unwrap();
+        assert_eq!(read, data);
+        assert_eq!(artifact.size_bytes, data.len() as u64);
+        let info = storage.get_plugin_info("adi.tasks", "1.0.0").await.unwrap();
+        assert_eq!(artifact.checksum, info.platforms[0].checksum);
+
+        assert!(storage
+            .open_artifact_reader("plugins", "adi.tasks", "1.0.0", "darwin-aarch64")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Named files published alongside a version's platform artifacts, such as a
+//! config schema or an icon.
+
+use crate::etag::sha256_hex;
+use crate::storage::{check_id, check_version, write_atomic, RegistryStorage};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+
+/// Longest accepted asset name.
+const MAX_ASSET_NAME_LEN: usize = 128;
+
+/// An asset as recorded in the version's `assets.json`, kept outside `info.json`
+/// since the info types have no field for it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetMeta {
+    pub name: String,
+    pub size_bytes: u64,
+    /// Lowercase hex SHA-256 of the asset.
+    pub checksum: String,
+}
+
+/// An asset name that can't be stored, since it becomes a file name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidAssetName {
+    pub name: String,
+}
+
+impl std::fmt::Display for InvalidAssetName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid asset name '{}': use up to {} letters, digits, '.', '-' or '_', not starting with '.'",
+            self.name, MAX_ASSET_NAME_LEN
+        )
+    }
+}
+
+impl std::error::Error for InvalidAssetName {}
+
+/// An asset with this name was already published for the version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetExists {
+    pub name: String,
+}
+
+impl std::fmt::Display for AssetExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Version already has an asset named {}", self.name)
+    }
+}
+
+impl std::error::Error for AssetExists {}
+
+fn check_asset_name(name: &str) -> Result<(), InvalidAssetName> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_ASSET_NAME_LEN
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(InvalidAssetName {
+            name: name.to_string(),
+        })
+    }
+}
+
+impl RegistryStorage {
+    fn version_dir(&self, kind: &str, id: &str, version: &str) -> PathBuf {
+        self.root().join(kind).join(id).join(version)
+    }
+
+    /// Store a named asset for an existing version (`kind` is `packages` or `plugins`)
+    /// and record it in the version's `assets.json`.
+    ///
+    /// Fails with [`InvalidAssetName`] or [`AssetExists`] before writing anything.
+    pub async fn publish_asset(
+        &self,
+        kind: &str,
+        id: &str,
+        version: &str,
+        name: &str,
+        data: &[u8],
+    ) -> Result<AssetMeta> {
+        check_id(id)?;
+        check_version(version)?;
+        check_asset_name(name)?;
+        let _lock = self.lock_index().await?;
+        let version_dir = self.version_dir(kind, id, version);
+        anyhow::ensure!(
+            self.resolve(version_dir.join("info.json")).exists(),
+            "{} {} is not published",
+            id,
+            version
+        );
+        let mut assets = self.list_assets(kind, id, version).await?;
+        if assets.iter().any(|a| a.name == name) {
+            return Err(AssetExists {
+                name: name.to_string(),
+            }
+            .into());
+        }
+
+        let assets_dir = version_dir.join("assets");
+        fs::create_dir_all(&assets_dir).await?;
+        write_atomic(&assets_dir.join(name), data).await?;
+        let asset = AssetMeta {
+            name: name.to_string(),
+            size_bytes: data.len() as u64,
+            checksum: sha256_hex(data),
+        };
+        assets.push(asset.clone());
+        assets.sort_by(|a, b| a.name.cmp(&b.name));
+        write_atomic(
+            &version_dir.join("assets.json"),
+            serde_json::to_string_pretty(&assets)?.as_bytes(),
+        )
+        .await?;
+        Ok(asset)
+    }
+
+    /// Assets published for a version, by name; empty if there are none.
+    pub async fn list_assets(&self, kind: &str, id: &str, version: &str) -> Result<Vec<AssetMeta>> {
+        let path = self.resolve(self.version_dir(kind, id, version).join("assets.json"));
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let data = fs::read_to_string(&path).await?;
+        serde_json::from_str(&data).context("Failed to parse assets.json")
+    }
+
+    /// Path of a published asset, or `None` if the version has no asset by that name.
+    pub fn asset_path(&self, kind: &str, id: &str, version: &str, name: &str) -> Option<PathBuf> {
+        check_asset_name(name).ok()?;
+        let path = self.resolve(
+            self.version_dir(kind, id, version)
+                .join("assets")
+                .join(name),
+        );
+        path.exists().then_some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup() -> (RegistryStorage, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        (storage, tmp)
+    }
+
+    #[tokio::test]
+    async fn test_publish_multiple_assets() {
+        let (storage, _tmp) = setup().await;
+        for (name, data) in [("schema.json", &b"{}"[..]), ("icon.png", b"png bytes")] {
+            storage
+                .publish_asset("plugins", "adi.tasks", "1.0.0", name, data)
+                .await
+                .unwrap();
+        }
+
+        let assets = storage
+            .list_assets("plugins", "adi.tasks", "1.0.0")
+            .await
+            .unwrap();
+        let names: Vec<_> = assets.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["icon.png", "schema.json"]);
+        assert_eq!(assets[0].size_bytes, 9);
+        assert_eq!(assets[0].checksum, sha256_hex(b"png bytes"));
+
+        let path = storage
+            .asset_path("plugins", "adi.tasks", "1.0.0", "schema.json")
+            .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), b"{}");
+        assert!(storage
+            .asset_path("plugins", "adi.tasks", "1.0.0", "missing.txt")
+            .is_none());
+        // The platform artifact is untouched
+        assert!(storage
+            .get_plugin_info("adi.tasks", "1.0.0")
+            .await
+            .is_ok_and(|info| info.platforms.len() == 1));
+    }
+
+    #[tokio::test]
+    async fn test_publish_asset_rejects_bad_input() {
+        let (storage, _tmp) = setup().await;
+        for name in ["", ".hidden", "../escape", "a/b", "spaced name"] {
+            let err = storage
+                .publish_asset("plugins", "adi.tasks", "1.0.0", name, b"data")
+                .await
+                .unwrap_err();
+            assert!(err.downcast_ref::<InvalidAssetName>().is_some(), "{}", name);
+        }
+
+        storage
+            .publish_asset("plugins", "adi.tasks", "1.0.0", "icon.png", b"data")
+            .await
+            .unwrap();
+        let err = storage
+            .publish_asset("plugins", "adi.tasks", "1.0.0", "icon.png", b"other")
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<AssetExists>().is_some());
+
+        assert!(storage
+            .publish_asset("plugins", "adi.tasks", "9.9.9", "icon.png", b"data")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,243 @@
+use crate::storage::{now_unix, RegistryStorage};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// How much of the end of `audit.log` is scanned when reading entries back.
+const AUDIT_TAIL_BYTES: u64 = 4 * 1024 * 1024;
+
+/// A single audit record, stored as one JSON line in `audit.log`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub action: String,
+    pub kind: String,
+    pub id: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    /// The author the client supplied when publishing.
+    pub author: String,
+    /// Who the request was authorized as; empty in entries written before it was recorded.
+    #[serde(default)]
+    pub actor: String,
+}
+
+impl AuditEntry {
+    /// Create an entry stamped with the current time.
+    pub fn new(
+        action: &str,
+        kind: &str,
+        id: &str,
+        version: &str,
+        platform: Option<&str>,
+        author: &str,
+        actor: &str,
+    ) -> Self {
+        Self {
+            timestamp: now_unix(),
+            action: action.to_string(),
+            kind: kind.to_string(),
+            id: id.to_string(),
+            version: version.to_string(),
+            platform: platform.map(str::to_string),
+            author: author.to_string(),
+            actor: actor.to_string(),
+        }
+    }
+}
+
+/// Criteria for reading back audit entries. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub kind: Option<String>,
+    pub id: Option<String>,
+    pub since: Option<u64>,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.kind.as_ref().is_none_or(|k| &entry.kind == k)
+            && self.id.as_ref().is_none_or(|id| &entry.id == id)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+    }
+}
+
+impl RegistryStorage {
+    fn audit_log_path(&self) -> PathBuf {
+        self.root().join("audit.log")
+    }
+
+    /// Append an entry to `audit.log`.
+    ///
+    /// The line is written with a single append so concurrent writers never interleave.
+    pub async fn append_audit(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.audit_log_path())
+            .await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Read the most recent audit entries matching `filter`, newest first.
+    ///
+    /// Only the tail of the log is scanned, so very old entries may not be returned.
+    pub async fn read_audit(&self, filter: &AuditFilter, limit: usize) -> Result<Vec<AuditEntry>> {
+        let path = self.audit_log_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut file = fs::File::open(&path).await?;
+        let len = file.metadata().await?.len();
+        let start = len.saturating_sub(AUDIT_TAIL_BYTES);
+        file.seek(SeekFrom::Start(start)).await?;
+
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf).await?;
+        let text = String::from_utf8_lossy(&buf);
+
+        // When starting mid-file the first line is likely partial
+        let skip = usize::from(start > 0);
+
+        Ok(text
+            .lines()
+            .skip(skip)
+            .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+            .filter(|entry| filter.matches(entry))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .take(limit)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup() -> (RegistryStorage, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        (storage, tmp)
+    }
+
+    fn entry(kind: &str, id: &str, version: &str, timestamp: u64) -> AuditEntry {
+        AuditEntry {
+            timestamp,
+            action: "publish".to_string(),
+            kind: kind.to_string(),
+            id: id.to_string(),
+            version: version.to_string(),
+            platform: Some("linux-x86_64".to_string()),
+            author: "ADI Team".to_string(),
+            actor: "ci".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_empty_log() {
+        let (storage, _tmp) = setup().await;
+        let entries = storage
+            .read_audit(&AuditFilter::default(), 10)
+            .await
+            .unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_append_writes_json_lines() {
+        let (storage, tmp) = setup().await;
+        storage
+            .append_audit(&entry("plugin", "adi.tasks", "1.0.0", 100))
+            .await
+            .unwrap();
+        storage
+            .append_audit(&entry("plugin", "adi.tasks", "1.1.0", 200))
+            .await
+            .unwrap();
+
+        let data = std::fs::read_to_string(tmp.path().join("audit.log")).unwrap();
+        let lines: Vec<_> = data.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: AuditEntry = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first.version, "1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_reads_entries_without_actor() {
+        let (storage, tmp) = setup().await;
+        let line = r#"{"timestamp":100,"action":"delete","kind":"plugin","id":"adi.tasks","version":"1.0.0","author":"unknown"}"#;
+        std::fs::write(tmp.path().join("audit.log"), format!("{}\n", line)).unwrap();
+
+        let entries = storage
+            .read_audit(&AuditFilter::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor, "");
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_id_newest_first() {
+        let (storage, _tmp) = setup().await;
+        for e in [
+            entry("plugin", "adi.tasks", "1.0.0", 100),
+            entry("plugin", "adi.notes", "1.0.0", 150),
+            entry("plugin", "adi.tasks", "1.1.0", 200),
+            entry("package", "adi.bundle", "1.0.0", 250),
+        ] {
+            storage.append_audit(&e).await.unwrap();
+        }
+
+        let filter = AuditFilter {
+            id: Some("adi.tasks".to_string()),
+            ..Default::default()
+        };
+        let entries = storage.read_audit(&filter, 10).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.id == "adi.tasks"));
+        assert_eq!(entries[0].version, "1.1.0");
+        assert_eq!(entries[1].version, "1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_kind_since_and_limit() {
+        let (storage, _tmp) = setup().await;
+        for i in 0..5 {
+            storage
+                .append_audit(&entry(
+                    "plugin",
+                    "adi.tasks",
+                    &format!("1.{}.0", i),
+                    i * 100,
+                ))
+                .await
+                .unwrap();
+        }
+        storage
+            .append_audit(&entry("package", "adi.bundle", "1.0.0", 500))
+            .await
+            .unwrap();
+
+        let filter = AuditFilter {
+            kind: Some("plugin".to_string()),
+            since: Some(200),
+            ..Default::default()
+        };
+        let entries = storage.read_audit(&filter, 2).await.unwrap();
+        let versions: Vec<_> = entries.iter().map(|e| e.version.as_str()).collect();
+        assert_eq!(versions, vec!["1.4.0", "1.3.0"]);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::storage::now_unix;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tokio::fs;
+use tokio::sync::Mutex;
+
+/// A publish API key as persisted in `keys.json`.
+///
+/// Only the SHA-256 of the key is stored; the plaintext is returned once on creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub label: String,
+    pub key_hash: String,
+    pub created_at: u64,
+}
+
+/// File-backed set of publish API keys.
+///
+/// Lookups go through an in-memory copy so request checks never touch disk.
+/// Mutations rewrite `keys.json` under a lock and then swap the in-memory set,
+/// so a revoked key is rejected by the very next request.
+pub struct KeyStore {
+    path: PathBuf,
+    keys: RwLock<Vec<ApiKey>>,
+    write_lock: Mutex<()>,
+}
+
+impl KeyStore {
+    /// Load keys from `path`, starting empty if the file does not exist.
+    pub async fn load(path: PathBuf) -> Result<Self> {
+        let keys = if path.exists() {
+            let data = fs::read_to_string(&path)
+                .await
+                .context("Failed to read keys.json")?;
+            serde_json::from_str(&data).context("Failed to parse keys.json")?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path,
+            keys: RwLock::new(keys),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    /// Whether any keys are configured. Publishing stays open when this is false.
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.read().unwrap().is_empty()
+    }
+
+    /// Return the label of the key matching `token`, if any.
+    pub fn verify(&self, token: &str) -> Option<String> {
+        let hash = hash_key(token);
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .find(|k| k.key_hash == hash)
+            .map(|k| k.label.clone())
+    }
+
+    /// List configured keys (hashes only).
+    pub fn list(&self) -> Vec<ApiKey> {
+        self.keys.read().unwrap().clone()
+    }
+
+    /// Register a new key under `label`. Returns `None` if the label is already taken.
+    pub async fn create(&self, label: &str, key: &str) -> Result<Option<ApiKey>> {
+        if label.is_empty() {
+            bail!("Key label must not be empty");
+        }
+
+        let _guard = self.write_lock.lock().await;
+        let mut keys = self.list();
+        if keys.iter().any(|k| k.label == label) {
+            return Ok(None);
+        }
+
+        let entry = ApiKey {
+            label: label.to_string(),
+            key_hash: hash_key(key),
+            created_at: now_unix(),
+        };
+        keys.push(entry.clone());
+        self.persist(keys).await?;
+
+        Ok(Some(entry))
+    }
+
+    /// Revoke the key with `label`. Returns false if no such key exists.
+    pub async fn revoke(&self, label: &str) -> Result<bool> {
+        let _guard = self.write_lock.lock().await;
+        let mut keys = self.list();
+        let before = keys.len();
+        keys.retain(|k| k.label != label);
+        if keys.len() == before {
+            return Ok(false);
+        }
+
+        self.persist(keys).await?;
+        Ok(true)
+    }
+
+    /// Write `keys` to disk, then replace the in-memory set.
+    async fn persist(&self, keys: Vec<ApiKey>) -> Result<()> {
+        let json = serde_json::to_string_pretty(&keys)?;
+        fs::write(&self.path, json).await?;
+        *self.keys.write().unwrap() = keys;
+        Ok(())
+    }
+}
+
+/// A key configured outside `keys.json`, written `key` or `key:prefix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticKey {
+    pub key: String,
+    /// Ids the key may publish and delete start with this; empty allows every id.
+    pub prefix: String,
+}
+
+impl StaticKey {
+    /// Parse `key` or `key:prefix`, e.g. `key123:acme.`. A trailing `*` on the prefix is
+    /// ignored, so `acme.*` and `*` work too. `None` when the key part is empty.
+    pub fn parse(entry: &str) -> Option<Self> {
+        let (key, prefix) = entry.split_once(':').unwrap_or((entry, ""));
+        if key.is_empty() {
+            return None;
+        }
+        Some(Self {
+            key: key.to_string(),
+            prefix: prefix.trim_end_matches('*').to_string(),
+        })
+    }
+
+    /// Whether the key covers `id`.
+    pub fn allows(&self, id: &str) -> bool {
+        id.starts_with(&self.prefix)
+    }
+}
+
+fn hash_key(key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup() -> (KeyStore, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = KeyStore::load(tmp.path().join("keys.json")).await.unwrap();
+        (store, tmp)
+    }
+
+    #[tokio::test]
+    async fn test_empty_store_is_disabled() {
+        let (store, _tmp) = setup().await;
+        assert!(!store.is_enabled());
+        assert!(store.verify("anything").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_and_verify() {
+        let (store, _tmp) = setup().await;
+        store.create("ci", "secret-1").await.unwrap();
+        assert!(store.is_enabled());
+        assert_eq!(store.verify("secret-1").as_deref(), Some("ci"));
+        assert!(store.verify("secret-2").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_label_rejected() {
+        let (store, _tmp) = setup().await;
+        store.create("ci", "secret-1").await.unwrap();
+        assert!(store.create("ci", "secret-2").await.unwrap().is_none());
+        assert!(store.verify("secret-2").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_takes_effect_immediately() {
+        let (store, _tmp) = setup().await;
+        store.create("ci", "secret-1").await.unwrap();
+        store.create("release", "secret-2").await.unwrap();
+
+        assert!(store.revoke("ci").await.unwrap());
+        assert!(store.verify("secret-1").is_none());
+        assert_eq!(store.verify("secret-2").as_deref(), Some("release"));
+        assert!(!store.revoke("ci").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_keys_persisted_without_plaintext() {
+        let (store, tmp) = setup().await;
+        store.create("ci", "secret-1").await.unwrap();
+        store.revoke("missing").await.unwrap();
+
+        let data = std::fs::read_to_string(tmp.path().join("keys.json")).unwrap();
+        assert!(!data.contains("secret-1"));
+
+        let reloaded = KeyStore::load(tmp.path().join("keys.json")).await.unwrap();
+        assert_eq!(reloaded.verify("secret-1").as_deref(), Some("ci"));
+    }
+
+    #[test]
+    fn test_static_key_prefixes() {
+        let acme = StaticKey::parse("key123:acme.").unwrap();
+        assert_eq!(acme.key, "key123");
+        assert!(acme.allows("acme.tasks"));
+        assert!(!acme.allows("adi.tasks"));
+        assert!(!acme.allows("acme"));
+
+        assert!(StaticKey::parse("key123:acme.*")
+            .unwrap()
+            .allows("acme.tasks"));
+        for wildcard in ["key123", "key123:", "key123:*"] {
+            let key = StaticKey::parse(wildcard).unwrap();
+            assert_eq!(key.key, "key123");
+            assert!(key.allows("adi.tasks"), "{}", wildcard);
+        }
+        assert_eq!(StaticKey::parse(":acme."), None);
+    }
+}
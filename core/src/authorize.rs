@@ -0,0 +1,203 @@
+use crate::auth::{KeyStore, StaticKey};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// What a request wants to do, as seen by an [`Authorizer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Publish an artifact, metadata or web UI.
+    Publish,
+    /// Remove published versions.
+    Delete,
+    /// Admin routes, and publishes that backdate `published_at`.
+    Admin,
+}
+
+/// Who a request was authorized as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// Key label, `admin`, or `anonymous` while publishing is open.
+    pub name: String,
+}
+
+impl Identity {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Why an [`Authorizer`] turned a request down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// Missing or unrecognized credentials; answered with 401.
+    Unauthenticated(String),
+    /// Valid credentials that may not perform the action; answered with 403.
+    Forbidden(String),
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Unauthenticated(msg) | AuthError::Forbidden(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Decides whether a caller may perform an action on an id.
+///
+/// The server ships [`TokenFileAuthorizer`]; embedders with their own identity system
+/// (OAuth, JWT, ...) implement this instead. `id` is empty for actions not tied to one.
+#[async_trait]
+pub trait Authorizer: Send + Sync + 'static {
+    async fn authorize(
+        &self,
+        action: Action,
+        id: &str,
+        token: Option<&str>,
+    ) -> Result<Identity, AuthError>;
+}
+
+/// The built-in authorizer: publish keys from `keys.json` and any static keys, plus the
+/// admin key.
+///
+/// Publishing and deleting stay open until the first key exists; the admin key
+/// is accepted for every action.
+pub struct TokenFileAuthorizer {
+    keys: Arc<KeyStore>,
+    admin_key: Option<String>,
+    static_keys: Vec<StaticKey>,
+}
+
+impl TokenFileAuthorizer {
+    pub fn new(keys: Arc<KeyStore>, admin_key: Option<String>) -> Self {
+        Self {
+            keys,
+            admin_key,
+            static_keys: Vec::new(),
+        }
+    }
+
+    /// Also accept these keys for publishing and deleting ids under their prefix, e.g.
+    /// from the environment. Unlike `keys.json` keys they can't be revoked at runtime.
+    pub fn with_static_keys(mut self, keys: Vec<StaticKey>) -> Self {
+        self.static_keys = keys;
+        self
+    }
+}
+
+#[async_trait]
+impl Authorizer for TokenFileAuthorizer {
+    async fn authorize(
+        &self,
+        action: Action,
+        id: &str,
+        token: Option<&str>,
+    ) -> Result<Identity, AuthError> {
+        if token.is_some() && token == self.admin_key.as_deref() {
+            return Ok(Identity::new("admin"));
+        }
+        match action {
+            Action::Admin => Err(AuthError::Unauthenticated("Invalid admin key".to_string())),
+            Action::Publish | Action::Delete
+                if !self.keys.is_enabled() && self.static_keys.is_empty() =>
+            {
+                Ok(Identity::new("anonymous"))
+            }
+            Action::Publish | Action::Delete => {
+                let token = token
+                    .ok_or_else(|| AuthError::Unauthenticated("Missing API key".to_string()))?;
+                if let Some(key) = self.static_keys.iter().find(|k| k.key == token) {
+                    if !key.allows(id) {
+                        return Err(AuthError::Forbidden(format!(
+                            "Key may only use ids starting with {:?}",
+                            key.prefix
+                        )));
+                    }
+                    return Ok(Identity::new("static"));
+                }
+                self.keys
+                    .verify(token)
+                    .map(|label| Identity::new(&label))
+                    .ok_or_else(|| AuthError::Unauthenticated("Invalid API key".to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_file_authorizer() {
+        let tmp = tempfile::tempdir().unwrap();
+        let keys = Arc::new(KeyStore::load(tmp.path().join("keys.json")).await.unwrap());
+        let authorizer = TokenFileAuthorizer::new(keys.clone(), Some("root".to_string()));
+
+        let open = authorizer
+            .authorize(Action::Publish, "adi.tasks", None)
+            .await;
+        assert_eq!(open, Ok(Identity::new("anonymous")));
+        assert!(authorizer.authorize(Action::Admin, "", None).await.is_err());
+
+        keys.create("ci", "ci-secret").await.unwrap();
+        let ci = authorizer
+            .authorize(Action::Publish, "adi.tasks", Some("ci-secret"))
+            .await;
+        assert_eq!(ci, Ok(Identity::new("ci")));
+        assert!(authorizer
+            .authorize(Action::Delete, "adi.tasks", None)
+            .await
+            .is_err());
+        assert!(authorizer
+            .authorize(Action::Admin, "", Some("ci-secret"))
+            .await
+            .is_err());
+        let admin = authorizer.authorize(Action::Admin, "", Some("root")).await;
+        assert_eq!(admin, Ok(Identity::new("admin")));
+    }
+
+    #[tokio::test]
+    async fn test_static_keys() {
+        let tmp = tempfile::tempdir().unwrap();
+        let keys = Arc::new(KeyStore::load(tmp.path().join("keys.json")).await.unwrap());
+        let static_keys = ["ci-secret", "acme-secret:acme."]
+            .into_iter()
+            .filter_map(StaticKey::parse)
+            .collect();
+        let authorizer = TokenFileAuthorizer::new(keys, None).with_static_keys(static_keys);
+        let publish = |token| authorizer.authorize(Action::Publish, "adi.tasks", token);
+
+        // Static keys alone close publishing, with no keys.json keys created
+        assert_eq!(
+            publish(Some("ci-secret")).await,
+            Ok(Identity::new("static"))
+        );
+        assert!(matches!(
+            publish(Some("wrong")).await,
+            Err(AuthError::Unauthenticated(_))
+        ));
+        assert!(matches!(
+            publish(None).await,
+            Err(AuthError::Unauthenticated(_))
+        ));
+        assert!(authorizer
+            .authorize(Action::Admin, "", Some("ci-secret"))
+            .await
+            .is_err());
+
+        // Prefixed keys only cover their own namespace
+        assert!(matches!(
+            publish(Some("acme-secret")).await,
+            Err(AuthError::Forbidden(_))
+        ));
+        let acme = authorizer
+            .authorize(Action::Delete, "acme.tasks", Some("acme-secret"))
+            .await;
+        assert_eq!(acme, Ok(Identity::new("static")));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::storage::RegistryStorage;
+use anyhow::{Context, Result};
+use lib_plugin_registry::PlatformBuild;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Everything an offline installer needs for a plugin's latest version, in one document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginBundle {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub plugin_type: String,
+    pub author: String,
+    pub version: String,
+    pub published_at: u64,
+    pub platforms: Vec<PlatformBuild>,
+    pub web_ui_url: Option<String>,
+    /// Required dependencies as `id -> requirement`.
+    pub dependencies: BTreeMap<String, String>,
+}
+
+impl RegistryStorage {
+    /// Assemble the bundle manifest for a plugin's latest version.
+    ///
+    /// URLs are prefixed with `base_url` (e.g. `https://plugins.example.com`); pass an
+    /// empty string to keep them relative.
+    pub async fn get_plugin_bundle(&self, id: &str, base_url: &str) -> Result<PluginBundle> {
+        let index = self.load_index().await?;
+        let entry = index
+            .plugins
+            .into_iter()
+            .find(|p| p.id == id)
+            .context("Plugin not found")?;
+
+        let info = self.get_plugin_info(id, &entry.latest_version).await?;
+        let dependencies = self
+            .get_plugin_dependencies(id, &entry.latest_version)
+            .await?;
+        let base_url = base_url.trim_end_matches('/');
+
+        let platforms = info
+            .platforms
+            .into_iter()
+            .map(|build| PlatformBuild {
+                download_url: format!("{}{}", base_url, build.download_url),
+                ..build
+            })
+            .collect();
+
+        Ok(PluginBundle {
+            id: entry.id,
+            name: entry.name,
+            description: entry.description,
+            plugin_type: entry.plugin_type,
+            author: entry.author,
+            version: info.version,
+            published_at: info.published_at,
+            platforms,
+            web_ui_url: info
+                .web_ui
+                .map(|web_ui| format!("{}{}", base_url, web_ui.entry_url)),
+            dependencies: dependencies
+                .into_iter()
+                .filter(|d| !d.optional)
+                .map(|d| (d.id, d.version_req))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup() -> (RegistryStorage, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        for platform in ["darwin-aarch64", "linux-x86_64"] {
+            storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "Task management",
+                    "core",
+                    "1.0.0",
+                    platform,
+                    platform.as_bytes(),
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        (storage, tmp)
+    }
+
+    #[tokio::test]
+    async fn test_bundle_absolute_urls() {
+        let (storage, _tmp) = setup().await;
+        storage
+            .publish_plugin_web_ui("adi.tasks", "1.0.0", b"export default {}")
+            .await
+            .unwrap();
+
+        let bundle = storage
+            .get_plugin_bundle("adi.tasks", "https://plugins.example.com/")
+            .await
+            .unwrap();
+        assert_eq!(bundle.version, "1.0.0");
+        assert_eq!(bundle.platforms.len(), 2);
+        assert_eq!(
+            bundle.platforms[0].download_url,
+            "https://plugins.example.com/v1/plugins/adi.tasks/1.0.0/darwin-aarch64.tar.gz"
+        );
+        assert_eq!(bundle.platforms[0].checksum.len(), 64);
+        assert_eq!(
+            bundle.web_ui_url.as_deref(),
+            Some("https://plugins.example.com/v1/plugins/adi.tasks/1.0.0/web.js")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bundle_unknown_plugin() {
+        let (storage, _tmp) = setup().await;
+        assert!(storage.get_plugin_bundle("adi.missing", "").await.is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::storage::{semver_greater, RegistryStorage};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// An entry from a client's local index snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalEntry {
+    pub id: String,
+    pub latest_version: String,
+}
+
+/// Differences between a client's snapshot and the server index, ids sorted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexComparison {
+    /// On the server but not in the snapshot.
+    pub added: Vec<String>,
+    /// The server's latest version is newer than the snapshot's.
+    pub updated: Vec<String>,
+    /// In the snapshot but no longer on the server.
+    pub removed: Vec<String>,
+}
+
+impl RegistryStorage {
+    /// Compare a client snapshot of `kind` entries (`packages` or `plugins`) with the index.
+    pub async fn compare_index(&self, kind: &str, local: &[LocalEntry]) -> Result<IndexComparison> {
+        let index = self.load_index().await?;
+        let server: HashMap<&str, &str> = match kind {
+            "packages" => index
+                .packages
+                .iter()
+                .map(|p| (p.id.as_str(), p.latest_version.as_str()))
+                .collect(),
+            "plugins" => index
+                .plugins
+                .iter()
+                .map(|p| (p.id.as_str(), p.latest_version.as_str()))
+                .collect(),
+            _ => anyhow::bail!("Unknown kind: {}", kind),
+        };
+        let local_ids: HashMap<&str, &str> = local
+            .iter()
+            .map(|e| (e.id.as_str(), e.latest_version.as_str()))
+            .collect();
+
+        let mut comparison = IndexComparison::default();
+        for (id, version) in &server {
+            match local_ids.get(id) {
+                None => comparison.added.push(id.to_string()),
+                Some(local_version) if semver_greater(version, local_version) => {
+                    comparison.updated.push(id.to_string())
+                }
+                Some(_) => {}
+            }
+        }
+        comparison.removed = local_ids
+            .keys()
+            .filter(|id| !server.contains_key(*id))
+            .map(|id| id.to_string())
+            .collect();
+
+        comparison.added.sort();
+        comparison.updated.sort();
+        comparison.removed.sort();
+        Ok(comparison)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(id: &str, version: &str) -> LocalEntry {
+        LocalEntry {
+            id: id.to_string(),
+            latest_version: version.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compare_all_categories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        for (id, version) in [
+            ("adi.tasks", "1.1.0"),
+            ("adi.notes", "1.0.0"),
+            ("adi.agent", "0.1.0"),
+        ] {
+            storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "extension",
+                    version,
+                    "linux-x86_64",
+                    b"fake binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+
+        let snapshot = [
+            local("adi.tasks", "1.0.0"),
+            local("adi.notes", "1.0.0"),
+            local("adi.legacy", "2.0.0"),
+        ];
+        let comparison = storage.compare_index("plugins", &snapshot).await.unwrap();
+        assert_eq!(
+            comparison,
+            IndexComparison {
+                added: vec!["adi.agent".to_string()],
+                updated: vec!["adi.tasks".to_string()],
+                removed: vec!["adi.legacy".to_string()],
+            }
+        );
+    }
+}
@@ -0,0 +1,647 @@
+//! Structured dependencies declared for a plugin version, and resolution of a
+//! version's full dependency closure.
+
+use crate::storage::{
+    check_id, check_version, semver_cmp, write_atomic, PluginVersionMeta, RegistryStorage,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::fs;
+
+/// A package or plugin a plugin version needs, stored in the version's
+/// `dependencies.json` since `PluginInfo` has no field for it.
+///
+/// This is the only dependency store: requirements given through plugin metadata
+/// are converted with [`Dependency::from_requirements`] and land here too.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub id: String,
+    /// A semver requirement, e.g. `^1.2`.
+    pub version_req: String,
+    #[serde(default)]
+    pub optional: bool,
+}
+
+/// A dependency whose `version_req` isn't a valid semver requirement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidVersionReq {
+    pub id: String,
+    pub version_req: String,
+}
+
+impl std::fmt::Display for InvalidVersionReq {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid version requirement {:?} for dependency {}",
+            self.version_req, self.id
+        )
+    }
+}
+
+impl std::error::Error for InvalidVersionReq {}
+
+impl Dependency {
+    /// Required dependencies from an `id -> requirement` map, as plugin metadata has.
+    pub fn from_requirements(requirements: &BTreeMap<String, String>) -> Vec<Dependency> {
+        requirements
+            .iter()
+            .map(|(id, version_req)| Dependency {
+                id: id.clone(),
+                version_req: version_req.clone(),
+                optional: false,
+            })
+            .collect()
+    }
+
+    /// Fail with [`InvalidVersionReq`] unless `version_req` parses as a `semver::VersionReq`.
+    pub fn validate(&self) -> Result<(), InvalidVersionReq> {
+        semver::VersionReq::parse(&self.version_req)
+            .map(|_| ())
+            .map_err(|_| InvalidVersionReq {
+                id: self.id.clone(),
+                version_req: self.version_req.clone(),
+            })
+    }
+}
+
+/// One entry of a resolved dependency closure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedDependency {
+    pub id: String,
+    pub version: String,
+}
+
+/// A required dependency that no published, unyanked version satisfies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsatisfiableDependency {
+    pub id: String,
+    pub version_req: String,
+}
+
+impl std::fmt::Display for UnsatisfiableDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "No published version of {} satisfies {}",
+            self.id, self.version_req
+        )
+    }
+}
+
+impl std::error::Error for UnsatisfiableDependency {}
+
+/// Dependencies that lead back to a plugin already being resolved, as the ids along
+/// the cycle with the repeated one at both ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyCycle {
+    pub path: Vec<String>,
+}
+
+impl std::fmt::Display for DependencyCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Dependency cycle: {}", self.path.join(" -> "))
+    }
+}
+
+impl std::error::Error for DependencyCycle {}
+
+/// A plugin on the resolution path and the dependencies still to visit.
+struct Frame {
+    id: String,
+    dependencies: Vec<Dependency>,
+    next: usize,
+}
+
+impl RegistryStorage {
+    /// Resolve the dependency closure of a plugin version, root first.
+    ///
+    /// Each dependency gets the highest unyanked version satisfying its requirement,
+    /// looked up among plugins and then packages; only plugins have dependencies of
+    /// their own. An id needed more than once keeps the version picked first, which
+    /// every later requirement must also accept. Optional dependencies are left out
+    /// when unsatisfiable. Fails with [`UnsatisfiableDependency`] or [`DependencyCycle`].
+    pub async fn resolve_dependencies(
+        &self,
+        id: &str,
+        version: &str,
+    ) -> Result<Vec<ResolvedDependency>> {
+        let yanked = self.load_yanked().await?;
+        let mut resolved = vec![ResolvedDependency {
+            id: id.to_string(),
+            version: version.to_string(),
+        }];
+        let mut path = vec![Frame {
+            id: id.to_string(),
+            dependencies: self.get_plugin_dependencies(id, version).await?,
+            next: 0,
+        }];
+
+        while let Some(frame) = path.last_mut() {
+            let Some(dependency) = frame.dependencies.get(frame.next).cloned() else {
+                path.pop();
+                continue;
+            };
+            frame.next += 1;
+
+            if path.iter().any(|f| f.id == dependency.id) {
+                let start = path.iter().position(|f| f.id == dependency.id).unwrap();
+                let mut cycle: Vec<_> = path[start..].iter().map(|f| f.id.clone()).collect();
+                cycle.push(dependency.id);
+                return Err(DependencyCycle { path: cycle }.into());
+            }
+
+            let req = semver::VersionReq::parse(&dependency.version_req).map_err(|_| {
+                InvalidVersionReq {
+                    id: dependency.id.clone(),
+                    version_req: dependency.version_req.clone(),
+                }
+            })?;
+            let unsatisfiable = || UnsatisfiableDependency {
+                id: dependency.id.clone(),
+                version_req: dependency.version_req.clone(),
+            };
+            let matches = |v: &str| semver::Version::parse(v).is_ok_and(|v| req.matches(&v));
+
+            if let Some(existing) = resolved.iter().find(|r| r.id == dependency.id) {
+                if matches(&existing.version) || dependency.optional {
+                    continue;
+                }
+                return Err(unsatisfiable().into());
+            }
+
+            let mut picked = None;
+            for kind in ["plugins", "packages"] {
+                let versions = self.list_versions(kind, &dependency.id).await?;
+                if versions.is_empty() {
+                    continue;
+                }
+                picked = versions
+                    .into_iter()
+                    .filter(|v| matches(v) && !yanked.contains(kind, &dependency.id, v))
+                    .max_by(|a, b| semver_cmp(a, b))
+                    .map(|v| (kind, v));
+                break;
+            }
+            let Some((kind, picked)) = picked else {
+                if dependency.optional {
+                    continue;
+                }
+                return Err(unsatisfiable().into());
+            };
+
+            let dependencies = if kind == "plugins" {
+                self.get_plugin_dependencies(&dependency.id, &picked)
+                    .await?
+            } else {
+                Vec::new()
+            };
+            resolved.push(ResolvedDependency {
+                id: dependency.id.clone(),
+                version: picked,
+            });
+            path.push(Frame {
+                id: dependency.id,
+                dependencies,
+                next: 0,
+            });
+        }
+
+        Ok(resolved)
+    }
+
+    /// The highest unyanked version of `id` satisfying `version_req`, or `None` if no
+    /// published version does. `kind` is `"plugins"` or `"packages"`.
+    ///
+    /// Fails with [`InvalidVersionReq`] if the requirement doesn't parse.
+    pub async fn latest_matching(
+        &self,
+        kind: &str,
+        id: &str,
+        version_req: &str,
+    ) -> Result<Option<String>> {
+        let req = semver::VersionReq::parse(version_req).map_err(|_| InvalidVersionReq {
+            id: id.to_string(),
+            version_req: version_req.to_string(),
+        })?;
+        let yanked = self.load_yanked().await?;
+        Ok(self
+            .list_versions(kind, id)
+            .await?
+            .into_iter()
+            .filter(|v| semver::Version::parse(v).is_ok_and(|v| req.matches(&v)))
+            .filter(|v| !yanked.contains(kind, id, v))
+            .max_by(|a, b| semver_cmp(a, b)))
+    }
+
+    /// Store a plugin version's dependencies, replacing any declared before.
+    ///
+    /// Fails with [`InvalidVersionReq`] before writing anything if a requirement
+    /// doesn't parse.
+    pub async fn set_plugin_dependencies(
+        &self,
+        id: &str,
+        version: &str,
+        dependencies: &[Dependency],
+    ) -> Result<()> {
+        check_id(id)?;
+        check_version(version)?;
+        for dependency in dependencies {
+            dependency.validate()?;
+        }
+        let version_dir = self.plugin_version_dir(id, version);
+        fs::create_dir_all(&version_dir).await?;
+        let json = serde_json::to_string_pretty(dependencies)?;
+        write_atomic(&version_dir.join("dependencies.json"), json.as_bytes()).await?;
+        self.dependents_cache().invalidate();
+        Ok(())
+    }
+
+    /// Dependencies declared for a plugin version; empty if none were.
+    ///
+    /// A version without `dependencies.json` that predates it may still list
+    /// requirements in its `meta.json` (e.g. under a read root, which
+    /// [`Self::migrate_meta_dependencies`] can't rewrite); those are used instead.
+    pub async fn get_plugin_dependencies(
+        &self,
+        id: &str,
+        version: &str,
+    ) -> Result<Vec<Dependency>> {
+        let path = self.resolve(
+            self.plugin_version_dir(id, version)
+                .join("dependencies.json"),
+        );
+        if !path.exists() {
+            let meta = self.get_plugin_meta(id, version).await?;
+            return Ok(meta
+                .map(|m| Dependency::from_requirements(&m.dependencies))
+                .unwrap_or_default());
+        }
+        let data = fs::read_to_string(&path).await?;
+        serde_json::from_str(&data).context("Failed to parse plugin dependencies")
+    }
+
+    /// Move dependencies that older releases kept in a version's `meta.json` into its
+    /// `dependencies.json`, unless that already exists. Returns how many versions
+    /// were migrated; running it again finds nothing to do.
+    pub async fn migrate_meta_dependencies(&self) -> Result<usize> {
+        let mut migrated = 0;
+        let mut plugins = fs::read_dir(self.root().join("plugins")).await?;
+        while let Some(plugin) = plugins.next_entry().await? {
+            if !plugin.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut versions = fs::read_dir(plugin.path()).await?;
+            while let Some(version) = versions.next_entry().await? {
+                let meta_path = version.path().join("meta.json");
+                if !meta_path.exists() {
+                    continue;
+                }
+                let data = fs::read_to_string(&meta_path).await?;
+                let mut meta: PluginVersionMeta = serde_json::from_str(&data)
+                    .with_context(|| format!("Failed to parse {}", meta_path.display()))?;
+                if meta.dependencies.is_empty() {
+                    continue;
+                }
+                let dependencies_path = version.path().join("dependencies.json");
+                if !dependencies_path.exists() {
+                    let dependencies = Dependency::from_requirements(&meta.dependencies);
+                    let json = serde_json::to_string_pretty(&dependencies)?;
+                    write_atomic(&dependencies_path, json.as_bytes()).await?;
+                }
+                meta.dependencies.clear();
+                let json = serde_json::to_string_pretty(&meta)?;
+                write_atomic(&meta_path, json.as_bytes()).await?;
+                migrated += 1;
+            }
+        }
+        if migrated > 0 {
+            self.dependents_cache().invalidate();
+        }
+        Ok(migrated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(id: &str, version_req: &str, optional: bool) -> Dependency {
+        Dependency {
+            id: id.to_string(),
+            version_req: version_req.to_string(),
+            optional,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_plugin_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        assert!(storage
+            .get_plugin_dependencies("adi.tasks", "1.0.0")
+            .await
+            .unwrap()
+            .is_empty());
+
+        let dependencies = vec![
+            dependency("adi.core", "^1.2", false),
+            dependency("adi.theme", ">=0.3, <0.5", true),
+        ];
+        storage
+            .set_plugin_dependencies("adi.tasks", "1.0.0", &dependencies)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .get_plugin_dependencies("adi.tasks", "1.0.0")
+                .await
+                .unwrap(),
+            dependencies
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_version_req_is_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+
+        let err = storage
+            .set_plugin_dependencies(
+                "adi.tasks",
+                "1.0.0",
+                &[
+                    dependency("adi.core", "^1.2", false),
+                    dependency("adi.theme", "not a version", false),
+                ],
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<InvalidVersionReq>(),
+            Some(&InvalidVersionReq {
+                id: "adi.theme".to_string(),
+                version_req: "not a version".to_string(),
+            })
+        );
+        // Nothing is stored when any requirement is invalid
+        assert!(storage
+            .get_plugin_dependencies("adi.tasks", "1.0.0")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_dependency_json_shape() {
+        let parsed: Vec<Dependency> =
+            serde_json::from_str(r#"[{"id": "adi.core", "version_req": "~1.4"}]"#).unwrap();
+        assert_eq!(parsed, [dependency("adi.core", "~1.4", false)]);
+    }
+
+    async fn publish(storage: &RegistryStorage, id: &str, version: &str, deps: &[Dependency]) {
+        storage
+            .publish_plugin(
+                id,
+                id,
+                "",
+                "core",
+                version,
+                "linux-x86_64",
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        storage
+            .set_plugin_dependencies(id, version, deps)
+            .await
+            .unwrap();
+    }
+
+    fn resolved(pairs: &[(&str, &str)]) -> Vec<ResolvedDependency> {
+        pairs
+            .iter()
+            .map(|(id, version)| ResolvedDependency {
+                id: id.to_string(),
+                version: version.to_string(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_resolve_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        for version in ["0.3.1", "0.3.5", "0.4.0"] {
+            storage
+                .publish_package(
+                    "adi.runtime",
+                    "Runtime",
+                    "",
+                    version,
+                    "linux-x86_64",
+                    b"fake binary",
+                    "ADI Team",
+                    vec![],
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        for version in ["1.0.0", "1.4.0", "2.0.0"] {
+            publish(
+                &storage,
+                "adi.core",
+                version,
+                &[dependency("adi.runtime", "~0.3", false)],
+            )
+            .await;
+        }
+        publish(
+            &storage,
+            "adi.tasks",
+            "1.0.0",
+            &[
+                dependency("adi.core", "^1", false),
+                dependency("adi.runtime", ">=0.3", false),
+                dependency("adi.theme", "^1", true),
+            ],
+        )
+        .await;
+
+        // The highest match wins; a repeated id keeps its first pick, and the
+        // unpublished optional dependency is left out
+        assert_eq!(
+            storage
+                .resolve_dependencies("adi.tasks", "1.0.0")
+                .await
+                .unwrap(),
+            resolved(&[
+                ("adi.tasks", "1.0.0"),
+                ("adi.core", "1.4.0"),
+                ("adi.runtime", "0.3.5"),
+            ])
+        );
+
+        // Yanked versions are never picked
+        storage
+            .set_yanked("plugins", "adi.core", "1.4.0", true)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .resolve_dependencies("adi.tasks", "1.0.0")
+                .await
+                .unwrap()[1]
+                .version,
+            "1.0.0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_latest_matching() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        for version in ["1.1.0", "1.2.0", "1.2.5", "1.3.0", "2.0.0"] {
+            publish(&storage, "adi.core", version, &[]).await;
+        }
+        let latest = |req: &'static str| storage.latest_matching("plugins", "adi.core", req);
+
+        assert_eq!(latest("^1.2").await.unwrap().as_deref(), Some("1.3.0"));
+        assert_eq!(latest("~1.2").await.unwrap().as_deref(), Some("1.2.5"));
+        assert_eq!(latest("=1.1.0").await.unwrap().as_deref(), Some("1.1.0"));
+        assert_eq!(latest("^3").await.unwrap(), None);
+        assert!(latest("not a req")
+            .await
+            .unwrap_err()
+            .downcast_ref::<InvalidVersionReq>()
+            .is_some());
+
+        storage
+            .set_yanked("plugins", "adi.core", "1.3.0", true)
+            .await
+            .unwrap();
+        assert_eq!(latest("^1.2").await.unwrap().as_deref(), Some("1.2.5"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_unsatisfiable_dependency() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        publish(&storage, "adi.core", "1.0.0", &[]).await;
+        publish(
+            &storage,
+            "adi.tasks",
+            "1.0.0",
+            &[dependency("adi.core", "^2", false)],
+        )
+        .await;
+
+        let err = storage
+            .resolve_dependencies("adi.tasks", "1.0.0")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<UnsatisfiableDependency>(),
+            Some(&UnsatisfiableDependency {
+                id: "adi.core".to_string(),
+                version_req: "^2".to_string(),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_detects_cycles() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        publish(
+            &storage,
+            "adi.a",
+            "1.0.0",
+            &[dependency("adi.b", "^1", false)],
+        )
+        .await;
+        publish(
+            &storage,
+            "adi.b",
+            "1.0.0",
+            &[dependency("adi.c", "^1", false)],
+        )
+        .await;
+        publish(
+            &storage,
+            "adi.c",
+            "1.0.0",
+            &[dependency("adi.b", "^1", false)],
+        )
+        .await;
+
+        let err = storage
+            .resolve_dependencies("adi.a", "1.0.0")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DependencyCycle>().unwrap().path,
+            ["adi.b", "adi.c", "adi.b"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrate_meta_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        publish(&storage, "adi.core", "1.0.0", &[]).await;
+        publish(&storage, "adi.tasks", "1.0.0", &[]).await;
+        // As written before dependencies.json existed
+        let version_dir = tmp.path().join("plugins/adi.tasks/1.0.0");
+        std::fs::remove_file(version_dir.join("dependencies.json")).unwrap();
+        let legacy = serde_json::json!({
+            "name": "Tasks",
+            "description": "",
+            "plugin_type": "core",
+            "author": "ADI Team",
+            "dependencies": {"adi.core": "^1.0"},
+        });
+        std::fs::write(version_dir.join("meta.json"), legacy.to_string()).unwrap();
+
+        // Read straight from meta.json until migrated
+        let expected = vec![dependency("adi.core", "^1.0", false)];
+        assert_eq!(
+            storage
+                .get_plugin_dependencies("adi.tasks", "1.0.0")
+                .await
+                .unwrap(),
+            expected
+        );
+
+        assert_eq!(storage.migrate_meta_dependencies().await.unwrap(), 1);
+        assert_eq!(storage.migrate_meta_dependencies().await.unwrap(), 0);
+        assert_eq!(
+            storage
+                .get_plugin_dependencies("adi.tasks", "1.0.0")
+                .await
+                .unwrap(),
+            expected
+        );
+        let meta = storage
+            .get_plugin_meta("adi.tasks", "1.0.0")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(meta.dependencies.is_empty());
+        assert_eq!(meta.name, "Tasks");
+        let dependents = storage.reverse_dependencies("adi.core").await.unwrap();
+        assert_eq!(dependents.len(), 1);
+        assert_eq!(dependents[0].requirement, "^1.0");
+    }
+}
@@ -0,0 +1,222 @@
+use crate::dependency::Dependency;
+use crate::storage::{RegistryStorage, UnsatisfiedDependencies};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// A plugin whose latest version declares a dependency on another plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependent {
+    pub id: String,
+    pub version: String,
+    /// The version requirement declared for the dependency, e.g. `^1.0`.
+    pub requirement: String,
+}
+
+/// Reverse dependency map, built lazily and dropped whenever a publish could change it.
+#[derive(Default)]
+pub(crate) struct DependentsCache {
+    map: Mutex<Option<BTreeMap<String, Vec<Dependent>>>>,
+}
+
+impl DependentsCache {
+    pub(crate) fn invalidate(&self) {
+        *self.map.lock().unwrap() = None;
+    }
+}
+
+impl RegistryStorage {
+    /// List plugins whose latest version depends on `id`, sorted by dependent id.
+    pub async fn reverse_dependencies(&self, id: &str) -> Result<Vec<Dependent>> {
+        if let Some(map) = self.dependents_cache().map.lock().unwrap().as_ref() {
+            return Ok(map.get(id).cloned().unwrap_or_default());
+        }
+        Ok(self
+            .build_dependents()
+            .await?
+            .remove(id)
+            .unwrap_or_default())
+    }
+
+    /// Rebuild the reverse dependency map and cache a copy.
+    pub(crate) async fn build_dependents(&self) -> Result<BTreeMap<String, Vec<Dependent>>> {
+        let index = self.load_index().await?;
+        let mut map: BTreeMap<String, Vec<Dependent>> = BTreeMap::new();
+        for plugin in &index.plugins {
+            let dependencies = self
+                .get_plugin_dependencies(&plugin.id, &plugin.latest_version)
+                .await?;
+            for dependency in dependencies {
+                map.entry(dependency.id).or_default().push(Dependent {
+                    id: plugin.id.clone(),
+                    version: plugin.latest_version.clone(),
+                    requirement: dependency.version_req,
+                });
+            }
+        }
+        for dependents in map.values_mut() {
+            dependents.sort_by(|a, b| a.id.cmp(&b.id));
+        }
+
+        *self.dependents_cache().map.lock().unwrap() = Some(map.clone());
+        Ok(map)
+    }
+
+    /// Fail with [`UnsatisfiedDependencies`] when dependency validation is enabled and
+    /// a required dependency has no published plugin or package version matching its
+    /// requirement.
+    pub async fn check_dependencies(&self, dependencies: &[Dependency]) -> Result<()> {
+        if !self.options().validate_dependencies {
+            return Ok(());
+        }
+
+        let mut unsatisfied = Vec::new();
+        for dependency in dependencies.iter().filter(|d| !d.optional) {
+            let satisfied = match semver::VersionReq::parse(&dependency.version_req) {
+                Ok(req) => {
+                    let mut versions = self.list_versions("plugins", &dependency.id).await?;
+                    versions.extend(self.list_versions("packages", &dependency.id).await?);
+                    versions
+                        .iter()
+                        .filter_map(|v| semver::Version::parse(v).ok())
+                        .any(|v| req.matches(&v))
+                }
+                Err(_) => false,
+            };
+            if !satisfied {
+                unsatisfied.push(format!("{} {}", dependency.id, dependency.version_req));
+            }
+        }
+
+        if unsatisfied.is_empty() {
+            Ok(())
+        } else {
+            Err(UnsatisfiedDependencies {
+                dependencies: unsatisfied,
+            }
+            .into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{PluginVersionMeta, StorageOptions};
+
+    fn meta(name: &str, dependencies: &[(&str, &str)]) -> PluginVersionMeta {
+        PluginVersionMeta {
+            name: name.to_string(),
+            plugin_type: "core".to_string(),
+            author: "ADI Team".to_string(),
+            dependencies: dependencies
+                .iter()
+                .map(|(id, req)| (id.to_string(), req.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reverse_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        for id in ["adi.a", "adi.b"] {
+            storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        storage
+            .publish_plugin_meta("adi.b", "1.0.0", &meta("B", &[("adi.a", "^1.0")]))
+            .await
+            .unwrap();
+
+        let dependents = storage.reverse_dependencies("adi.a").await.unwrap();
+        assert_eq!(
+            dependents,
+            vec![Dependent {
+                id: "adi.b".to_string(),
+                version: "1.0.0".to_string(),
+                requirement: "^1.0".to_string(),
+            }]
+        );
+        assert!(storage
+            .reverse_dependencies("adi.b")
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Publishing new metadata invalidates the cached map
+        storage
+            .publish_plugin_meta("adi.b", "1.0.0", &meta("B", &[]))
+            .await
+            .unwrap();
+        assert!(storage
+            .reverse_dependencies("adi.a")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validate_dependencies_on_publish() {
+        let tmp = tempfile::tempdir().unwrap();
+        let options = StorageOptions {
+            validate_dependencies: true,
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        storage.init().await.unwrap();
+        storage
+            .publish_plugin(
+                "adi.a",
+                "A",
+                "",
+                "core",
+                "1.2.0",
+                "linux-x86_64",
+                b"bin",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let err = storage
+            .publish_plugin_meta(
+                "adi.b",
+                "1.0.0",
+                &meta("B", &[("adi.a", "^2.0"), ("adi.missing", "*")]),
+            )
+            .await
+            .unwrap_err();
+        let unsatisfied = err.downcast_ref::<UnsatisfiedDependencies>().unwrap();
+        assert_eq!(
+            unsatisfied.dependencies,
+            vec!["adi.a ^2.0".to_string(), "adi.missing *".to_string()]
+        );
+        assert!(storage
+            .get_plugin_meta("adi.b", "1.0.0")
+            .await
+            .unwrap()
+            .is_none());
+
+        storage
+            .publish_plugin_meta("adi.b", "1.0.0", &meta("B", &[("adi.a", "^1.0")]))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+use crate::storage::RegistryStorage;
+use anyhow::Result;
+use fs2::FileExt;
+use lib_plugin_registry::RegistryIndex;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Per-id counter file, `{kind}/{id}/downloads.json`, holding the total as a bare JSON number.
+const COUNTER_FILE: &str = "downloads.json";
+
+fn read_count(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Add one to the counter at `path`, starting from `seed` if it is new or empty.
+///
+/// The file is locked while it is rewritten in place. Totals only grow, so the new
+/// number always covers the old one and a reader never sees a shorter, torn value.
+fn bump_counter(path: &Path, seed: u64) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)?;
+    file.lock_exclusive()?;
+    let mut current = String::new();
+    file.read_to_string(&mut current)?;
+    let count = current.trim().parse::<u64>().unwrap_or(seed) + 1;
+    let count = count.to_string();
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(count.as_bytes())?;
+    file.set_len(count.len() as u64)?;
+    file.sync_data()?;
+    FileExt::unlock(&file)?;
+    Ok(())
+}
+
+impl RegistryStorage {
+    /// Increment download counter.
+    ///
+    /// Counts live in a small file per id rather than in `index.json`, so a download
+    /// never rewrites the index or waits on [`Self::lock_index`]. The first download of
+    /// an id starts from the count already recorded in the index.
+    ///
+    /// A no-op when download counting is disabled in [`StorageOptions`](crate::StorageOptions),
+    /// or for ids that were never published.
+    pub async fn increment_downloads(&self, kind: &str, id: &str) -> Result<()> {
+        if !self.options().count_downloads {
+            return Ok(());
+        }
+        let path = self.root().join(kind).join(id).join(COUNTER_FILE);
+
+        let seed = if path.exists() {
+            0
+        } else {
+            let index = self.load_index().await?;
+            let downloads = match kind {
+                "packages" => index
+                    .packages
+                    .iter()
+                    .find(|p| p.id == id)
+                    .map(|p| p.downloads),
+                "plugins" => index
+                    .plugins
+                    .iter()
+                    .find(|p| p.id == id)
+                    .map(|p| p.downloads),
+                _ => None,
+            };
+            let Some(downloads) = downloads else {
+                return Ok(());
+            };
+            fs::create_dir_all(path.parent().unwrap_or(self.root())).await?;
+            downloads
+        };
+
+        tokio::task::spawn_blocking(move || bump_counter(&path, seed)).await??;
+        self.record_change();
+        Ok(())
+    }
+
+    /// Overwrite each entry's `downloads` with its counter file, where one exists.
+    pub(crate) async fn merge_download_counts(&self, index: &mut RegistryIndex) -> Result<()> {
+        let roots: Vec<PathBuf> = std::iter::once(self.root())
+            .chain(self.options().read_root.as_deref())
+            .map(Path::to_path_buf)
+            .collect();
+        let packages: Vec<String> = index.packages.iter().map(|p| p.id.clone()).collect();
+        let plugins: Vec<String> = index.plugins.iter().map(|p| p.id.clone()).collect();
+        // One blocking task for the whole index rather than one per file
+        let (packages, plugins) = tokio::task::spawn_blocking(move || {
+            let read_all = |kind: &str, ids: Vec<String>| {
+                ids.iter()
+                    .map(|id| {
+                        roots.iter().find_map(|root| {
+                            read_count(&root.join(kind).join(id).join(COUNTER_FILE))
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            };
+            (read_all("packages", packages), read_all("plugins", plugins))
+        })
+        .await?;
+
+        for (entry, count) in index.packages.iter_mut().zip(packages) {
+            entry.downloads = count.unwrap_or(entry.downloads);
+        }
+        for (entry, count) in index.plugins.iter_mut().zip(plugins) {
+            entry.downloads = count.unwrap_or(entry.downloads);
+        }
+        Ok(())
+    }
+}
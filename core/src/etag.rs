@@ -0,0 +1,70 @@
+use sha2::{Digest, Sha256};
+use std::time::UNIX_EPOCH;
+
+/// Weak ETag derived from file metadata only: `W/"<size>-<mtime>"`.
+///
+/// Cheap enough for every download of a large artifact, since the file body is never read.
+pub fn weak_etag(metadata: &std::fs::Metadata) -> String {
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("W/\"{}-{}\"", metadata.len(), mtime)
+}
+
+/// Strong ETag for a known content checksum.
+pub fn strong_etag(checksum: &str) -> String {
+    format!("\"{}\"", checksum)
+}
+
+/// Lowercase hex SHA-256 of in-memory bytes.
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Strong ETag over in-memory bytes, for small documents served verbatim.
+pub fn content_etag(data: &[u8]) -> String {
+    strong_etag(&sha256_hex(data))
+}
+
+/// Weak ETag over a serialized document, for responses built per request rather than
+/// served from a stored file.
+pub fn weak_content_etag(data: &[u8]) -> String {
+    format!("W/{}", content_etag(data))
+}
+
+/// Check an `If-None-Match` header value against an ETag using weak comparison.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weak_etag_from_metadata() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("linux-x86_64.tar.gz");
+        std::fs::write(&path, b"fake binary").unwrap();
+
+        let etag = weak_etag(&std::fs::metadata(&path).unwrap());
+        assert!(etag.starts_with("W/\"11-"));
+        assert_eq!(etag, weak_etag(&std::fs::metadata(&path).unwrap()));
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        assert!(etag_matches("W/\"11-5\"", "W/\"11-5\""));
+        assert!(etag_matches("\"abc\", W/\"11-5\"", "W/\"11-5\""));
+        assert!(etag_matches("*", "W/\"11-5\""));
+        assert!(etag_matches("\"abc\"", &strong_etag("abc")));
+        assert!(!etag_matches("W/\"11-6\"", "W/\"11-5\""));
+    }
+}
@@ -0,0 +1,88 @@
+use lib_plugin_registry::PluginEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Plugins that belong to the same package.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageGroup {
+    pub package_id: String,
+    pub plugins: Vec<PluginEntry>,
+}
+
+/// Plugins bucketed by their owning package.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupedPlugins {
+    /// One group per package, sorted by package id.
+    pub groups: Vec<PackageGroup>,
+    /// Plugins without a `package_id`.
+    pub orphans: Vec<PluginEntry>,
+}
+
+/// Group plugins under their `package_id`, keeping their relative order within each group.
+pub fn group_by_package(plugins: Vec<PluginEntry>) -> GroupedPlugins {
+    let mut groups: BTreeMap<String, Vec<PluginEntry>> = BTreeMap::new();
+    let mut orphans = Vec::new();
+    for plugin in plugins {
+        match plugin.package_id.clone() {
+            Some(package_id) => groups.entry(package_id).or_default().push(plugin),
+            None => orphans.push(plugin),
+        }
+    }
+    GroupedPlugins {
+        groups: groups
+            .into_iter()
+            .map(|(package_id, plugins)| PackageGroup {
+                package_id,
+                plugins,
+            })
+            .collect(),
+        orphans,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(id: &str, package_id: Option<&str>) -> PluginEntry {
+        PluginEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            plugin_type: "core".to_string(),
+            package_id: package_id.map(str::to_string),
+            latest_version: "1.0.0".to_string(),
+            downloads: 0,
+            author: "ADI Team".to_string(),
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_plugins_sharing_a_package_form_one_group() {
+        let grouped = group_by_package(vec![
+            plugin("adi.tasks", Some("adi.suite")),
+            plugin("adi.lint", None),
+            plugin("adi.agent", Some("adi.suite")),
+            plugin("adi.audio", Some("adi.media")),
+        ]);
+
+        let groups: Vec<(&str, Vec<&str>)> = grouped
+            .groups
+            .iter()
+            .map(|g| {
+                let ids = g.plugins.iter().map(|p| p.id.as_str()).collect();
+                (g.package_id.as_str(), ids)
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("adi.media", vec!["adi.audio"]),
+                ("adi.suite", vec!["adi.tasks", "adi.agent"]),
+            ]
+        );
+        assert_eq!(grouped.orphans.len(), 1);
+        assert_eq!(grouped.orphans[0].id, "adi.lint");
+    }
+}
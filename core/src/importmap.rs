@@ -0,0 +1,73 @@
+use crate::storage::RegistryStorage;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// An ES module import map (`<script type="importmap">`) from plugin id to `web.js` URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportMap {
+    pub imports: BTreeMap<String, String>,
+}
+
+impl RegistryStorage {
+    /// Build an import map covering every plugin whose latest version has a web UI.
+    ///
+    /// URLs are prefixed with `base_url`, as for [`Self::get_plugin_bundle`].
+    pub async fn get_import_map(&self, base_url: &str) -> Result<ImportMap> {
+        let index = self.load_index().await?;
+        let base_url = base_url.trim_end_matches('/');
+        let imports = index
+            .plugins
+            .into_iter()
+            .filter_map(|entry| {
+                let web_ui = self.web_ui_meta(&entry.id, &entry.latest_version)?;
+                Some((entry.id, format!("{}{}", base_url, web_ui.entry_url)))
+            })
+            .collect();
+        Ok(ImportMap { imports })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_import_map_lists_web_uis() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        for id in ["adi.tasks", "adi.cli"] {
+            storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        storage
+            .publish_plugin_web_ui("adi.tasks", "1.0.0", b"export default {}")
+            .await
+            .unwrap();
+
+        let map = storage
+            .get_import_map("https://plugins.example.com/")
+            .await
+            .unwrap();
+        assert_eq!(
+            map.imports,
+            BTreeMap::from([(
+                "adi.tasks".to_string(),
+                "https://plugins.example.com/v1/plugins/adi.tasks/1.0.0/web.js".to_string()
+            )])
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Interned copy of the registry index, for keeping it resident in memory.
+
+use lib_plugin_registry::RegistryIndex;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Hands out one shared allocation per distinct string.
+#[derive(Default)]
+struct Interner {
+    pool: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(existing) = self.pool.get(s) {
+            return existing.clone();
+        }
+        let interned: Arc<str> = Arc::from(s);
+        self.pool.insert(interned.clone());
+        interned
+    }
+}
+
+/// Author and tags of one index entry.
+struct SharedFields {
+    author: Arc<str>,
+    tags: Vec<Arc<str>>,
+}
+
+impl SharedFields {
+    fn take(interner: &mut Interner, author: &mut String, tags: &mut Vec<String>) -> Self {
+        let fields = Self {
+            author: interner.intern(author),
+            tags: tags.iter().map(|tag| interner.intern(tag)).collect(),
+        };
+        *author = String::new();
+        *tags = Vec::new();
+        fields
+    }
+}
+
+/// A [`RegistryIndex`] whose authors and tags are split out and interned, so the
+/// thousands of entries sharing a handful of tags hold one copy of each.
+///
+/// `index` keeps everything else, with empty authors and tags; `packages[i]` and
+/// `plugins[i]` hold the fields of the entry at the same position.
+pub(crate) struct InternedIndex {
+    index: RegistryIndex,
+    packages: Vec<SharedFields>,
+    plugins: Vec<SharedFields>,
+}
+
+impl InternedIndex {
+    pub(crate) fn new(mut index: RegistryIndex) -> Self {
+        let mut interner = Interner::default();
+        let packages = index
+            .packages
+            .iter_mut()
+            .map(|p| SharedFields::take(&mut interner, &mut p.author, &mut p.tags))
+            .collect();
+        let plugins = index
+            .plugins
+            .iter_mut()
+            .map(|p| SharedFields::take(&mut interner, &mut p.author, &mut p.tags))
+            .collect();
+        Self {
+            index,
+            packages,
+            plugins,
+        }
+    }
+
+    /// Rebuild a plain index, identical to the one interned.
+    pub(crate) fn to_index(&self) -> RegistryIndex {
+        let mut index = self.index.clone();
+        for (entry, fields) in index.packages.iter_mut().zip(&self.packages) {
+            entry.author = fields.author.to_string();
+            entry.tags = fields.tags.iter().map(|t| t.to_string()).collect();
+        }
+        for (entry, fields) in index.plugins.iter_mut().zip(&self.plugins) {
+            entry.author = fields.author.to_string();
+            entry.tags = fields.tags.iter().map(|t| t.to_string()).collect();
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_plugin_registry::PluginEntry;
+
+    fn plugin(id: &str, tags: &[&str]) -> PluginEntry {
+        PluginEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            plugin_type: "core".to_string(),
+            package_id: None,
+            latest_version: "1.0.0".to_string(),
+            downloads: 0,
+            author: "ADI Team".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_equal_tags_share_storage() {
+        let index = RegistryIndex {
+            plugins: vec![
+                plugin("adi.tasks", &["productivity", "cli"]),
+                plugin("adi.notes", &["productivity"]),
+            ],
+            ..Default::default()
+        };
+        let before = serde_json::to_string(&index).unwrap();
+        let interned = InternedIndex::new(index);
+
+        let (tasks, notes) = (&interned.plugins[0], &interned.plugins[1]);
+        assert!(Arc::ptr_eq(&tasks.tags[0], &notes.tags[0]));
+        assert!(Arc::ptr_eq(&tasks.author, &notes.author));
+        assert_eq!(serde_json::to_string(&interned.to_index()).unwrap(), before);
+    }
+}
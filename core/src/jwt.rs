@@ -0,0 +1,462 @@
+//! JSON Web Tokens as publish credentials, signed with a shared secret (HS256) or an
+//! RSA key (RS256) given as a PEM file or fetched from a JWKS URL.
+
+use crate::authorize::{Action, AuthError, Authorizer, Identity};
+use async_trait::async_trait;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Clock skew tolerated on `exp` and `nbf`, in seconds.
+const LEEWAY_SECS: u64 = 60;
+
+/// How long fetched JWKS keys are used before being fetched again.
+const JWKS_TTL: Duration = Duration::from_secs(300);
+
+/// Minimum time between JWKS fetches, so tokens with unknown key ids can't make the
+/// registry hammer the key endpoint.
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(30);
+
+/// Give up on a JWKS endpoint after this long.
+const JWKS_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: Option<String>,
+    /// OAuth-style space-separated scopes.
+    #[serde(default)]
+    scope: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+}
+
+impl Claims {
+    /// Whether a scope grants `action` on `id`.
+    ///
+    /// `admin` grants everything; `publish` and `delete` grant their action on any id, and
+    /// `publish:<prefix>` / `delete:<prefix>` only on ids starting with the prefix
+    /// (a trailing `*` is ignored).
+    fn allows(&self, action: Action, id: &str) -> bool {
+        let verb = match action {
+            Action::Publish => "publish",
+            Action::Delete => "delete",
+            Action::Admin => "admin",
+        };
+        let scopes = self
+            .scope
+            .split_whitespace()
+            .chain(self.scopes.iter().map(String::as_str));
+        scopes.into_iter().any(|scope| {
+            if scope == "admin" || scope == verb {
+                return true;
+            }
+            match scope.strip_prefix(verb).and_then(|s| s.strip_prefix(':')) {
+                Some(prefix) if action != Action::Admin => {
+                    id.starts_with(prefix.trim_end_matches('*'))
+                }
+                _ => false,
+            }
+        })
+    }
+}
+
+fn unauthenticated(msg: &str) -> AuthError {
+    AuthError::Unauthenticated(msg.to_string())
+}
+
+fn token_error(e: jsonwebtoken::errors::Error) -> AuthError {
+    unauthenticated(match e.kind() {
+        ErrorKind::ExpiredSignature => "Token has expired",
+        ErrorKind::ImmatureSignature => "Token is not valid yet",
+        ErrorKind::InvalidAudience => "Token audience does not match",
+        ErrorKind::MissingRequiredClaim(_) => "Token has no expiry",
+        ErrorKind::InvalidSignature => "Invalid token signature",
+        ErrorKind::InvalidAlgorithm => "Unsupported token algorithm",
+        _ => "Malformed token",
+    })
+}
+
+/// RS256 keys published at a JWKS URL, fetched on first use and refreshed when stale
+/// or when a token names a key id that isn't known yet.
+struct Jwks {
+    url: String,
+    client: reqwest::Client,
+    /// The last fetched set and when it was fetched. Never held across a fetch.
+    cached: RwLock<Option<(Instant, JwkSet)>>,
+    /// Held for the duration of a fetch, so concurrent requests share one.
+    refresh: Mutex<()>,
+}
+
+impl Jwks {
+    /// The key for `kid`, or the only key when the token names none.
+    async fn key(&self, kid: Option<&str>) -> Result<DecodingKey, AuthError> {
+        let (needs_refresh, known) = self.needs_refresh(kid);
+        if needs_refresh {
+            // A request whose key is cached doesn't wait on another's fetch
+            let refresh = if known {
+                self.refresh.try_lock().ok()
+            } else {
+                Some(self.refresh.lock().await)
+            };
+            // Someone else may have refreshed while this one waited
+            if refresh.is_some() && self.needs_refresh(kid).0 {
+                match self.fetch().await {
+                    Ok(set) => {
+                        *self.cached.write().unwrap() = Some((Instant::now(), set));
+                    }
+                    Err(e) => tracing::warn!(url = %self.url, "Failed to fetch JWKS: {:#}", e),
+                }
+            }
+        }
+
+        let cached = self.cached.read().unwrap();
+        let Some((_, set)) = cached.as_ref() else {
+            return Err(unauthenticated("Token signing keys are unavailable"));
+        };
+        let jwk = find_jwk(set, kid).ok_or_else(|| unauthenticated("Unknown token key id"))?;
+        DecodingKey::from_jwk(jwk).map_err(token_error)
+    }
+
+    /// Whether the set should be fetched before looking up `kid`, and whether the
+    /// cached set already has it.
+    fn needs_refresh(&self, kid: Option<&str>) -> (bool, bool) {
+        let cached = self.cached.read().unwrap();
+        let fetched_at = cached.as_ref().map(|(at, _)| at.elapsed());
+        let known = cached
+            .as_ref()
+            .is_some_and(|(_, set)| find_jwk(set, kid).is_some());
+        let stale = fetched_at.is_none_or(|age| age > JWKS_TTL);
+        let may_refresh = fetched_at.is_none_or(|age| age > JWKS_MIN_REFRESH);
+        ((stale || !known) && may_refresh, known)
+    }
+
+    async fn fetch(&self) -> anyhow::Result<JwkSet> {
+        Ok(self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+fn find_jwk<'a>(set: &'a JwkSet, kid: Option<&str>) -> Option<&'a jsonwebtoken::jwk::Jwk> {
+    match kid {
+        Some(kid) => set.find(kid),
+        None if set.keys.len() == 1 => set.keys.first(),
+        None => None,
+    }
+}
+
+enum Keys {
+    Static(DecodingKey),
+    Jwks(Jwks),
+}
+
+/// Authorizes requests carrying a signed JWT.
+///
+/// The token must not be expired, must match the configured audience if one is set,
+/// and must carry a scope granting the action (see the scope rules on `publish`,
+/// `delete` and `admin`). Only the configured algorithm is accepted, whatever the
+/// token's header says. Tokens that aren't JWTs at all go to the fallback authorizer,
+/// so the admin key and API keys keep working alongside.
+pub struct JwtAuthorizer {
+    algorithm: Algorithm,
+    keys: Keys,
+    audience: Option<String>,
+    fallback: Option<Arc<dyn Authorizer>>,
+}
+
+impl JwtAuthorizer {
+    fn new(algorithm: Algorithm, keys: Keys) -> Self {
+        Self {
+            algorithm,
+            keys,
+            audience: None,
+            fallback: None,
+        }
+    }
+
+    /// Accept tokens signed with a shared HMAC secret.
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self::new(
+            Algorithm::HS256,
+            Keys::Static(DecodingKey::from_secret(secret)),
+        )
+    }
+
+    /// Accept RS256 tokens, checked against a PEM-encoded RSA public key.
+    pub fn rs256_pem(pem: &[u8]) -> anyhow::Result<Self> {
+        let key = DecodingKey::from_rsa_pem(pem)
+            .map_err(|e| anyhow::anyhow!("Invalid RSA public key: {}", e))?;
+        Ok(Self::new(Algorithm::RS256, Keys::Static(key)))
+    }
+
+    /// Accept RS256 tokens signed by any key published at a JWKS URL, picked by the
+    /// token's `kid`.
+    pub fn jwks_url(url: &str) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder().timeout(JWKS_TIMEOUT).build()?;
+        Ok(Self::new(
+            Algorithm::RS256,
+            Keys::Jwks(Jwks {
+                url: url.to_string(),
+                client,
+                cached: RwLock::new(None),
+                refresh: Mutex::new(()),
+            }),
+        ))
+    }
+
+    /// Require tokens whose `aud` claim contains `audience`.
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.audience = Some(audience.to_string());
+        self
+    }
+
+    /// Authorizer for tokens that aren't JWTs.
+    pub fn with_fallback(mut self, fallback: Arc<dyn Authorizer>) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Check the signature and time and audience claims of a compact JWT.
+    async fn verify(&self, token: &str) -> Result<Claims, AuthError> {
+        let header = jsonwebtoken::decode_header(token).map_err(token_error)?;
+        if header.alg != self.algorithm {
+            return Err(unauthenticated("Unsupported token algorithm"));
+        }
+        let jwks_key;
+        let key = match &self.keys {
+            Keys::Static(key) => key,
+            Keys::Jwks(jwks) => {
+                jwks_key = jwks.key(header.kid.as_deref()).await?;
+                &jwks_key
+            }
+        };
+
+        // `exp` is required by default
+        let mut validation = Validation::new(self.algorithm);
+        validation.leeway = LEEWAY_SECS;
+        validation.validate_nbf = true;
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        jsonwebtoken::decode::<Claims>(token, key, &validation)
+            .map(|data| data.claims)
+            .map_err(token_error)
+    }
+}
+
+#[async_trait]
+impl Authorizer for JwtAuthorizer {
+    async fn authorize(
+        &self,
+        action: Action,
+        id: &str,
+        token: Option<&str>,
+    ) -> Result<Identity, AuthError> {
+        let is_jwt = token.is_some_and(|t| t.matches('.').count() == 2);
+        if !is_jwt {
+            return match &self.fallback {
+                Some(fallback) => fallback.authorize(action, id, token).await,
+                None => Err(unauthenticated("Missing bearer token")),
+            };
+        }
+
+        let claims = self.verify(token.unwrap_or_default()).await?;
+        if !claims.allows(action, id) {
+            return Err(AuthError::Forbidden(format!(
+                "Token does not grant {:?} on {}",
+                action, id
+            )));
+        }
+        Ok(Identity::new(claims.sub.as_deref().unwrap_or("jwt")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::now_unix;
+    use jsonwebtoken::{EncodingKey, Header};
+
+    const PRIVATE_KEY: &[u8] = include_bytes!("../testdata/jwt_rs256_private.pem");
+    const PUBLIC_KEY: &[u8] = include_bytes!("../testdata/jwt_rs256_public.pem");
+    const JWKS: &str = include_str!("../testdata/jwks.json");
+
+    fn sign(secret: &[u8], claims: serde_json::Value) -> String {
+        let key = EncodingKey::from_secret(secret);
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &key).unwrap()
+    }
+
+    fn sign_rs256(kid: Option<&str>, claims: serde_json::Value) -> String {
+        let header = Header {
+            kid: kid.map(str::to_string),
+            ..Header::new(Algorithm::RS256)
+        };
+        let key = EncodingKey::from_rsa_pem(PRIVATE_KEY).unwrap();
+        jsonwebtoken::encode(&header, &claims, &key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_jwt_claims_checked() {
+        let authorizer = &JwtAuthorizer::hs256(b"s3cret").with_audience("registry");
+        let now = now_unix();
+        let authorize = |token: String, id: &'static str| async move {
+            authorizer
+                .authorize(Action::Publish, id, Some(&token))
+                .await
+        };
+
+        let valid = sign(
+            b"s3cret",
+            serde_json::json!({
+                "sub": "ci", "aud": ["registry"], "exp": now + 300, "scope": "publish:adi.*"
+            }),
+        );
+        assert_eq!(
+            authorize(valid.clone(), "adi.tasks").await,
+            Ok(Identity::new("ci"))
+        );
+        assert!(matches!(
+            authorize(valid, "acme.tasks").await,
+            Err(AuthError::Forbidden(_))
+        ));
+
+        let expired = sign(
+            b"s3cret",
+            serde_json::json!({ "aud": "registry", "exp": now - 3600, "scope": "publish" }),
+        );
+        let wrong_audience = sign(
+            b"s3cret",
+            serde_json::json!({ "aud": "billing", "exp": now + 300, "scope": "publish" }),
+        );
+        let forged = sign(
+            b"guess",
+            serde_json::json!({ "aud": "registry", "exp": now + 300, "scope": "admin" }),
+        );
+        let no_expiry = sign(
+            b"s3cret",
+            serde_json::json!({ "aud": "registry", "scope": "publish" }),
+        );
+        // An RS256 token can't be checked against the HS256 secret
+        let other_algorithm = sign_rs256(
+            None,
+            serde_json::json!({ "aud": "registry", "exp": now + 300, "scope": "publish" }),
+        );
+        for token in [expired, wrong_audience, forged, no_expiry, other_algorithm] {
+            assert!(matches!(
+                authorize(token, "adi.tasks").await,
+                Err(AuthError::Unauthenticated(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rs256_public_key() {
+        let authorizer = JwtAuthorizer::rs256_pem(PUBLIC_KEY).unwrap();
+        let now = now_unix();
+        let token = sign_rs256(
+            None,
+            serde_json::json!({ "sub": "ci", "exp": now + 300, "scope": "publish" }),
+        );
+        assert_eq!(
+            authorizer
+                .authorize(Action::Publish, "adi.tasks", Some(&token))
+                .await,
+            Ok(Identity::new("ci"))
+        );
+
+        // An HS256 token "signed" with the public key must not pass
+        let confused = sign(
+            PUBLIC_KEY,
+            serde_json::json!({ "exp": now + 300, "scope": "admin" }),
+        );
+        assert!(matches!(
+            authorizer
+                .authorize(Action::Admin, "adi.tasks", Some(&confused))
+                .await,
+            Err(AuthError::Unauthenticated(_))
+        ));
+        assert!(JwtAuthorizer::rs256_pem(b"not a key").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_jwks_url() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/.well-known/jwks.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(JWKS))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let url = format!("{}/.well-known/jwks.json", server.uri());
+        let authorizer = JwtAuthorizer::jwks_url(&url).unwrap();
+        let now = now_unix();
+        let claims = serde_json::json!({ "sub": "ci", "exp": now + 300, "scope": "publish" });
+
+        for _ in 0..2 {
+            let token = sign_rs256(Some("test-key"), claims.clone());
+            assert_eq!(
+                authorizer
+                    .authorize(Action::Publish, "adi.tasks", Some(&token))
+                    .await,
+                Ok(Identity::new("ci"))
+            );
+        }
+        // Unknown key ids are refused without fetching again right away
+        let token = sign_rs256(Some("rotated-key"), claims);
+        assert!(matches!(
+            authorizer
+                .authorize(Action::Publish, "adi.tasks", Some(&token))
+                .await,
+            Err(AuthError::Unauthenticated(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_jwks_fetched_once_for_concurrent_requests() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(JWKS)
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let authorizer = Arc::new(JwtAuthorizer::jwks_url(&server.uri()).unwrap());
+        let claims =
+            serde_json::json!({ "sub": "ci", "exp": now_unix() + 300, "scope": "publish" });
+        let token = sign_rs256(Some("test-key"), claims);
+
+        let requests: Vec<_> = (0..4)
+            .map(|_| {
+                let authorizer = authorizer.clone();
+                let token = token.clone();
+                tokio::spawn(async move {
+                    authorizer
+                        .authorize(Action::Publish, "adi.tasks", Some(&token))
+                        .await
+                })
+            })
+            .collect();
+        for request in requests {
+            assert_eq!(request.await.unwrap(), Ok(Identity::new("ci")));
+        }
+    }
+}
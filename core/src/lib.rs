@@ -1,3 +1,59 @@
+mod accept;
+mod artifact;
+mod asset;
+mod audit;
+mod auth;
+mod authorize;
+mod base64;
+mod bundle;
+mod compare;
+mod dependency;
+mod dependents;
+mod downloads;
+mod etag;
+mod group;
+mod importmap;
+mod intern;
+mod jwt;
+mod locale;
+mod path;
+mod scrub;
+mod signing;
+mod sort;
+mod stats;
 mod storage;
+mod telemetry;
+mod text;
+mod tombstone;
+mod yank;
 
-pub use storage::RegistryStorage;
+pub use accept::accepts;
+pub use artifact::ArtifactReader;
+pub use asset::{AssetExists, AssetMeta, InvalidAssetName};
+pub use audit::{AuditEntry, AuditFilter};
+pub use auth::{ApiKey, KeyStore, StaticKey};
+pub use authorize::{Action, AuthError, Authorizer, Identity, TokenFileAuthorizer};
+pub use base64::{decode_base64, encode_base64};
+pub use bundle::PluginBundle;
+pub use compare::{IndexComparison, LocalEntry};
+pub use dependency::{
+    Dependency, DependencyCycle, InvalidVersionReq, ResolvedDependency, UnsatisfiableDependency,
+};
+pub use dependents::Dependent;
+pub use etag::{content_etag, etag_matches, sha256_hex, strong_etag, weak_content_etag, weak_etag};
+pub use group::{group_by_package, GroupedPlugins, PackageGroup};
+pub use importmap::ImportMap;
+pub use jwt::JwtAuthorizer;
+pub use locale::{parse_accept_language, pick_localized};
+pub use path::{content_type_for, normalize_platform, sanitize_filename, strip_extension};
+pub use scrub::ScrubReport;
+pub use signing::{InvalidSignature, PublishKey};
+pub use sort::SearchSort;
+pub use stats::RegistryStats;
+pub use storage::{
+    is_valid_id, is_valid_version, ArtifactExists, IndexLock, InvalidId, InvalidVersion,
+    PluginVersionMeta, PluginVersionSummary, PublishExtras, RegistryStorage, StorageOptions,
+    TooManyPlatforms, UnsatisfiedDependencies, WebUiExists,
+};
+pub use telemetry::{InstallCounts, InstallOutcome, InstallTelemetry};
+pub use text::{fold_case, has_all_tags, matches_terms, search_terms, tag_filter, truncate_chars};
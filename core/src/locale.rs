@@ -0,0 +1,187 @@
+use crate::storage::{write_atomic, RegistryStorage};
+use anyhow::{Context, Result};
+use lib_plugin_registry::RegistryIndex;
+use std::collections::BTreeMap;
+use tokio::fs;
+
+/// Locale tags from an `Accept-Language` header, most preferred first.
+///
+/// Entries with `q=0` and the `*` wildcard are dropped; ties keep header order.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut tags: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let tag = parts.next().filter(|t| !t.is_empty() && *t != "*")?;
+            let q = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (q > 0.0).then(|| (tag.to_string(), q))
+        })
+        .collect();
+    tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+    tags.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Pick the description for the first preferred locale that has one.
+///
+/// A locale matches exactly (case-insensitively) or by primary language, so `fr-CA`
+/// falls back to `fr` and `fr` accepts `fr-FR`.
+pub fn pick_localized<'a>(
+    descriptions: &'a BTreeMap<String, String>,
+    locales: &[String],
+) -> Option<&'a str> {
+    let primary = |tag: &str| {
+        tag.split(['-', '_'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase()
+    };
+    locales.iter().find_map(|locale| {
+        descriptions
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(locale))
+            .or_else(|| {
+                descriptions
+                    .iter()
+                    .find(|(key, _)| primary(key) == primary(locale))
+            })
+            .map(|(_, text)| text.as_str())
+    })
+}
+
+impl RegistryStorage {
+    /// Localized plugin descriptions (locale → text) by plugin id, from `descriptions.json`.
+    ///
+    /// Kept outside `index.json` because the index entry types have no field for them.
+    pub async fn plugin_descriptions(&self) -> Result<BTreeMap<String, BTreeMap<String, String>>> {
+        let path = self.resolve(self.root().join("descriptions.json"));
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let data = fs::read_to_string(&path).await?;
+        serde_json::from_str(&data).context("Failed to parse descriptions.json")
+    }
+
+    /// Replace a plugin's localized descriptions; an empty map removes them. Callers
+    /// hold [`Self::lock_index`].
+    pub(crate) async fn set_plugin_descriptions(
+        &self,
+        id: &str,
+        descriptions: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let mut all = self.plugin_descriptions().await?;
+        if descriptions.is_empty() {
+            if all.remove(id).is_none() {
+                return Ok(());
+            }
+        } else {
+            all.insert(id.to_string(), descriptions.clone());
+        }
+        let path = self.root().join("descriptions.json");
+        write_atomic(&path, serde_json::to_string_pretty(&all)?.as_bytes()).await?;
+        self.record_change();
+        Ok(())
+    }
+
+    /// Swap each plugin's default description for its best match among `locales`.
+    pub async fn localize_index(
+        &self,
+        index: &mut RegistryIndex,
+        locales: &[String],
+    ) -> Result<()> {
+        if locales.is_empty() {
+            return Ok(());
+        }
+        let descriptions = self.plugin_descriptions().await?;
+        for plugin in &mut index.plugins {
+            if let Some(text) = descriptions
+                .get(&plugin.id)
+                .and_then(|d| pick_localized(d, locales))
+            {
+                plugin.description = text.to_string();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::PluginVersionMeta;
+
+    #[test]
+    fn test_parse_accept_language() {
+        assert_eq!(
+            parse_accept_language("de;q=0.5, fr-CA, en;q=0.8, *;q=0.1, es;q=0"),
+            vec!["fr-CA", "en", "de"]
+        );
+        assert!(parse_accept_language("").is_empty());
+    }
+
+    #[test]
+    fn test_pick_localized() {
+        let descriptions = BTreeMap::from([
+            ("fr".to_string(), "Gestion des tâches".to_string()),
+            ("pt-BR".to_string(), "Gerenciamento de tarefas".to_string()),
+        ]);
+        let pick = |locales: &[&str]| {
+            let locales: Vec<String> = locales.iter().map(|l| l.to_string()).collect();
+            pick_localized(&descriptions, &locales)
+        };
+        assert_eq!(pick(&["FR"]), Some("Gestion des tâches"));
+        assert_eq!(pick(&["fr-CA"]), Some("Gestion des tâches"));
+        assert_eq!(pick(&["pt"]), Some("Gerenciamento de tarefas"));
+        assert_eq!(pick(&["de", "fr"]), Some("Gestion des tâches"));
+        assert_eq!(pick(&["de"]), None);
+    }
+
+    #[tokio::test]
+    async fn test_localize_index_from_meta() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"bin",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let meta = PluginVersionMeta {
+            name: "Tasks".to_string(),
+            description: "Task management".to_string(),
+            plugin_type: "core".to_string(),
+            author: "ADI Team".to_string(),
+            descriptions: BTreeMap::from([("fr".to_string(), "Gestion des tâches".to_string())]),
+            ..Default::default()
+        };
+        storage
+            .publish_plugin_meta("adi.tasks", "1.0.0", &meta)
+            .await
+            .unwrap();
+
+        let mut index = storage.load_index().await.unwrap();
+        storage
+            .localize_index(&mut index, &["fr".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(index.plugins[0].description, "Gestion des tâches");
+
+        let mut index = storage.load_index().await.unwrap();
+        storage
+            .localize_index(&mut index, &["de".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(index.plugins[0].description, "Task management");
+    }
+}
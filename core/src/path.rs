@@ -0,0 +1,141 @@
+/// Strip a literal extension from a URL path segment, exactly once.
+///
+/// Route segments such as `1.0.0.json` or `linux-x86_64.tar.gz` carry both the
+/// parameter and its extension. Versions may contain dots themselves
+/// (`2024.01.15`, `1.0.0+build.5`), so only the trailing extension is removed.
+/// Returns `None` if the extension is missing or nothing precedes it.
+pub fn strip_extension<'a>(segment: &'a str, ext: &str) -> Option<&'a str> {
+    segment.strip_suffix(ext).filter(|rest| !rest.is_empty())
+}
+
+/// Normalize a platform string to its canonical lowercase, hyphen-separated form.
+///
+/// Clients on Windows sometimes send `Windows-X86_64` or `windows\x86_64`; both map
+/// to `windows-x86_64` so they resolve to the same stored build.
+pub fn normalize_platform(platform: &str) -> String {
+    platform
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '\\' | '/' => '-',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Make a client-supplied name safe to put in a `Content-Disposition` filename.
+///
+/// Anything outside ASCII letters, digits and `.`, `_`, `-`, `+` becomes `_`, leading
+/// dots are dropped so the name can't be hidden or relative, and the result is capped
+/// at 128 characters. Returns `None` if nothing usable is left.
+pub fn sanitize_filename(name: &str) -> Option<String> {
+    let safe: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' | '-' | '+' => c,
+            _ => '_',
+        })
+        .collect();
+    let safe: String = safe.trim_start_matches('.').chars().take(128).collect();
+    Some(safe).filter(|s| s.chars().any(|c| c.is_ascii_alphanumeric()))
+}
+
+/// The `Content-Type` to serve a stored file with, going by its extension.
+///
+/// Anything unrecognized is `application/octet-stream`, so browsers download it
+/// rather than guess.
+pub fn content_type_for(name: &str) -> &'static str {
+    let name = name.to_ascii_lowercase();
+    let types = [
+        (".tar.gz", "application/gzip"),
+        (".tgz", "application/gzip"),
+        (".js", "application/javascript"),
+        (".json", "application/json"),
+        (".png", "image/png"),
+    ];
+    types
+        .iter()
+        .find(|(ext, _)| name.ends_with(ext))
+        .map_or("application/octet-stream", |(_, content_type)| content_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_version() {
+        assert_eq!(strip_extension("1.0.0.json", ".json"), Some("1.0.0"));
+    }
+
+    #[test]
+    fn test_build_metadata() {
+        assert_eq!(
+            strip_extension("1.0.0+build.json", ".json"),
+            Some("1.0.0+build")
+        );
+        assert_eq!(
+            strip_extension("1.0.0+build.5.json", ".json"),
+            Some("1.0.0+build.5")
+        );
+    }
+
+    #[test]
+    fn test_dotted_version() {
+        assert_eq!(
+            strip_extension("2024.01.15.json", ".json"),
+            Some("2024.01.15")
+        );
+    }
+
+    #[test]
+    fn test_strips_only_once() {
+        assert_eq!(
+            strip_extension("1.0.0.json.json", ".json"),
+            Some("1.0.0.json")
+        );
+        assert_eq!(
+            strip_extension("linux-x86_64.tar.gz.tar.gz", ".tar.gz"),
+            Some("linux-x86_64.tar.gz")
+        );
+    }
+
+    #[test]
+    fn test_missing_extension() {
+        assert_eq!(strip_extension("1.0.0", ".json"), None);
+        assert_eq!(strip_extension(".json", ".json"), None);
+    }
+
+    #[test]
+    fn test_normalize_platform() {
+        assert_eq!(normalize_platform("Windows-X86_64"), "windows-x86_64");
+        assert_eq!(normalize_platform("windows\\x86_64"), "windows-x86_64");
+        assert_eq!(normalize_platform("Darwin/AArch64"), "darwin-aarch64");
+        assert_eq!(normalize_platform("linux-x86_64"), "linux-x86_64");
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(
+            sanitize_filename("adi.tasks-1.0.0+build.5-linux-x86_64.tar.gz").as_deref(),
+            Some("adi.tasks-1.0.0+build.5-linux-x86_64.tar.gz")
+        );
+        assert_eq!(
+            sanitize_filename("../../etc/\"passwd\"").as_deref(),
+            Some("_.._etc__passwd_")
+        );
+        assert_eq!(sanitize_filename("..."), None);
+        assert_eq!(sanitize_filename("/;/"), None);
+    }
+
+    #[test]
+    fn test_content_type_for() {
+        assert_eq!(content_type_for("linux-x86_64.tar.gz"), "application/gzip");
+        assert_eq!(content_type_for("bundle.TGZ"), "application/gzip");
+        assert_eq!(content_type_for("web.js"), "application/javascript");
+        assert_eq!(content_type_for("schema.json"), "application/json");
+        assert_eq!(content_type_for("icon.png"), "image/png");
+        assert_eq!(content_type_for("notes.txt"), "application/octet-stream");
+        assert_eq!(content_type_for("json"), "application/octet-stream");
+    }
+}
@@ -0,0 +1,182 @@
+use crate::storage::{write_atomic, RegistryStorage};
+use anyhow::{Context, Result};
+use lib_plugin_registry::PlatformBuild;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+
+/// Outcome of one pass over all stored artifacts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScrubReport {
+    pub checked: usize,
+    pub corrupt: usize,
+}
+
+/// Key for an artifact in `corrupt.json`: `<kind>/<id>/<version>/<platform>`.
+fn artifact_key(kind: &str, id: &str, version: &str, platform: &str) -> String {
+    format!("{}/{}/{}/{}", kind, id, version, platform)
+}
+
+pub(crate) fn sha256_file(path: PathBuf) -> Result<String> {
+    let mut file = std::fs::File::open(&path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+impl RegistryStorage {
+    /// Artifacts whose content no longer matched their recorded checksum on the last scrub.
+    async fn load_corrupt(&self) -> Result<BTreeSet<String>> {
+        let path = self.root().join("corrupt.json");
+        if !path.exists() {
+            return Ok(BTreeSet::new());
+        }
+        let data = fs::read_to_string(&path).await?;
+        serde_json::from_str(&data).context("Failed to parse corrupt.json")
+    }
+
+    /// Platforms of a version flagged corrupt by the scrubber. `kind` is `packages` or `plugins`.
+    pub async fn corrupt_platforms(
+        &self,
+        kind: &str,
+        id: &str,
+        version: &str,
+    ) -> Result<BTreeSet<String>> {
+        let prefix = artifact_key(kind, id, version, "");
+        Ok(self
+            .load_corrupt()
+            .await?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&prefix).map(str::to_string))
+            .collect())
+    }
+
+    /// Re-hash every stored artifact and compare it with its recorded checksum.
+    ///
+    /// Sleeps `pause` between artifacts so a pass never competes with request handling
+    /// for disk bandwidth. Mismatches are logged and recorded in `corrupt.json`; an
+    /// artifact that verifies again is cleared.
+    pub async fn scrub_artifacts(&self, pause: Duration) -> Result<ScrubReport> {
+        let index = self.load_index().await?;
+        let ids = index
+            .packages
+            .iter()
+            .map(|p| ("packages", p.id.clone()))
+            .chain(index.plugins.iter().map(|p| ("plugins", p.id.clone())));
+
+        let mut report = ScrubReport::default();
+        let mut corrupt = BTreeSet::new();
+        for (kind, id) in ids {
+            for version in self.list_versions(kind, &id).await? {
+                let platforms: Vec<PlatformBuild> = match kind {
+                    "packages" => self.get_package_info(&id, &version).await?.platforms,
+                    _ => self.get_plugin_info(&id, &version).await?.platforms,
+                };
+                for build in platforms {
+                    let path = match kind {
+                        "packages" => self.package_artifact_path(&id, &version, &build.platform),
+                        _ => self.plugin_artifact_path(&id, &version, &build.platform),
+                    };
+                    let actual = tokio::task::spawn_blocking(move || sha256_file(path)).await?;
+                    report.checked += 1;
+                    if actual.ok().as_deref() != Some(build.checksum.as_str()) {
+                        tracing::warn!(
+                            kind,
+                            id = %id,
+                            version = %version,
+                            platform = %build.platform,
+                            "Artifact checksum mismatch"
+                        );
+                        corrupt.insert(artifact_key(kind, &id, &version, &build.platform));
+                    }
+                    tokio::time::sleep(pause).await;
+                }
+            }
+        }
+
+        report.corrupt = corrupt.len();
+        let path = self.root().join("corrupt.json");
+        write_atomic(&path, serde_json::to_string_pretty(&corrupt)?.as_bytes()).await?;
+        Ok(report)
+    }
+
+    /// Run [`Self::scrub_artifacts`] every `interval` in the background.
+    pub fn spawn_scrubber(
+        self: &Arc<Self>,
+        interval: Duration,
+        pause: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let storage = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match storage.scrub_artifacts(pause).await {
+                    Ok(report) => tracing::info!(
+                        checked = report.checked,
+                        corrupt = report.corrupt,
+                        "Artifact scrub completed"
+                    ),
+                    Err(e) => tracing::warn!("Artifact scrub failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scrubber_flags_corruption() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = Arc::new(RegistryStorage::new(tmp.path().to_path_buf()));
+        storage.init().await.unwrap();
+        for platform in ["darwin-aarch64", "linux-x86_64"] {
+            storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "Task management",
+                    "core",
+                    "1.0.0",
+                    platform,
+                    platform.as_bytes(),
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+
+        let path = storage.plugin_artifact_path("adi.tasks", "1.0.0", "linux-x86_64");
+        std::fs::write(&path, b"bit rot").unwrap();
+
+        let scrubber = storage.spawn_scrubber(Duration::from_millis(10), Duration::ZERO);
+        let mut flagged = BTreeSet::new();
+        for _ in 0..100 {
+            flagged = storage
+                .corrupt_platforms("plugins", "adi.tasks", "1.0.0")
+                .await
+                .unwrap();
+            if !flagged.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        scrubber.abort();
+
+        assert_eq!(flagged, BTreeSet::from(["linux-x86_64".to_string()]));
+    }
+}
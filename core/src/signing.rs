@@ -0,0 +1,91 @@
+//! Ed25519 signatures over an artifact's SHA-256 digest, checked at publish time.
+
+use crate::base64::decode_base64;
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+/// Why a publish signature was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidSignature {
+    /// Not base64 of a 64-byte Ed25519 signature.
+    Malformed,
+    /// Well-formed, but not made by the configured key over this artifact.
+    Mismatch,
+}
+
+impl std::fmt::Display for InvalidSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidSignature::Malformed => {
+                write!(f, "Signature must be base64 of a 64-byte Ed25519 signature")
+            }
+            InvalidSignature::Mismatch => {
+                write!(f, "Signature does not verify against the publish key")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidSignature {}
+
+/// The Ed25519 public key publish signatures must verify against.
+#[derive(Debug, Clone)]
+pub struct PublishKey(VerifyingKey);
+
+impl PublishKey {
+    /// Parse base64 of a raw 32-byte Ed25519 public key; `None` if it isn't one.
+    pub fn parse(text: &str) -> Option<Self> {
+        let bytes: [u8; 32] = decode_base64(text.trim())?.try_into().ok()?;
+        VerifyingKey::from_bytes(&bytes).ok().map(Self)
+    }
+
+    /// Check `signature`, base64 of an Ed25519 signature over the SHA-256 of `data`.
+    pub fn verify(&self, data: &[u8], signature: &str) -> Result<(), InvalidSignature> {
+        let bytes: [u8; 64] = decode_base64(signature.trim())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(InvalidSignature::Malformed)?;
+        let digest = Sha256::digest(data);
+        self.0
+            .verify_strict(&digest, &Signature::from_bytes(&bytes))
+            .map_err(|_| InvalidSignature::Mismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8032 test vector 1, signing the SHA-256 of b"fake binary"
+    const PUBLIC_KEY: &str = "11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=";
+    const SIGNATURE: &str =
+        "729TE5A1iorgJE3oA8ED00LPCdcSpG8O32KP/1a1BDPre1BOzmzNBhzWpbSeutayo22qUSaxwoffi8taL3QzDQ==";
+
+    #[test]
+    fn test_verify_signature() {
+        let key = PublishKey::parse(PUBLIC_KEY).unwrap();
+        assert_eq!(key.verify(b"fake binary", SIGNATURE), Ok(()));
+        assert_eq!(
+            key.verify(b"tampered binary", SIGNATURE),
+            Err(InvalidSignature::Mismatch)
+        );
+    }
+
+    #[test]
+    fn test_malformed_signature() {
+        let key = PublishKey::parse(PUBLIC_KEY).unwrap();
+        for signature in ["", "not base64!", "c2hvcnQ="] {
+            assert_eq!(
+                key.verify(b"fake binary", signature),
+                Err(InvalidSignature::Malformed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_other_keys() {
+        assert!(PublishKey::parse("").is_none());
+        assert!(PublishKey::parse("c2hvcnQ=").is_none());
+        // A signature is base64 too, but 64 bytes long
+        assert!(PublishKey::parse(SIGNATURE).is_none());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::text::fold_case;
+use lib_plugin_registry::{PackageEntry, PluginEntry};
+use std::cmp::Reverse;
+
+/// Order of search results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchSort {
+    /// Index order.
+    #[default]
+    Relevance,
+    /// Most downloaded first.
+    Downloads,
+    /// By name, case-insensitively.
+    Name,
+}
+
+impl SearchSort {
+    /// Parse a `sort` query value; anything unknown falls back to [`SearchSort::Relevance`].
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("downloads") => SearchSort::Downloads,
+            Some("name") => SearchSort::Name,
+            _ => SearchSort::Relevance,
+        }
+    }
+
+    /// Sort packages and plugins in place. Ties keep their index order.
+    pub fn apply(self, packages: &mut [PackageEntry], plugins: &mut [PluginEntry]) {
+        match self {
+            SearchSort::Relevance => {}
+            SearchSort::Downloads => {
+                packages.sort_by_key(|p| Reverse(p.downloads));
+                plugins.sort_by_key(|p| Reverse(p.downloads));
+            }
+            SearchSort::Name => {
+                packages.sort_by_cached_key(|p| fold_case(&p.name));
+                plugins.sort_by_cached_key(|p| fold_case(&p.name));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(id: &str, name: &str, downloads: u64) -> PluginEntry {
+        PluginEntry {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            plugin_type: "core".to_string(),
+            package_id: None,
+            latest_version: "1.0.0".to_string(),
+            downloads,
+            author: "ADI Team".to_string(),
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_sort_plugins() {
+        let plugins = vec![
+            plugin("adi.tasks", "tasks", 10),
+            plugin("adi.agent", "Agent", 50),
+            plugin("adi.lint", "Lint", 10),
+        ];
+        let sorted = |sort: Option<&str>| {
+            let mut plugins = plugins.clone();
+            SearchSort::parse(sort).apply(&mut [], &mut plugins);
+            plugins.into_iter().map(|p| p.id).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            sorted(Some("downloads")),
+            ["adi.agent", "adi.tasks", "adi.lint"]
+        );
+        assert_eq!(sorted(Some("name")), ["adi.agent", "adi.lint", "adi.tasks"]);
+        assert_eq!(
+            sorted(Some("stars")),
+            ["adi.tasks", "adi.agent", "adi.lint"]
+        );
+        assert_eq!(sorted(None), sorted(Some("relevance")));
+    }
+}
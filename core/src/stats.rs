@@ -0,0 +1,108 @@
+use crate::storage::RegistryStorage;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Aggregate numbers over the whole registry index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryStats {
+    pub packages: u64,
+    pub plugins: u64,
+    /// Downloads of all packages and plugins together.
+    pub downloads: u64,
+    /// The most downloaded plugin, the earliest in the index on ties. `None` until
+    /// some plugin has been downloaded.
+    pub top_plugin: Option<String>,
+    /// The index's `updated_at`.
+    pub updated_at: u64,
+}
+
+impl RegistryStorage {
+    /// Totals over the current index, in a single pass.
+    pub async fn compute_stats(&self) -> Result<RegistryStats> {
+        let index = self.load_index().await?;
+        let mut stats = RegistryStats {
+            packages: index.packages.len() as u64,
+            plugins: index.plugins.len() as u64,
+            updated_at: index.updated_at,
+            ..Default::default()
+        };
+        for package in &index.packages {
+            stats.downloads += package.downloads;
+        }
+        let mut top = 0;
+        for plugin in &index.plugins {
+            stats.downloads += plugin.downloads;
+            if plugin.downloads > top {
+                top = plugin.downloads;
+                stats.top_plugin = Some(plugin.id.clone());
+            }
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_compute_stats() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        assert_eq!(storage.compute_stats().await.unwrap().top_plugin, None);
+
+        storage
+            .publish_package(
+                "adi.suite",
+                "Suite",
+                "",
+                "1.0.0",
+                "linux-x86_64",
+                b"fake binary",
+                "ADI Team",
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+        for id in ["adi.tasks", "adi.notes", "adi.lint"] {
+            storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"fake binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let downloads = [
+            ("packages", "adi.suite"),
+            ("plugins", "adi.notes"),
+            ("plugins", "adi.notes"),
+            ("plugins", "adi.lint"),
+        ];
+        for (kind, id) in downloads {
+            storage.increment_downloads(kind, id).await.unwrap();
+        }
+
+        let stats = storage.compute_stats().await.unwrap();
+        let updated_at = storage.load_index().await.unwrap().updated_at;
+        assert_eq!(
+            stats,
+            RegistryStats {
+                packages: 1,
+                plugins: 3,
+                downloads: 4,
+                top_plugin: Some("adi.notes".to_string()),
+                updated_at,
+            }
+        );
+    }
+}
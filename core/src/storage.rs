@@ -1,61 +1,659 @@
+use crate::dependency::Dependency;
+use crate::dependents::DependentsCache;
+use crate::intern::InternedIndex;
+use crate::path::normalize_platform;
+use crate::scrub::sha256_file;
 use anyhow::{Context, Result};
+use fs2::FileExt;
 use lib_plugin_registry::{
     PackageEntry, PackageInfo, PlatformBuild, PluginEntry, PluginInfo, RegistryIndex, WebUiMeta,
 };
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 
+/// Tunable storage behaviour. The defaults match a plain `RegistryStorage::new`.
+#[derive(Debug, Clone)]
+pub struct StorageOptions {
+    /// Record per-entry download counts.
+    pub count_downloads: bool,
+    /// Let a metadata-only plugin version become `latest_version` in the index.
+    pub artifactless_latest: bool,
+    /// Coalesce index writes: mutations apply in memory at once and `index.json` is
+    /// written after this delay. `None` writes through on every change. Only suitable
+    /// when a single process owns the data directory.
+    pub index_flush_delay: Option<Duration>,
+    /// Maximum number of distinct platforms a single version may have.
+    pub max_platforms: usize,
+    /// Read-only data root (e.g. a snapshot volume) consulted for any file missing
+    /// from the write root. Writes always go to the write root.
+    pub read_root: Option<PathBuf>,
+    /// Reject plugin publishes whose declared dependencies match no published version.
+    pub validate_dependencies: bool,
+    /// Refuse to replace an existing web UI, since `web.js` is served with an
+    /// immutable cache and clients would keep whichever copy they fetched first.
+    pub immutable_web_ui: bool,
+    /// Keep the index loaded by [`RegistryStorage::warm`] in memory instead of
+    /// re-reading `index.json` on every request. Only suitable when a single process
+    /// owns the data directory.
+    pub cache_index: bool,
+    /// How long a deleted id is reported as gone rather than never published.
+    pub tombstone_retention: Duration,
+}
+
+impl Default for StorageOptions {
+    fn default() -> Self {
+        Self {
+            count_downloads: true,
+            artifactless_latest: false,
+            index_flush_delay: None,
+            max_platforms: 32,
+            read_root: None,
+            validate_dependencies: false,
+            immutable_web_ui: false,
+            cache_index: false,
+            tombstone_retention: Duration::from_secs(30 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// Publishing a new platform would exceed [`StorageOptions::max_platforms`].
+#[derive(Debug)]
+pub struct TooManyPlatforms {
+    pub max: usize,
+}
+
+impl std::fmt::Display for TooManyPlatforms {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Version already has the maximum of {} platforms",
+            self.max
+        )
+    }
+}
+
+impl std::error::Error for TooManyPlatforms {}
+
+/// Declared dependencies that no published version satisfies, as `<id> <requirement>`.
+#[derive(Debug)]
+pub struct UnsatisfiedDependencies {
+    pub dependencies: Vec<String>,
+}
+
+impl std::fmt::Display for UnsatisfiedDependencies {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unsatisfied dependencies: {}",
+            self.dependencies.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnsatisfiedDependencies {}
+
+/// The version already has a web UI and [`StorageOptions::immutable_web_ui`] is set.
+#[derive(Debug)]
+pub struct WebUiExists;
+
+impl std::fmt::Display for WebUiExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Version already has a web UI")
+    }
+}
+
+impl std::error::Error for WebUiExists {}
+
+/// Publishing would replace an artifact that already exists for this platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactExists {
+    pub platform: String,
+}
+
+impl std::fmt::Display for ArtifactExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Version already has a {} artifact", self.platform)
+    }
+}
+
+impl std::error::Error for ArtifactExists {}
+
+/// Longest accepted package or plugin id.
+const MAX_ID_LEN: usize = 128;
+
+/// An id that can't be published, since it becomes a directory name under the data root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidId {
+    pub id: String,
+}
+
+impl std::fmt::Display for InvalidId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid id {:?}: use up to {} lowercase letters, digits, '.', '_' or '-', \
+             starting with a letter or digit",
+            self.id, MAX_ID_LEN
+        )
+    }
+}
+
+impl std::error::Error for InvalidId {}
+
+/// Whether `id` is safe to use as a single path component: `[a-z0-9._-]`, at most
+/// [`MAX_ID_LEN`] bytes, starting with a letter or digit (which also rules out `.` and `..`).
+pub fn is_valid_id(id: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c);
+    id.len() <= MAX_ID_LEN
+        && id.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && id.chars().all(allowed)
+}
+
+pub(crate) fn check_id(id: &str) -> Result<()> {
+    if !is_valid_id(id) {
+        return Err(InvalidId { id: id.to_string() }.into());
+    }
+    Ok(())
+}
+
+/// A version that can't be published, since it becomes a directory name under the id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidVersion {
+    pub version: String,
+}
+
+impl std::fmt::Display for InvalidVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid version {:?}: use up to {} letters, digits, '.', '_', '-' or '+', \
+             starting with a letter or digit and without '..'",
+            self.version, MAX_ID_LEN
+        )
+    }
+}
+
+impl std::error::Error for InvalidVersion {}
+
+/// Whether `version` is safe to use as a single path component. Looser than semver so
+/// date versions like `2024.01.15` still work, but never contains a separator or `..`.
+pub fn is_valid_version(version: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "._-+".contains(c);
+    version.len() <= MAX_ID_LEN
+        && version.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && version.chars().all(allowed)
+        && !version.contains("..")
+}
+
+pub(crate) fn check_version(version: &str) -> Result<()> {
+    if !is_valid_version(version) {
+        return Err(InvalidVersion {
+            version: version.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Plugin version metadata published without an artifact, stored as `meta.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PluginVersionMeta {
+    pub name: String,
+    pub description: String,
+    pub plugin_type: String,
+    pub author: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub changelog: Option<String>,
+    /// Required dependencies as `id -> requirement`. Publishing moves them into the
+    /// version's `dependencies.json`, so a stored `meta.json` leaves this empty.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, String>,
+    /// Localized descriptions by locale tag (e.g. `fr`, `pt-BR`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptions: BTreeMap<String, String>,
+}
+
+/// Details of a publish beyond the artifact, written to `info.json` in the same locked
+/// update as the build by [`RegistryStorage::store_package`] and
+/// [`RegistryStorage::store_plugin`].
+#[derive(Debug, Clone, Default)]
+pub struct PublishExtras {
+    /// Detached signature of the artifact. Republishing the platform without one drops it.
+    pub signature: Option<String>,
+    /// Overrides the version's `published_at`, e.g. to preserve the original time when
+    /// importing from another registry.
+    pub published_at: Option<u64>,
+    /// Plugin changelog, kept in the version's `meta.json` since `PluginInfo` has no
+    /// field for it. Packages take theirs as an argument to [`RegistryStorage::store_package`].
+    pub changelog: Option<String>,
+    /// Plugin dependencies, replacing any declared before; `None` keeps them.
+    pub dependencies: Option<Vec<Dependency>>,
+}
+
+/// One published plugin version, as listed by [`RegistryStorage::list_plugin_versions`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginVersionSummary {
+    pub version: String,
+    pub has_web_ui: bool,
+}
+
+/// Per-entry last-update times, stored as `updated.json` next to the index.
+///
+/// Kept outside `index.json` because the index entry types have no timestamp field.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct EntryTimes {
+    #[serde(default)]
+    packages: BTreeMap<String, u64>,
+    #[serde(default)]
+    plugins: BTreeMap<String, u64>,
+    /// When each package's current `latest_version` was published.
+    #[serde(default)]
+    latest_packages: BTreeMap<String, u64>,
+    /// When each plugin's current `latest_version` was published.
+    #[serde(default)]
+    latest_plugins: BTreeMap<String, u64>,
+}
+
+/// In-memory index state: the copy loaded by [`RegistryStorage::warm`] and any
+/// changes awaiting a coalesced write.
+#[derive(Default)]
+struct IndexCache {
+    /// Set once warmed, then kept current by every save. Interned, as it stays
+    /// resident for the life of the process.
+    loaded: Mutex<Option<InternedIndex>>,
+    pending: Mutex<Option<RegistryIndex>>,
+    flush_scheduled: AtomicBool,
+    writes: AtomicUsize,
+    /// Bumped by every change this process makes to what the index serves; part of
+    /// [`RegistryStorage::revision`].
+    changes: AtomicUsize,
+    /// Malformed entries left out of the index last read from disk. Saving is refused
+    /// while there are any, as it would drop them for good.
+    skipped: AtomicUsize,
+}
+
+impl IndexCache {
+    /// Write the pending index, if any. The lock is held across the write so
+    /// concurrent flushes can never persist an older index over a newer one.
+    fn flush(&self, path: &Path) -> Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(index) = pending.as_ref() {
+            replace_index(path, index)?;
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            *pending = None;
+        }
+        Ok(())
+    }
+}
+
 /// File-based registry storage.
 pub struct RegistryStorage {
     root: PathBuf,
+    options: StorageOptions,
+    cache: Arc<IndexCache>,
+    /// Queues this process's index writers before they contend for `index.lock`.
+    index_mutex: Arc<tokio::sync::Mutex<()>>,
+    dependents: DependentsCache,
+    /// Size, mtime and checksum of artifacts that last verified, so unchanged files aren't re-hashed.
+    verified: Mutex<HashMap<PathBuf, (u64, SystemTime, String)>>,
 }
 
 impl RegistryStorage {
     pub fn new(root: PathBuf) -> Self {
-        Self { root }
+        Self::with_options(root, StorageOptions::default())
+    }
+
+    pub fn with_options(root: PathBuf, options: StorageOptions) -> Self {
+        Self {
+            root,
+            options,
+            cache: Arc::default(),
+            index_mutex: Arc::default(),
+            dependents: DependentsCache::default(),
+            verified: Mutex::default(),
+        }
     }
 
     pub fn root(&self) -> &Path {
         &self.root
     }
 
+    pub(crate) fn dependents_cache(&self) -> &DependentsCache {
+        &self.dependents
+    }
+
+    pub fn options(&self) -> &StorageOptions {
+        &self.options
+    }
+
     /// Initialize storage directories.
+    ///
+    /// Fails if a configured read root is missing or is the write root itself.
     pub async fn init(&self) -> Result<()> {
         fs::create_dir_all(&self.root).await?;
         fs::create_dir_all(self.root.join("packages")).await?;
         fs::create_dir_all(self.root.join("plugins")).await?;
 
-        // Create empty index if not exists
+        if let Some(read_root) = &self.options.read_root {
+            anyhow::ensure!(
+                read_root.is_dir(),
+                "Read root {} is not a directory",
+                read_root.display()
+            );
+            anyhow::ensure!(
+                fs::canonicalize(read_root).await? != fs::canonicalize(&self.root).await?,
+                "Read root and write root must differ"
+            );
+        }
+
+        // Create empty index if not exists; a read root's index is used until the first write
         let index_path = self.root.join("index.json");
-        if !index_path.exists() {
+        if !self.resolve(index_path.clone()).exists() {
             let index = RegistryIndex::default();
             let json = serde_json::to_string_pretty(&index)?;
             fs::write(&index_path, json).await?;
         }
 
+        let migrated = self.migrate_meta_dependencies().await?;
+        if migrated > 0 {
+            tracing::info!(migrated, "Moved plugin dependencies out of meta.json");
+        }
+
         Ok(())
     }
 
-    /// Load the registry index.
+    /// Map a path under the write root to the read root when the file only exists there.
+    pub(crate) fn resolve(&self, path: PathBuf) -> PathBuf {
+        let Some(read_root) = &self.options.read_root else {
+            return path;
+        };
+        if path.exists() {
+            return path;
+        }
+        let fallback = path
+            .strip_prefix(&self.root)
+            .ok()
+            .map(|relative| read_root.join(relative));
+        fallback.unwrap_or(path)
+    }
+
+    /// Versions of a package or plugin (`kind` is `packages` or `plugins`) that have an
+    /// `info.json`, across the write root and any read root.
+    pub async fn list_versions(&self, kind: &str, id: &str) -> Result<Vec<String>> {
+        let mut versions = BTreeSet::new();
+        let roots = std::iter::once(&self.root).chain(self.options.read_root.as_ref());
+        for root in roots {
+            let Ok(mut entries) = fs::read_dir(root.join(kind).join(id)).await else {
+                continue;
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.path().join("info.json").exists() {
+                    if let Some(version) = entry.file_name().to_str() {
+                        versions.insert(version.to_string());
+                    }
+                }
+            }
+        }
+        Ok(versions.into_iter().collect())
+    }
+
+    /// Load the registry index, with current download counts.
+    ///
+    /// Returns the in-memory index while a coalesced write is pending, or once the
+    /// storage has been [warmed](Self::warm) with [`StorageOptions::cache_index`] set.
     pub async fn load_index(&self) -> Result<RegistryIndex> {
-        let path = self.root.join("index.json");
-        let data = fs::read_to_string(&path)
-            .await
-            .context("Failed to read index.json")?;
-        serde_json::from_str(&data).context("Failed to parse index.json")
+        let mut index = self.load_stored_index().await?;
+        self.merge_download_counts(&mut index).await?;
+        Ok(index)
+    }
+
+    async fn load_stored_index(&self) -> Result<RegistryIndex> {
+        if let Some(index) = self.cache.pending.lock().unwrap().clone() {
+            return Ok(index);
+        }
+        if let Some(index) = self.cache.loaded.lock().unwrap().as_ref() {
+            return Ok(index.to_index());
+        }
+        let path = self.resolve(self.root.join("index.json"));
+        let data = fs::read_to_string(&path).await;
+        let parsed = match &data {
+            Ok(data) => serde_json::from_str(data).context("Failed to parse index.json"),
+            Err(e) => Err(anyhow::anyhow!("Failed to read index.json: {}", e)),
+        };
+        let Err(e) = parsed else {
+            self.cache.skipped.store(0, Ordering::Relaxed);
+            return parsed;
+        };
+        // A hand edit gone wrong usually breaks one entry, not the whole file
+        let lenient = data.as_deref().ok().map(parse_index_lenient);
+        if let Some(Ok((index, skipped))) = lenient {
+            tracing::error!(
+                skipped,
+                "{:#}; serving the index without its malformed entries",
+                e
+            );
+            self.cache.skipped.store(skipped, Ordering::Relaxed);
+            return Ok(index);
+        }
+        self.cache.skipped.store(0, Ordering::Relaxed);
+        let backup = self.resolve(self.root.join("index.json.bak"));
+        let Ok(data) = fs::read_to_string(&backup).await else {
+            return Err(e);
+        };
+        let Ok(index) = serde_json::from_str(&data) else {
+            return Err(e);
+        };
+        tracing::warn!("{:#}; using index.json.bak from before the last save", e);
+        Ok(index)
     }
 
     /// Save the registry index.
+    ///
+    /// The file is replaced atomically, and the previous one kept as `index.json.bak` for
+    /// [`Self::load_index`] to fall back to.
+    ///
+    /// Callers doing a read-modify-write should hold [`Self::lock_index`] across both steps.
+    ///
+    /// Fails while the index on disk has malformed entries that [`Self::load_index`]
+    /// skipped, since writing would lose them; fix or remove them by hand first.
+    ///
+    /// With [`StorageOptions::index_flush_delay`] set, the write is deferred and
+    /// coalesced with any other changes made before the delay expires.
     pub async fn save_index(&self, index: &RegistryIndex) -> Result<()> {
+        let skipped = self.cache.skipped.load(Ordering::Relaxed);
+        if skipped > 0 {
+            anyhow::bail!(
+                "index.json has {} malformed entries; fix them before changing the registry",
+                skipped
+            );
+        }
+        self.record_change();
+        let path = self.root.join("index.json");
+        if let Some(loaded) = self.cache.loaded.lock().unwrap().as_mut() {
+            *loaded = InternedIndex::new(index.clone());
+        }
+        let Some(delay) = self.options.index_flush_delay else {
+            let index = index.clone();
+            tokio::task::spawn_blocking(move || replace_index(&path, &index)).await??;
+            self.cache.writes.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        };
+
+        *self.cache.pending.lock().unwrap() = Some(index.clone());
+        if !self.cache.flush_scheduled.swap(true, Ordering::AcqRel) {
+            let cache = self.cache.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                cache.flush_scheduled.store(false, Ordering::Release);
+                let result = tokio::task::spawn_blocking(move || cache.flush(&path)).await;
+                if let Ok(Err(e)) = result {
+                    tracing::warn!("Failed to flush index.json: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// A token that changes whenever the index as served may have: it was saved, a
+    /// version was yanked, or localized descriptions or download counts changed.
+    ///
+    /// Far cheaper than [`Self::load_index`], so responses built from the index can be
+    /// revalidated before doing the work. Changes made by other processes sharing the
+    /// data directory show up through the files' modification times, except download
+    /// counts, which live in a file per id.
+    pub async fn revision(&self) -> String {
+        let mut revision = self.cache.changes.load(Ordering::Relaxed).to_string();
+        for file in ["index.json", "yanked.json", "descriptions.json"] {
+            let mtime = fs::metadata(self.resolve(self.root.join(file)))
+                .await
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_nanos());
+            revision.push_str(&format!("-{}", mtime));
+        }
+        revision
+    }
+
+    /// Note a change to what the index serves, for [`Self::revision`].
+    pub(crate) fn record_change(&self) {
+        self.cache.changes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Load the index and build the reverse dependency map up front, so the first
+    /// requests after startup don't pay for it.
+    ///
+    /// With [`StorageOptions::cache_index`] the index then stays in memory.
+    pub async fn warm(&self) -> Result<()> {
+        let started = std::time::Instant::now();
+        *self.cache.loaded.lock().unwrap() = None;
+        let index = self.load_index().await?;
+        let (packages, plugins) = (index.packages.len(), index.plugins.len());
+        if self.options.cache_index {
+            *self.cache.loaded.lock().unwrap() = Some(InternedIndex::new(index));
+        }
+        let dependencies: usize = self.build_dependents().await?.values().map(Vec::len).sum();
+        tracing::info!(
+            packages,
+            plugins,
+            dependencies,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Registry caches warmed"
+        );
+        Ok(())
+    }
+
+    /// Write any pending coalesced index changes to disk now.
+    pub async fn flush_index(&self) -> Result<()> {
+        let cache = self.cache.clone();
         let path = self.root.join("index.json");
-        let json = serde_json::to_string_pretty(index)?;
-        fs::write(&path, json).await?;
+        tokio::task::spawn_blocking(move || cache.flush(&path)).await?
+    }
+
+    /// Load the index keeping only entries updated at or after `since` (unix seconds).
+    ///
+    /// `updated_at` still reflects the whole index so clients can advance their cursor.
+    /// Entries last written before per-entry times were recorded are treated as time 0.
+    pub async fn load_index_since(&self, since: u64) -> Result<RegistryIndex> {
+        let mut index = self.load_index().await?;
+        let times = self.load_entry_times().await?;
+        let updated =
+            |map: &BTreeMap<String, u64>, id: &str| map.get(id).copied().unwrap_or(0) >= since;
+        index.packages.retain(|p| updated(&times.packages, &p.id));
+        index.plugins.retain(|p| updated(&times.plugins, &p.id));
+        Ok(index)
+    }
+
+    async fn load_entry_times(&self) -> Result<EntryTimes> {
+        let path = self.resolve(self.root.join("updated.json"));
+        if !path.exists() {
+            return Ok(EntryTimes::default());
+        }
+        let data = fs::read_to_string(&path).await?;
+        serde_json::from_str(&data).context("Failed to parse updated.json")
+    }
+
+    /// Stamp an index entry as updated now. Callers must hold [`Self::lock_index`].
+    pub(crate) async fn touch_entry(&self, kind: &str, id: &str, at: u64) -> Result<()> {
+        self.record_entry_time(kind, id, at, false).await
+    }
+
+    /// Like [`Self::touch_entry`], also recording `at` as when the latest version was published.
+    pub(crate) async fn touch_latest(&self, kind: &str, id: &str, at: u64) -> Result<()> {
+        self.record_entry_time(kind, id, at, true).await
+    }
+
+    async fn record_entry_time(&self, kind: &str, id: &str, at: u64, latest: bool) -> Result<()> {
+        let mut times = self.load_entry_times().await?;
+        let (map, latest_map) = match kind {
+            "packages" => (&mut times.packages, &mut times.latest_packages),
+            _ => (&mut times.plugins, &mut times.latest_plugins),
+        };
+        map.insert(id.to_string(), at);
+        if latest {
+            latest_map.insert(id.to_string(), at);
+        }
+        let json = serde_json::to_string_pretty(&times)?;
+        write_atomic(&self.root.join("updated.json"), json.as_bytes()).await?;
         Ok(())
     }
 
+    /// Serialize `index` for clients, adding each entry's `latest_published_at`.
+    ///
+    /// Entries whose latest version predates this being recorded get 0.
+    pub async fn index_json(&self, index: &RegistryIndex) -> Result<Vec<u8>> {
+        let times = self.load_entry_times().await?;
+        let mut value = serde_json::to_value(index)?;
+        for (kind, published) in [
+            ("packages", &times.latest_packages),
+            ("plugins", &times.latest_plugins),
+        ] {
+            for entry in value[kind].as_array_mut().into_iter().flatten() {
+                let at = entry["id"]
+                    .as_str()
+                    .and_then(|id| published.get(id))
+                    .copied()
+                    .unwrap_or(0);
+                entry["latest_published_at"] = at.into();
+            }
+        }
+        Ok(serde_json::to_vec(&value)?)
+    }
+
+    /// Take an exclusive advisory lock guarding index read-modify-write cycles.
+    ///
+    /// Writers in this process first take an async mutex, so waiting doesn't tie up
+    /// a blocking thread each. The lock itself is a `flock` on `index.lock`, so it also
+    /// serializes other processes sharing the data directory. Both are released when
+    /// the returned guard is dropped.
+    pub async fn lock_index(&self) -> Result<IndexLock> {
+        let guard = self.index_mutex.clone().lock_owned().await;
+        let path = self.root.join("index.lock");
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
+                .context("Failed to open index.lock")?;
+            file.lock_exclusive().context("Failed to lock index.lock")?;
+            Ok(IndexLock {
+                file,
+                _guard: guard,
+            })
+        })
+        .await?
+    }
+
     // === Package Operations ===
 
     /// Get package directory path.
@@ -70,11 +668,20 @@ impl RegistryStorage {
 
     /// Get package info for a specific version.
     pub async fn get_package_info(&self, id: &str, version: &str) -> Result<PackageInfo> {
-        let path = self.package_version_dir(id, version).join("info.json");
+        let path = self.resolve(self.package_version_dir(id, version).join("info.json"));
         let data = fs::read_to_string(&path).await?;
         serde_json::from_str(&data).context("Failed to parse package info")
     }
 
+    /// Every published version of a package, newest first.
+    ///
+    /// Versions that aren't valid semver follow the valid ones in string order.
+    pub async fn list_package_versions(&self, id: &str) -> Result<Vec<String>> {
+        let mut versions = self.list_versions("packages", id).await?;
+        versions.sort_by(|a, b| newest_first(a, b));
+        Ok(versions)
+    }
+
     /// Get latest package version.
     pub async fn get_package_latest(&self, id: &str) -> Result<PackageInfo> {
         let index = self.load_index().await?;
@@ -83,16 +690,56 @@ impl RegistryStorage {
             .iter()
             .find(|p| p.id == id)
             .context("Package not found")?;
+        anyhow::ensure!(
+            !self
+                .is_yanked("packages", id, &entry.latest_version)
+                .await?,
+            "Every version of {} is yanked",
+            id
+        );
         self.get_package_info(id, &entry.latest_version).await
     }
 
+    /// Latest info of each package in `ids`, reading the index once. Ids that aren't
+    /// published, or whose every version is yanked, are left out.
+    pub async fn get_packages_latest(
+        &self,
+        ids: &[String],
+    ) -> Result<BTreeMap<String, PackageInfo>> {
+        let index = self.load_index().await?;
+        let yanked = self.load_yanked().await?;
+        let ids: BTreeSet<&str> = ids.iter().map(String::as_str).collect();
+        let mut infos = BTreeMap::new();
+        for entry in index
+            .packages
+            .iter()
+            .filter(|p| ids.contains(p.id.as_str()))
+        {
+            if yanked.contains("packages", &entry.id, &entry.latest_version) {
+                continue;
+            }
+            let info = self
+                .get_package_info(&entry.id, &entry.latest_version)
+                .await?;
+            infos.insert(entry.id.clone(), info);
+        }
+        Ok(infos)
+    }
+
     /// Get package artifact path.
+    ///
+    /// The platform is normalized, so `Windows-X86_64` resolves to `windows-x86_64`.
     pub fn package_artifact_path(&self, id: &str, version: &str, platform: &str) -> PathBuf {
-        self.package_version_dir(id, version)
-            .join(format!("{}.tar.gz", platform))
+        self.resolve(
+            self.package_version_dir(id, version)
+                .join(format!("{}.tar.gz", normalize_platform(platform))),
+        )
     }
 
     /// Publish a package version.
+    ///
+    /// Fails with [`ArtifactExists`] if the version already has an artifact for `platform`;
+    /// other platforms can still be added to the version.
     #[allow(clippy::too_many_arguments)]
     pub async fn publish_package(
         &self,
@@ -104,24 +751,97 @@ impl RegistryStorage {
         data: &[u8],
         author: &str,
         tags: Vec<String>,
+        changelog: Option<&str>,
     ) -> Result<()> {
-        let version_dir = self.package_version_dir(id, version);
-        fs::create_dir_all(&version_dir).await?;
+        self.store_package(
+            id,
+            name,
+            description,
+            version,
+            platform,
+            data,
+            author,
+            tags,
+            changelog,
+            false,
+            &PublishExtras::default(),
+        )
+        .await
+    }
 
-        // Calculate checksum
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        let checksum = hex::encode(hasher.finalize());
+    /// Publish a package version, replacing any existing artifact for the platform.
+    ///
+    /// A `changelog` replaces the version's existing one; `None` keeps it.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn replace_package(
+        &self,
+        id: &str,
+        name: &str,
+        description: &str,
+        version: &str,
+        platform: &str,
+        data: &[u8],
+        author: &str,
+        tags: Vec<String>,
+        changelog: Option<&str>,
+    ) -> Result<()> {
+        self.store_package(
+            id,
+            name,
+            description,
+            version,
+            platform,
+            data,
+            author,
+            tags,
+            changelog,
+            true,
+            &PublishExtras::default(),
+        )
+        .await
+    }
 
-        // Write artifact
-        let artifact_path = version_dir.join(format!("{}.tar.gz", platform));
-        let mut file = fs::File::create(&artifact_path).await?;
-        file.write_all(data).await?;
+    /// Write a package artifact and record it in `info.json` and the index.
+    ///
+    /// Everything from the existence check to the index update happens under
+    /// [`Self::lock_index`], so concurrent publishes of one version can't drop each
+    /// other's platforms or both pass the [`StorageOptions::max_platforms`] check.
+    /// `overwrite` replaces an existing artifact for the platform instead of failing
+    /// with [`ArtifactExists`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn store_package(
+        &self,
+        id: &str,
+        name: &str,
+        description: &str,
+        version: &str,
+        platform: &str,
+        data: &[u8],
+        author: &str,
+        tags: Vec<String>,
+        changelog: Option<&str>,
+        overwrite: bool,
+        extras: &PublishExtras,
+    ) -> Result<()> {
+        check_id(id)?;
+        check_version(version)?;
+        let platform = normalize_platform(platform);
+        let platform = platform.as_str();
+        let _lock = self.lock_index().await?;
+        if !overwrite && self.package_artifact_path(id, version, platform).exists() {
+            return Err(ArtifactExists {
+                platform: platform.to_string(),
+            }
+            .into());
+        }
+        let version_dir = self.package_version_dir(id, version);
+        fs::create_dir_all(&version_dir).await?;
 
         // Load or create package info
         let info_path = version_dir.join("info.json");
-        let mut info = if info_path.exists() {
-            let data = fs::read_to_string(&info_path).await?;
+        let existing = self.resolve(info_path.clone());
+        let mut info = if existing.exists() {
+            let data = fs::read_to_string(&existing).await?;
             serde_json::from_str::<PackageInfo>(&data)?
         } else {
             PackageInfo {
@@ -132,6 +852,29 @@ impl RegistryStorage {
                 changelog: None,
             }
         };
+        if let Some(published_at) = extras.published_at {
+            info.published_at = published_at;
+        }
+
+        if !info.platforms.iter().any(|p| p.platform == platform)
+            && info.platforms.len() >= self.options.max_platforms
+        {
+            return Err(TooManyPlatforms {
+                max: self.options.max_platforms,
+            }
+            .into());
+        }
+
+        // Calculate checksum
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        let checksum = hex::encode(hasher.finalize());
+
+        // Write artifact
+        let artifact_path = version_dir.join(format!("{}.tar.gz", platform));
+        let mut file = fs::File::create(&artifact_path).await?;
+        file.write_all(data).await?;
+        file.flush().await?;
 
         // Add platform build
         let build = PlatformBuild {
@@ -139,7 +882,7 @@ impl RegistryStorage {
             download_url: format!("/v1/packages/{}/{}/{}.tar.gz", id, version, platform),
             size_bytes: data.len() as u64,
             checksum,
-            signature: None,
+            signature: extras.signature.clone(),
         };
 
         // Update or add platform
@@ -148,10 +891,13 @@ impl RegistryStorage {
         } else {
             info.platforms.push(build);
         }
+        if let Some(changelog) = changelog {
+            info.changelog = Some(changelog.to_string());
+        }
 
         // Save info
         let json = serde_json::to_string_pretty(&info)?;
-        fs::write(&info_path, json).await?;
+        write_atomic(&info_path, json.as_bytes()).await?;
 
         // Update index
         self.update_package_index(id, name, description, version, author, tags)
@@ -160,7 +906,7 @@ impl RegistryStorage {
         Ok(())
     }
 
-    /// Update package entry in index.
+    /// Update package entry in index. Callers hold [`Self::lock_index`].
     async fn update_package_index(
         &self,
         id: &str,
@@ -171,16 +917,22 @@ impl RegistryStorage {
         tags: Vec<String>,
     ) -> Result<()> {
         let mut index = self.load_index().await?;
+        let yanked = self.load_yanked().await?;
 
+        let mut advanced = true;
         if let Some(entry) = index.packages.iter_mut().find(|p| p.id == id) {
             // Update existing
-            if semver_greater(version, &entry.latest_version) {
+            advanced = yanked.supersedes("packages", id, version, &entry.latest_version);
+            if advanced {
                 entry.latest_version = version.to_string();
             }
-            entry.name = name.to_string();
-            entry.description = description.to_string();
-            entry.author = author.to_string();
-            entry.tags = tags;
+            // An older version's metadata mustn't replace the latest's
+            if entry.latest_version == version {
+                entry.name = name.to_string();
+                entry.description = description.to_string();
+                entry.author = author.to_string();
+                entry.tags = tags;
+            }
         } else {
             // Add new
             index.packages.push(PackageEntry {
@@ -197,7 +949,13 @@ impl RegistryStorage {
         }
 
         index.updated_at = now_unix();
-        self.save_index(&index).await
+        if advanced {
+            self.touch_latest("packages", id, index.updated_at).await?;
+        } else {
+            self.touch_entry("packages", id, index.updated_at).await?;
+        }
+        self.save_index(&index).await?;
+        self.clear_tombstone("packages", id).await
     }
 
     // === Plugin Operations ===
@@ -208,13 +966,13 @@ impl RegistryStorage {
     }
 
     /// Get plugin version directory path.
-    fn plugin_version_dir(&self, id: &str, version: &str) -> PathBuf {
+    pub(crate) fn plugin_version_dir(&self, id: &str, version: &str) -> PathBuf {
         self.plugin_dir(id).join(version)
     }
 
     /// Get plugin info for a specific version.
     pub async fn get_plugin_info(&self, id: &str, version: &str) -> Result<PluginInfo> {
-        let path = self.plugin_version_dir(id, version).join("info.json");
+        let path = self.resolve(self.plugin_version_dir(id, version).join("info.json"));
         let data = fs::read_to_string(&path).await?;
         let mut info: PluginInfo =
             serde_json::from_str(&data).context("Failed to parse plugin info")?;
@@ -222,6 +980,15 @@ impl RegistryStorage {
         Ok(info)
     }
 
+    /// Read a plugin version's `info.json` exactly as stored on disk.
+    ///
+    /// Unlike [`get_plugin_info`](Self::get_plugin_info) the `web_ui` field is not
+    /// injected, so mirrors can copy the file byte for byte.
+    pub async fn get_plugin_info_raw(&self, id: &str, version: &str) -> Result<Vec<u8>> {
+        let path = self.resolve(self.plugin_version_dir(id, version).join("info.json"));
+        Ok(fs::read(&path).await?)
+    }
+
     /// Get latest plugin version.
     pub async fn get_plugin_latest(&self, id: &str) -> Result<PluginInfo> {
         let index = self.load_index().await?;
@@ -230,16 +997,105 @@ impl RegistryStorage {
             .iter()
             .find(|p| p.id == id)
             .context("Plugin not found")?;
+        anyhow::ensure!(
+            !self.is_yanked("plugins", id, &entry.latest_version).await?,
+            "Every version of {} is yanked",
+            id
+        );
         self.get_plugin_info(id, &entry.latest_version).await
     }
 
+    /// Latest info of each plugin in `ids`, reading the index once. Ids that aren't
+    /// published, or whose every version is yanked, are left out.
+    pub async fn get_plugins_latest(&self, ids: &[String]) -> Result<BTreeMap<String, PluginInfo>> {
+        let index = self.load_index().await?;
+        let yanked = self.load_yanked().await?;
+        let ids: BTreeSet<&str> = ids.iter().map(String::as_str).collect();
+        let mut infos = BTreeMap::new();
+        for entry in index.plugins.iter().filter(|p| ids.contains(p.id.as_str())) {
+            if yanked.contains("plugins", &entry.id, &entry.latest_version) {
+                continue;
+            }
+            let info = self
+                .get_plugin_info(&entry.id, &entry.latest_version)
+                .await?;
+            infos.insert(entry.id.clone(), info);
+        }
+        Ok(infos)
+    }
+
+    /// Every published version of a plugin, newest first, with whether it ships a web UI.
+    ///
+    /// Version directories without an `info.json` are skipped.
+    pub async fn list_plugin_versions(&self, id: &str) -> Result<Vec<PluginVersionSummary>> {
+        let mut versions = self.list_versions("plugins", id).await?;
+        versions.sort_by(|a, b| newest_first(a, b));
+        Ok(versions
+            .into_iter()
+            .map(|version| PluginVersionSummary {
+                has_web_ui: self.has_plugin_web_ui(id, &version),
+                version,
+            })
+            .collect())
+    }
+
+    /// Versions of a plugin whose `info.json` lists `platform`, newest first.
+    pub async fn plugin_versions_with_platform(
+        &self,
+        id: &str,
+        platform: &str,
+    ) -> Result<Vec<String>> {
+        let platform = normalize_platform(platform);
+        let mut versions = Vec::new();
+        for version in self.list_versions("plugins", id).await? {
+            let info = self.get_plugin_info(id, &version).await?;
+            if info.platforms.iter().any(|b| b.platform == platform) {
+                versions.push(version);
+            }
+        }
+        versions.sort_by(|a, b| newest_first(a, b));
+        Ok(versions)
+    }
+
+    /// Whether the artifact at `path` still hashes to `checksum`.
+    ///
+    /// A match is remembered against the file's size and mtime, so the file is only hashed
+    /// again once it changes.
+    pub async fn artifact_matches_checksum(&self, path: &Path, checksum: &str) -> Result<bool> {
+        let metadata = fs::metadata(path).await?;
+        let stamp = (metadata.len(), metadata.modified()?);
+        let cached = self.verified.lock().unwrap().get(path).cloned();
+        if let Some((len, mtime, verified)) = cached {
+            if (len, mtime) == stamp && verified == checksum {
+                return Ok(true);
+            }
+        }
+
+        let owned = path.to_path_buf();
+        let actual = tokio::task::spawn_blocking(move || sha256_file(owned)).await??;
+        let mut verified = self.verified.lock().unwrap();
+        if actual != checksum {
+            verified.remove(path);
+            return Ok(false);
+        }
+        verified.insert(path.to_path_buf(), (stamp.0, stamp.1, actual));
+        Ok(true)
+    }
+
     /// Get plugin artifact path.
+    ///
+    /// The platform is normalized, so `Windows-X86_64` resolves to `windows-x86_64`.
     pub fn plugin_artifact_path(&self, id: &str, version: &str, platform: &str) -> PathBuf {
-        self.plugin_version_dir(id, version)
-            .join(format!("{}.tar.gz", platform))
+        self.resolve(
+            self.plugin_version_dir(id, version)
+                .join(format!("{}.tar.gz", normalize_platform(platform))),
+        )
     }
 
     /// Publish a plugin version.
+    ///
+    /// Fails with [`ArtifactExists`] if the version already has an artifact for `platform`;
+    /// other platforms can still be added to the version.
     #[allow(clippy::too_many_arguments)]
     pub async fn publish_plugin(
         &self,
@@ -253,23 +1109,98 @@ impl RegistryStorage {
         author: &str,
         tags: Vec<String>,
     ) -> Result<()> {
-        let version_dir = self.plugin_version_dir(id, version);
-        fs::create_dir_all(&version_dir).await?;
-
-        // Calculate checksum
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        let checksum = hex::encode(hasher.finalize());
+        self.store_plugin(
+            id,
+            name,
+            description,
+            plugin_type,
+            version,
+            platform,
+            data,
+            author,
+            tags,
+            false,
+            &PublishExtras::default(),
+        )
+        .await
+    }
 
-        // Write artifact
-        let artifact_path = version_dir.join(format!("{}.tar.gz", platform));
-        let mut file = fs::File::create(&artifact_path).await?;
-        file.write_all(data).await?;
+    /// Publish a plugin version, replacing any existing artifact for the platform.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn replace_plugin(
+        &self,
+        id: &str,
+        name: &str,
+        description: &str,
+        plugin_type: &str,
+        version: &str,
+        platform: &str,
+        data: &[u8],
+        author: &str,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        self.store_plugin(
+            id,
+            name,
+            description,
+            plugin_type,
+            version,
+            platform,
+            data,
+            author,
+            tags,
+            true,
+            &PublishExtras::default(),
+        )
+        .await
+    }
 
-        // Load or create plugin info
+    /// Write a plugin artifact and record it in `info.json` and the index, all under
+    /// [`Self::lock_index`] like [`Self::store_package`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn store_plugin(
+        &self,
+        id: &str,
+        name: &str,
+        description: &str,
+        plugin_type: &str,
+        version: &str,
+        platform: &str,
+        data: &[u8],
+        author: &str,
+        tags: Vec<String>,
+        overwrite: bool,
+        extras: &PublishExtras,
+    ) -> Result<()> {
+        check_id(id)?;
+        check_version(version)?;
+        let platform = normalize_platform(platform);
+        let platform = platform.as_str();
+        let dependencies = match &extras.dependencies {
+            Some(dependencies) => {
+                for dependency in dependencies {
+                    dependency.validate()?;
+                }
+                dependencies.clone()
+            }
+            None => self.get_plugin_dependencies(id, version).await?,
+        };
+        self.check_dependencies(&dependencies).await?;
+        let _lock = self.lock_index().await?;
+        if !overwrite && self.plugin_artifact_path(id, version, platform).exists() {
+            return Err(ArtifactExists {
+                platform: platform.to_string(),
+            }
+            .into());
+        }
+        let version_dir = self.plugin_version_dir(id, version);
+        fs::create_dir_all(&version_dir).await?;
+
+        // Load or create plugin info
         let info_path = version_dir.join("info.json");
-        let mut info = if info_path.exists() {
-            let data = fs::read_to_string(&info_path).await?;
+        let existing = self.resolve(info_path.clone());
+        let mut info = if existing.exists() {
+            let data = fs::read_to_string(&existing).await?;
             serde_json::from_str::<PluginInfo>(&data)?
         } else {
             PluginInfo {
@@ -280,6 +1211,29 @@ impl RegistryStorage {
                 web_ui: None,
             }
         };
+        if let Some(published_at) = extras.published_at {
+            info.published_at = published_at;
+        }
+
+        if !info.platforms.iter().any(|p| p.platform == platform)
+            && info.platforms.len() >= self.options.max_platforms
+        {
+            return Err(TooManyPlatforms {
+                max: self.options.max_platforms,
+            }
+            .into());
+        }
+
+        // Calculate checksum
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        let checksum = hex::encode(hasher.finalize());
+
+        // Write artifact
+        let artifact_path = version_dir.join(format!("{}.tar.gz", platform));
+        let mut file = fs::File::create(&artifact_path).await?;
+        file.write_all(data).await?;
+        file.flush().await?;
 
         // Add platform build
         let build = PlatformBuild {
@@ -287,7 +1241,7 @@ impl RegistryStorage {
             download_url: format!("/v1/plugins/{}/{}/{}.tar.gz", id, version, platform),
             size_bytes: data.len() as u64,
             checksum,
-            signature: None,
+            signature: extras.signature.clone(),
         };
 
         // Update or add platform
@@ -299,7 +1253,29 @@ impl RegistryStorage {
 
         // Save info
         let json = serde_json::to_string_pretty(&info)?;
-        fs::write(&info_path, json).await?;
+        write_atomic(&info_path, json.as_bytes()).await?;
+
+        // Before the index update, so the version is never listed without them
+        if let Some(dependencies) = &extras.dependencies {
+            self.set_plugin_dependencies(id, version, dependencies)
+                .await?;
+        }
+        if let Some(changelog) = &extras.changelog {
+            let meta = PluginVersionMeta {
+                changelog: Some(changelog.clone()),
+                ..self
+                    .get_plugin_meta(id, version)
+                    .await?
+                    .unwrap_or_else(|| PluginVersionMeta {
+                        name: name.to_string(),
+                        description: description.to_string(),
+                        plugin_type: plugin_type.to_string(),
+                        author: author.to_string(),
+                        ..Default::default()
+                    })
+            };
+            self.write_plugin_meta(&version_dir, &meta).await?;
+        }
 
         // Update index
         self.update_plugin_index(id, name, description, plugin_type, version, author, tags)
@@ -308,7 +1284,7 @@ impl RegistryStorage {
         Ok(())
     }
 
-    /// Update plugin entry in index.
+    /// Update plugin entry in index. Callers hold [`Self::lock_index`].
     #[allow(clippy::too_many_arguments)]
     async fn update_plugin_index(
         &self,
@@ -321,17 +1297,23 @@ impl RegistryStorage {
         tags: Vec<String>,
     ) -> Result<()> {
         let mut index = self.load_index().await?;
+        let yanked = self.load_yanked().await?;
 
+        let mut advanced = true;
         if let Some(entry) = index.plugins.iter_mut().find(|p| p.id == id) {
             // Update existing
-            if semver_greater(version, &entry.latest_version) {
+            advanced = yanked.supersedes("plugins", id, version, &entry.latest_version);
+            if advanced {
                 entry.latest_version = version.to_string();
             }
-            entry.name = name.to_string();
-            entry.description = description.to_string();
-            entry.plugin_type = plugin_type.to_string();
-            entry.author = author.to_string();
-            entry.tags = tags;
+            // An older version's metadata mustn't replace the latest's
+            if entry.latest_version == version {
+                entry.name = name.to_string();
+                entry.description = description.to_string();
+                entry.plugin_type = plugin_type.to_string();
+                entry.author = author.to_string();
+                entry.tags = tags;
+            }
         } else {
             // Add new
             index.plugins.push(PluginEntry {
@@ -348,37 +1330,202 @@ impl RegistryStorage {
         }
 
         index.updated_at = now_unix();
-        self.save_index(&index).await
+        if advanced {
+            self.touch_latest("plugins", id, index.updated_at).await?;
+        } else {
+            self.touch_entry("plugins", id, index.updated_at).await?;
+        }
+        self.save_index(&index).await?;
+        self.clear_tombstone("plugins", id).await?;
+        // A new latest version may declare different dependencies
+        self.dependents.invalidate();
+        Ok(())
     }
 
-    // === Web UI Operations ===
+    // === Deletion ===
+
+    /// Remove a plugin version's directory; `false` if the version doesn't exist.
+    ///
+    /// Deleting the latest version makes the newest remaining one latest. Deleting the
+    /// only version removes the plugin from the index and leaves a tombstone. Versions
+    /// served from the read root can't be deleted.
+    pub async fn delete_plugin_version(&self, id: &str, version: &str) -> Result<bool> {
+        // Anything else can't name a version directory, and `..` would escape it
+        if !is_valid_id(id) || !is_valid_version(version) {
+            return Ok(false);
+        }
 
-    /// Store the single JS entry point for a plugin's web UI.
-    pub async fn publish_plugin_web_ui(
+        let _lock = self.lock_index().await?;
+        let dir = self.plugin_version_dir(id, version);
+        if !dir.join("info.json").exists() {
+            anyhow::ensure!(
+                !self.resolve(dir.join("info.json")).exists(),
+                "Plugin {} {} is in the read root and can't be deleted",
+                id,
+                version
+            );
+            return Ok(false);
+        }
+        let mut remaining = self.list_versions("plugins", id).await?;
+        remaining.retain(|v| v != version);
+        // A version published again under the same number starts out unyanked
+        let mut yanked = self.load_yanked().await?;
+        if yanked.set("plugins", id, version, false)? {
+            self.save_yanked(&yanked).await?;
+        }
+        let mut index = self.load_index().await?;
+        index.updated_at = now_unix();
+        match yanked.newest("plugins", id, &remaining) {
+            Some(newest) => {
+                if let Some(entry) = index.plugins.iter_mut().find(|p| p.id == id) {
+                    if entry.latest_version == version {
+                        entry.latest_version = newest.to_string();
+                    }
+                }
+                self.touch_entry("plugins", id, index.updated_at).await?;
+                self.save_index(&index).await?;
+            }
+            None => {
+                index.plugins.retain(|p| p.id != id);
+                self.save_index(&index).await?;
+                self.add_tombstone("plugins", id).await?;
+            }
+        }
+        // Only once the index no longer points at it
+        fs::remove_dir_all(&dir).await?;
+        // The deleted version may have been the one declaring dependencies
+        self.dependents.invalidate();
+        Ok(true)
+    }
+
+    // === Metadata-only Operations ===
+
+    /// Register a plugin version from metadata alone.
+    ///
+    /// Creates the version's `info.json` with no platforms (existing builds are kept) and
+    /// stores `meta` alongside it. The index is only updated when the version already has
+    /// artifacts or [`StorageOptions::artifactless_latest`] is set, so a version without
+    /// builds never becomes `latest_version` by default.
+    pub async fn publish_plugin_meta(
         &self,
         id: &str,
         version: &str,
-        data: &[u8],
-    ) -> Result<()> {
+        meta: &PluginVersionMeta,
+    ) -> Result<PluginInfo> {
+        check_id(id)?;
+        check_version(version)?;
+        let dependencies = Dependency::from_requirements(&meta.dependencies);
+        for dependency in &dependencies {
+            dependency.validate()?;
+        }
+        self.check_dependencies(&dependencies).await?;
+        // Held from the existence check on, so a concurrent artifact publish can't
+        // lose its platform to the empty info written here
+        let _lock = self.lock_index().await?;
         let version_dir = self.plugin_version_dir(id, version);
         fs::create_dir_all(&version_dir).await?;
 
-        // Write JS file
-        let js_path = version_dir.join("web.js");
-        let mut file = fs::File::create(&js_path).await?;
-        file.write_all(data).await?;
+        let info_path = version_dir.join("info.json");
+        let existing = self.resolve(info_path.clone());
+        let info = if existing.exists() {
+            let data = fs::read_to_string(&existing).await?;
+            serde_json::from_str::<PluginInfo>(&data)?
+        } else {
+            let info = PluginInfo {
+                id: id.to_string(),
+                version: version.to_string(),
+                platforms: Vec::new(),
+                published_at: now_unix(),
+                web_ui: None,
+            };
+            let json = serde_json::to_string_pretty(&info)?;
+            write_atomic(&info_path, json.as_bytes()).await?;
+            info
+        };
+
+        self.write_plugin_meta(&version_dir, meta).await?;
+        self.set_plugin_dependencies(id, version, &dependencies)
+            .await?;
+
+        if !info.platforms.is_empty() || self.options.artifactless_latest {
+            self.set_plugin_descriptions(id, &meta.descriptions).await?;
+            self.update_plugin_index(
+                id,
+                &meta.name,
+                &meta.description,
+                &meta.plugin_type,
+                version,
+                &meta.author,
+                meta.tags.clone(),
+            )
+            .await?;
+        }
+
+        Ok(info)
+    }
+
+    /// Write `meta.json`, leaving out dependencies, which live in `dependencies.json`.
+    async fn write_plugin_meta(&self, version_dir: &Path, meta: &PluginVersionMeta) -> Result<()> {
+        let meta = PluginVersionMeta {
+            dependencies: BTreeMap::new(),
+            ..meta.clone()
+        };
+        let json = serde_json::to_string_pretty(&meta)?;
+        write_atomic(&version_dir.join("meta.json"), json.as_bytes()).await
+    }
+
+    /// Get metadata registered through [`Self::publish_plugin_meta`], if any.
+    pub async fn get_plugin_meta(
+        &self,
+        id: &str,
+        version: &str,
+    ) -> Result<Option<PluginVersionMeta>> {
+        let path = self.resolve(self.plugin_version_dir(id, version).join("meta.json"));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read_to_string(&path).await?;
+        Ok(Some(
+            serde_json::from_str(&data).context("Failed to parse plugin meta")?,
+        ))
+    }
+
+    // === Web UI Operations ===
+
+    /// Store the single JS entry point for a plugin's web UI.
+    ///
+    /// Fails with [`WebUiExists`] if the version already has one and
+    /// [`StorageOptions::immutable_web_ui`] is set.
+    pub async fn publish_plugin_web_ui(&self, id: &str, version: &str, data: &[u8]) -> Result<()> {
+        check_id(id)?;
+        check_version(version)?;
+        if self.options.immutable_web_ui && self.has_plugin_web_ui(id, version) {
+            return Err(WebUiExists.into());
+        }
+        self.replace_plugin_web_ui(id, version, data).await
+    }
+
+    /// Store a plugin's web UI, replacing any existing one regardless of policy.
+    pub async fn replace_plugin_web_ui(&self, id: &str, version: &str, data: &[u8]) -> Result<()> {
+        check_id(id)?;
+        check_version(version)?;
+        let version_dir = self.plugin_version_dir(id, version);
+        fs::create_dir_all(&version_dir).await?;
+
+        // Downloads are served with an immutable cache, so a reader must never see a
+        // partially written file. Metadata follows only once the JS is in place.
+        write_atomic(&version_dir.join("web.js"), data).await?;
 
-        // Write size metadata
         let meta = serde_json::json!({ "size_bytes": data.len() });
         let meta_path = version_dir.join("web_meta.json");
-        fs::write(&meta_path, serde_json::to_string_pretty(&meta)?).await?;
+        write_atomic(&meta_path, serde_json::to_string_pretty(&meta)?.as_bytes()).await?;
 
         Ok(())
     }
 
     /// Get the filesystem path to a plugin's web UI JS file.
     pub fn get_plugin_web_ui_path(&self, id: &str, version: &str) -> PathBuf {
-        self.plugin_version_dir(id, version).join("web.js")
+        self.resolve(self.plugin_version_dir(id, version).join("web.js"))
     }
 
     /// Check if a plugin version has a web UI.
@@ -387,7 +1534,7 @@ impl RegistryStorage {
     }
 
     /// Build WebUiMeta for a plugin version if web.js exists.
-    fn web_ui_meta(&self, id: &str, version: &str) -> Option<WebUiMeta> {
+    pub(crate) fn web_ui_meta(&self, id: &str, version: &str) -> Option<WebUiMeta> {
         let js_path = self.get_plugin_web_ui_path(id, version);
         if !js_path.exists() {
             return None;
@@ -399,39 +1546,190 @@ impl RegistryStorage {
         })
     }
 
-    /// Increment download counter.
-    pub async fn increment_downloads(&self, kind: &str, id: &str) -> Result<()> {
+    /// Recompute every package's `plugin_ids`/`plugin_count` from the plugins whose
+    /// `package_id` points at it.
+    ///
+    /// Returns the number of package entries that had drifted and were corrected.
+    pub async fn repair_package_links(&self) -> Result<usize> {
+        let _lock = self.lock_index().await?;
         let mut index = self.load_index().await?;
 
-        match kind {
-            "packages" => {
-                if let Some(entry) = index.packages.iter_mut().find(|p| p.id == id) {
-                    entry.downloads += 1;
-                }
+        let mut links: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for plugin in &index.plugins {
+            if let Some(package_id) = &plugin.package_id {
+                links
+                    .entry(package_id.clone())
+                    .or_default()
+                    .push(plugin.id.clone());
             }
-            "plugins" => {
-                if let Some(entry) = index.plugins.iter_mut().find(|p| p.id == id) {
-                    entry.downloads += 1;
-                }
+        }
+
+        let now = now_unix();
+        let mut corrected = Vec::new();
+        for package in &mut index.packages {
+            let mut plugin_ids = links.remove(&package.id).unwrap_or_default();
+            plugin_ids.sort();
+            let mut current = package.plugin_ids.clone();
+            current.sort();
+            if current != plugin_ids || package.plugin_count as usize != plugin_ids.len() {
+                package.plugin_count = plugin_ids.len() as u32;
+                package.plugin_ids = plugin_ids;
+                corrected.push(package.id.clone());
+            }
+        }
+
+        if !corrected.is_empty() {
+            index.updated_at = now;
+            self.save_index(&index).await?;
+            for id in &corrected {
+                self.touch_entry("packages", id, now).await?;
+            }
+        }
+        Ok(corrected.len())
+    }
+}
+
+impl Drop for RegistryStorage {
+    /// Persist coalesced index changes so none are lost when the storage goes away.
+    fn drop(&mut self) {
+        let _ = self.cache.flush(&self.root.join("index.json"));
+    }
+}
+
+/// Guard for the cross-process index lock; unlocks on drop.
+pub struct IndexLock {
+    file: std::fs::File,
+    _guard: tokio::sync::OwnedMutexGuard<()>,
+}
+
+impl Drop for IndexLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Write `data` to a temporary sibling of `path`, then rename it into place so
+/// concurrent readers see either the previous or the complete new content.
+pub(crate) async fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp = tmp_sibling(path)?;
+    let mut file = fs::File::create(&tmp).await?;
+    let written = async {
+        file.write_all(data).await?;
+        file.sync_all().await
+    }
+    .await;
+    drop(file);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp).await;
+        return Err(e.into());
+    }
+    fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// A temporary file name next to `path`, unique within this process.
+fn tmp_sibling(path: &Path) -> Result<PathBuf> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let file_name = path
+        .file_name()
+        .context("Path has no file name")?
+        .to_string_lossy();
+    Ok(path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+/// Parse `index.json` entry by entry, leaving out (and logging) packages and plugins
+/// that don't deserialize. Returns the index and how many entries were left out.
+fn parse_index_lenient(data: &str) -> Result<(RegistryIndex, usize)> {
+    let mut value: serde_json::Value = serde_json::from_str(data)?;
+    let Some(fields) = value.as_object_mut() else {
+        anyhow::bail!("index.json is not an object");
+    };
+    let mut take = |key: &str| match fields.insert(key.to_string(), serde_json::json!([])) {
+        Some(serde_json::Value::Array(entries)) => entries,
+        _ => Vec::new(),
+    };
+    let (packages, plugins) = (take("packages"), take("plugins"));
+    let mut index: RegistryIndex = serde_json::from_value(value)?;
+
+    let mut skipped = 0;
+    index.packages = parse_entries("package", packages, &mut skipped);
+    index.plugins = parse_entries("plugin", plugins, &mut skipped);
+    Ok((index, skipped))
+}
+
+fn parse_entries<T: serde::de::DeserializeOwned>(
+    kind: &str,
+    entries: Vec<serde_json::Value>,
+    skipped: &mut usize,
+) -> Vec<T> {
+    let mut parsed = Vec::new();
+    for entry in entries {
+        let id = entry.get("id").cloned();
+        match serde_json::from_value(entry) {
+            Ok(entry) => parsed.push(entry),
+            Err(e) => {
+                *skipped += 1;
+                tracing::warn!(kind, id = ?id, "Skipping malformed index entry: {}", e);
             }
-            _ => {}
         }
+    }
+    parsed
+}
 
-        self.save_index(&index).await
+/// Atomically replace `index.json` at `path`, copying the current file to
+/// `index.json.bak` first. Blocking, so it can run under the coalescing cache's lock.
+fn replace_index(path: &Path, index: &RegistryIndex) -> Result<()> {
+    use std::io::Write;
+
+    let json = serde_json::to_string_pretty(index)?;
+    let tmp = tmp_sibling(path)?;
+    let written = std::fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(json.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    if path.exists() {
+        std::fs::copy(path, path.with_file_name("index.json.bak"))?;
     }
+    std::fs::rename(&tmp, path)?;
+    Ok(())
 }
 
-fn now_unix() -> u64 {
+pub(crate) fn now_unix() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0)
 }
 
-fn semver_greater(a: &str, b: &str) -> bool {
+pub(crate) fn semver_greater(a: &str, b: &str) -> bool {
+    semver_cmp(a, b).is_gt()
+}
+
+/// Order versions by semver, falling back to string order when either fails to parse.
+pub(crate) fn semver_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    match (semver::Version::parse(a), semver::Version::parse(b)) {
+        (Ok(va), Ok(vb)) => va.cmp(&vb),
+        _ => a.cmp(b),
+    }
+}
+
+/// Sort order for version listings: semver descending, then non-semver names ascending.
+pub(crate) fn newest_first(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
     match (semver::Version::parse(a), semver::Version::parse(b)) {
-        (Ok(va), Ok(vb)) => va > vb,
-        _ => a > b,
+        (Ok(va), Ok(vb)) => vb.cmp(&va),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
     }
 }
 
@@ -461,6 +1759,76 @@ mod tests {
         (storage, tmp)
     }
 
+    #[tokio::test]
+    async fn test_warm_serves_index_from_memory() {
+        let (storage, tmp) = setup().await;
+        storage
+            .publish_plugin(
+                "adi.notes",
+                "Notes",
+                "Quick notes",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let meta = PluginVersionMeta {
+            name: "Notes".to_string(),
+            plugin_type: "core".to_string(),
+            author: "ADI Team".to_string(),
+            dependencies: BTreeMap::from([("adi.tasks".to_string(), "^1.0".to_string())]),
+            ..Default::default()
+        };
+        storage
+            .publish_plugin_meta("adi.notes", "1.0.0", &meta)
+            .await
+            .unwrap();
+
+        // A fresh instance, as after a restart
+        let options = StorageOptions {
+            cache_index: true,
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        storage.warm().await.unwrap();
+        std::fs::remove_file(tmp.path().join("index.json")).unwrap();
+        std::fs::remove_dir_all(tmp.path().join("plugins/adi.notes")).unwrap();
+
+        let index = storage.load_index().await.unwrap();
+        let terms = crate::search_terms("notes", 16);
+        let found: Vec<&str> = index
+            .plugins
+            .iter()
+            .filter(|p| crate::matches_terms([p.id.as_str(), &p.name, &p.description], &terms))
+            .map(|p| p.id.as_str())
+            .collect();
+        assert_eq!(found, vec!["adi.notes"]);
+        let dependents = storage.reverse_dependencies("adi.tasks").await.unwrap();
+        assert_eq!(dependents.len(), 1);
+        assert_eq!(dependents[0].id, "adi.notes");
+
+        // Saves keep the warmed copy current
+        storage
+            .publish_plugin(
+                "adi.files",
+                "Files",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        assert_eq!(storage.load_index().await.unwrap().plugins.len(), 3);
+    }
+
     #[tokio::test]
     async fn test_publish_web_ui_creates_file() {
         let (storage, _tmp) = setup().await;
@@ -491,6 +1859,85 @@ mod tests {
         assert_eq!(meta["size_bytes"], js.len() as u64);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_web_ui_republish_never_exposes_partial_file() {
+        let (storage, _tmp) = setup().await;
+        let storage = Arc::new(storage);
+        let old = vec![b'a'; 1 << 20];
+        let new = vec![b'b'; 1 << 20];
+        storage
+            .publish_plugin_web_ui("adi.tasks", "1.0.0", &old)
+            .await
+            .unwrap();
+
+        let writer = {
+            let storage = storage.clone();
+            let (old, new) = (old.clone(), new.clone());
+            tokio::spawn(async move {
+                for i in 0..20 {
+                    let data = if i % 2 == 0 { &new } else { &old };
+                    storage
+                        .publish_plugin_web_ui("adi.tasks", "1.0.0", data)
+                        .await
+                        .unwrap();
+                }
+            })
+        };
+
+        let path = storage.get_plugin_web_ui_path("adi.tasks", "1.0.0");
+        while !writer.is_finished() {
+            let read = fs::read(&path).await.unwrap();
+            assert!(read == old || read == new, "read {} bytes", read.len());
+        }
+        writer.await.unwrap();
+
+        let leftovers: Vec<_> = std::fs::read_dir(storage.plugin_version_dir("adi.tasks", "1.0.0"))
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_immutable_web_ui_rejects_overwrite() {
+        let tmp = tempfile::tempdir().unwrap();
+        let options = StorageOptions {
+            immutable_web_ui: true,
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        storage.init().await.unwrap();
+        storage
+            .publish_plugin_web_ui("adi.tasks", "1.0.0", b"v1")
+            .await
+            .unwrap();
+
+        let err = storage
+            .publish_plugin_web_ui("adi.tasks", "1.0.0", b"v2")
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<WebUiExists>().is_some());
+        let path = storage.get_plugin_web_ui_path("adi.tasks", "1.0.0");
+        assert_eq!(std::fs::read(&path).unwrap(), b"v1");
+
+        // An explicit replace bypasses the policy
+        storage
+            .replace_plugin_web_ui("adi.tasks", "1.0.0", b"v2")
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"v2");
+
+        // The permissive default still allows overwriting (see test_publish_web_ui_overwrite)
+        let (permissive, _tmp) = setup().await;
+        for js in [b"v1", b"v2"] {
+            permissive
+                .publish_plugin_web_ui("adi.tasks", "1.0.0", js)
+                .await
+                .unwrap();
+        }
+    }
+
     #[tokio::test]
     async fn test_has_web_ui_true() {
         let (storage, _tmp) = setup().await;
@@ -543,4 +1990,1154 @@ mod tests {
         let info = storage.get_plugin_info("adi.tasks", "1.0.0").await.unwrap();
         assert!(info.web_ui.is_none());
     }
+
+    #[tokio::test]
+    async fn test_increment_downloads() {
+        let (storage, _tmp) = setup().await;
+        storage
+            .increment_downloads("plugins", "adi.tasks")
+            .await
+            .unwrap();
+        let index = storage.load_index().await.unwrap();
+        assert_eq!(index.plugins[0].downloads, 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_counts_kept_outside_index() {
+        let (storage, tmp) = setup().await;
+        // A count recorded in the index before counter files existed carries over
+        let mut index = storage.load_index().await.unwrap();
+        index.plugins[0].downloads = 5;
+        storage.save_index(&index).await.unwrap();
+        let writes = storage.cache.writes.load(Ordering::Relaxed);
+
+        for _ in 0..2 {
+            storage
+                .increment_downloads("plugins", "adi.tasks")
+                .await
+                .unwrap();
+        }
+        assert_eq!(storage.cache.writes.load(Ordering::Relaxed), writes);
+        let on_disk: RegistryIndex =
+            serde_json::from_slice(&std::fs::read(tmp.path().join("index.json")).unwrap()).unwrap();
+        assert_eq!(on_disk.plugins[0].downloads, 5);
+        assert_eq!(storage.load_index().await.unwrap().plugins[0].downloads, 7);
+
+        // Rewriting the index with the merged count doesn't count anything twice
+        storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.1.0",
+                "darwin-aarch64",
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        assert_eq!(storage.load_index().await.unwrap().plugins[0].downloads, 7);
+
+        storage
+            .increment_downloads("plugins", "adi.unknown")
+            .await
+            .unwrap();
+        assert!(!tmp.path().join("plugins/adi.unknown").exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_counts_disabled() {
+        let (storage, tmp) = setup().await;
+        storage
+            .increment_downloads("plugins", "adi.tasks")
+            .await
+            .unwrap();
+
+        let options = StorageOptions {
+            count_downloads: false,
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        for _ in 0..3 {
+            storage
+                .increment_downloads("plugins", "adi.tasks")
+                .await
+                .unwrap();
+        }
+
+        let index = storage.load_index().await.unwrap();
+        assert_eq!(index.plugins[0].downloads, 1);
+    }
+
+    fn meta() -> PluginVersionMeta {
+        PluginVersionMeta {
+            name: "Tasks".to_string(),
+            description: "Task management".to_string(),
+            plugin_type: "core".to_string(),
+            author: "ADI Team".to_string(),
+            changelog: Some("Adds recurring tasks".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_meta_without_artifacts() {
+        let (storage, _tmp) = setup().await;
+        storage
+            .publish_plugin_meta("adi.tasks", "2.0.0", &meta())
+            .await
+            .unwrap();
+
+        let info = storage.get_plugin_info("adi.tasks", "2.0.0").await.unwrap();
+        assert!(info.platforms.is_empty());
+        let stored = storage.get_plugin_meta("adi.tasks", "2.0.0").await.unwrap();
+        assert_eq!(stored, Some(meta()));
+
+        // Latest still points at the version with builds
+        let latest = storage.get_plugin_latest("adi.tasks").await.unwrap();
+        assert_eq!(latest.version, "1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_publish_meta_artifactless_latest() {
+        let (_, tmp) = setup().await;
+        let options = StorageOptions {
+            artifactless_latest: true,
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        storage
+            .publish_plugin_meta("adi.tasks", "2.0.0", &meta())
+            .await
+            .unwrap();
+
+        let latest = storage.get_plugin_latest("adi.tasks").await.unwrap();
+        assert_eq!(latest.version, "2.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_publish_meta_keeps_existing_builds() {
+        let (storage, _tmp) = setup().await;
+        let info = storage
+            .publish_plugin_meta("adi.tasks", "1.0.0", &meta())
+            .await
+            .unwrap();
+        assert_eq!(info.platforms.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_index_since() {
+        let (storage, _tmp) = setup().await;
+
+        // Backdate the first publish so the two entries land at distinct times
+        {
+            let _lock = storage.lock_index().await.unwrap();
+            storage
+                .touch_entry("plugins", "adi.tasks", 100)
+                .await
+                .unwrap();
+        }
+        storage
+            .publish_plugin(
+                "adi.notes",
+                "Notes",
+                "Note taking",
+                "core",
+                "1.0.0",
+                "darwin-aarch64",
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let index = storage.load_index_since(101).await.unwrap();
+        let ids: Vec<_> = index.plugins.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["adi.notes"]);
+        assert!(index.updated_at >= 101);
+
+        let index = storage.load_index_since(100).await.unwrap();
+        assert_eq!(index.plugins.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_latest_published_at_advances_with_latest() {
+        let (storage, _tmp) = setup().await;
+        let publish = |version: &'static str, platform: &'static str| {
+            storage.publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                version,
+                platform,
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+        };
+        let published_at = || {
+            let storage = &storage;
+            async move {
+                let index = storage.load_index().await.unwrap();
+                let json = storage.index_json(&index).await.unwrap();
+                let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+                json["plugins"][0]["latest_published_at"].as_u64().unwrap()
+            }
+        };
+
+        // setup() published 1.0.0 for darwin-aarch64
+        assert!(published_at().await > 100);
+        {
+            let _lock = storage.lock_index().await.unwrap();
+            storage
+                .touch_latest("plugins", "adi.tasks", 100)
+                .await
+                .unwrap();
+        }
+
+        // Older versions and more platforms for the latest leave it alone
+        publish("0.9.0", "darwin-aarch64").await.unwrap();
+        publish("1.0.0", "linux-x86_64").await.unwrap();
+        assert_eq!(published_at().await, 100);
+
+        publish("1.1.0", "darwin-aarch64").await.unwrap();
+        assert!(published_at().await > 100);
+    }
+
+    #[tokio::test]
+    async fn test_older_publish_keeps_latest_metadata() {
+        let (storage, _tmp) = setup().await;
+        let publish = |version: &'static str, name: &'static str, author: &'static str| {
+            storage.publish_package(
+                "adi.suite",
+                name,
+                "",
+                version,
+                "linux-x86_64",
+                b"fake binary",
+                author,
+                vec![author.to_string()],
+                None,
+            )
+        };
+
+        publish("2.0.0", "Suite", "ADI Team").await.unwrap();
+        publish("1.0.0", "Old Suite", "Someone Else").await.unwrap();
+
+        let index = storage.load_index().await.unwrap();
+        let entry = index.packages.iter().find(|p| p.id == "adi.suite").unwrap();
+        assert_eq!(entry.latest_version, "2.0.0");
+        assert_eq!(entry.name, "Suite");
+        assert_eq!(entry.author, "ADI Team");
+        assert_eq!(entry.tags, vec!["ADI Team"]);
+    }
+
+    #[tokio::test]
+    async fn test_republish_conflicts_per_platform() {
+        let (storage, _tmp) = setup().await;
+        let publish = |platform: &'static str, data: &'static [u8]| {
+            storage.publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.0.0",
+                platform,
+                data,
+                "ADI Team",
+                vec![],
+            )
+        };
+        let path = storage.plugin_artifact_path("adi.tasks", "1.0.0", "darwin-aarch64");
+        let original = std::fs::read(&path).unwrap();
+
+        let err = publish("Darwin-AArch64", b"typo").await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ArtifactExists>(),
+            Some(&ArtifactExists {
+                platform: "darwin-aarch64".to_string()
+            })
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+
+        // A new platform for the same version is not a conflict
+        publish("linux-x86_64", b"linux").await.unwrap();
+
+        storage
+            .replace_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.0.0",
+                "darwin-aarch64",
+                b"rebuilt",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"rebuilt");
+    }
+
+    #[tokio::test]
+    async fn test_platform_case_maps_to_same_build() {
+        let (storage, _tmp) = setup().await;
+        for platform in ["Windows-X86_64", "windows-x86_64", "windows\\x86_64"] {
+            storage
+                .replace_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "Task management",
+                    "core",
+                    "1.0.0",
+                    platform,
+                    platform.as_bytes(),
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+
+        let info = storage.get_plugin_info("adi.tasks", "1.0.0").await.unwrap();
+        let platforms: Vec<_> = info.platforms.iter().map(|p| p.platform.as_str()).collect();
+        assert_eq!(platforms, vec!["darwin-aarch64", "windows-x86_64"]);
+        assert_eq!(
+            storage.plugin_artifact_path("adi.tasks", "1.0.0", "Windows-X86_64"),
+            storage.plugin_artifact_path("adi.tasks", "1.0.0", "windows-x86_64")
+        );
+    }
+
+    async fn publish_burst(storage: &RegistryStorage, count: usize) {
+        for i in 0..count {
+            storage
+                .publish_plugin(
+                    &format!("adi.burst{}", i),
+                    "Burst",
+                    "",
+                    "extension",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"fake binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_index_writes() {
+        let (_, tmp) = setup().await;
+        let options = StorageOptions {
+            index_flush_delay: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        publish_burst(&storage, 10).await;
+
+        // Reads see the mutations before they reach disk
+        assert_eq!(storage.load_index().await.unwrap().plugins.len(), 11);
+
+        storage.flush_index().await.unwrap();
+        assert!(storage.cache.writes.load(Ordering::Relaxed) < 10);
+
+        let reader = RegistryStorage::new(tmp.path().to_path_buf());
+        assert_eq!(reader.load_index().await.unwrap().plugins.len(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_revision_tracks_changes() {
+        let (storage, tmp) = setup().await;
+        let mut seen = vec![storage.revision().await];
+        let mut assert_changed = |revision: String| {
+            assert!(!seen.contains(&revision), "{}", revision);
+            seen.push(revision);
+        };
+
+        storage
+            .increment_downloads("plugins", "adi.tasks")
+            .await
+            .unwrap();
+        assert_changed(storage.revision().await);
+        storage
+            .set_yanked("plugins", "adi.tasks", "1.0.0", true)
+            .await
+            .unwrap();
+        assert_changed(storage.revision().await);
+
+        // Another process sharing the directory starts from its own count, but sees
+        // the files it didn't write
+        let other = RegistryStorage::new(tmp.path().to_path_buf());
+        let before = other.revision().await;
+        publish_burst(&storage, 1).await;
+        assert_ne!(other.revision().await, before);
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_index_flushed_on_drop() {
+        let (_, tmp) = setup().await;
+        let options = StorageOptions {
+            index_flush_delay: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        publish_burst(&storage, 3).await;
+        drop(storage);
+
+        let reader = RegistryStorage::new(tmp.path().to_path_buf());
+        assert_eq!(reader.load_index().await.unwrap().plugins.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_publish_with_published_at() {
+        let (storage, _tmp) = setup().await;
+        let store = |platform: &'static str, published_at: Option<u64>| {
+            let storage = &storage;
+            async move {
+                storage
+                    .store_plugin(
+                        "adi.tasks",
+                        "Tasks",
+                        "Task management",
+                        "core",
+                        "1.0.0",
+                        platform,
+                        b"fake binary",
+                        "ADI Team",
+                        vec![],
+                        true,
+                        &PublishExtras {
+                            published_at,
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .unwrap();
+                storage.get_plugin_info("adi.tasks", "1.0.0").await.unwrap()
+            }
+        };
+
+        let info = store("linux-x86_64", Some(1_600_000_000)).await;
+        assert_eq!(info.published_at, 1_600_000_000);
+        assert_eq!(info.platforms.len(), 2);
+
+        // Later builds of the version keep it
+        let info = store("windows-x86_64", None).await;
+        assert_eq!(info.published_at, 1_600_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_get_plugins_latest() {
+        let (storage, _tmp) = setup().await;
+        for (id, version) in [("adi.tasks", "1.1.0"), ("adi.notes", "1.0.0")] {
+            storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    b"fake binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        storage
+            .set_yanked("plugins", "adi.notes", "1.0.0", true)
+            .await
+            .unwrap();
+
+        let ids = ["adi.tasks", "adi.notes", "adi.missing"].map(String::from);
+        let infos = storage.get_plugins_latest(&ids).await.unwrap();
+        // Unknown ids and fully yanked plugins are left out
+        assert_eq!(infos.keys().collect::<Vec<_>>(), ["adi.tasks"]);
+        assert_eq!(infos["adi.tasks"].version, "1.1.0");
+    }
+
+    #[tokio::test]
+    async fn test_publish_with_signature() {
+        let (storage, _tmp) = setup().await;
+        let extras = PublishExtras {
+            signature: Some("sig-data".to_string()),
+            ..Default::default()
+        };
+        let store = |platform: &'static str, extras: PublishExtras| {
+            let storage = &storage;
+            async move {
+                storage
+                    .store_plugin(
+                        "adi.tasks",
+                        "Tasks",
+                        "Task management",
+                        "core",
+                        "1.0.0",
+                        platform,
+                        b"fake binary",
+                        "ADI Team",
+                        vec![],
+                        true,
+                        &extras,
+                    )
+                    .await
+                    .unwrap();
+                storage.get_plugin_info("adi.tasks", "1.0.0").await.unwrap()
+            }
+        };
+
+        let info = store("Linux-X86_64", extras.clone()).await;
+        let build = |platform: &str| {
+            info.platforms
+                .iter()
+                .find(|b| b.platform == platform)
+                .unwrap()
+                .signature
+                .clone()
+        };
+        assert_eq!(build("linux-x86_64").as_deref(), Some("sig-data"));
+        assert_eq!(build("darwin-aarch64"), None);
+
+        // Republishing without one drops it
+        let info = store("linux-x86_64", PublishExtras::default()).await;
+        assert!(info.platforms.iter().all(|b| b.signature.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_max_platforms() {
+        let (_, tmp) = setup().await;
+        let options = StorageOptions {
+            max_platforms: 2,
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        let publish = |platform: &'static str| {
+            storage.publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.0.0",
+                platform,
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+        };
+
+        publish("linux-x86_64").await.unwrap();
+        let err = publish("windows-x86_64").await.unwrap_err();
+        assert!(err.downcast_ref::<TooManyPlatforms>().is_some());
+        assert!(!storage
+            .plugin_artifact_path("adi.tasks", "1.0.0", "windows-x86_64")
+            .exists());
+
+        // Replacing an existing platform is still allowed
+        storage
+            .replace_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.0.0",
+                "darwin-aarch64",
+                b"fake binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_instances_do_not_lose_entries() {
+        let (storage, tmp) = setup().await;
+        let storage = std::sync::Arc::new(storage);
+        let other = std::sync::Arc::new(RegistryStorage::new(tmp.path().to_path_buf()));
+
+        let mut handles = Vec::new();
+        for i in 0..20 {
+            let s = if i % 2 == 0 {
+                storage.clone()
+            } else {
+                other.clone()
+            };
+            handles.push(tokio::spawn(async move {
+                s.publish_plugin(
+                    &format!("adi.concurrent-{}", i),
+                    "Concurrent",
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let index = storage.load_index().await.unwrap();
+        // 20 concurrent publishes plus the base plugin from setup
+        assert_eq!(index.plugins.len(), 21);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_platforms_all_recorded() {
+        let (storage, _tmp) = setup().await;
+        let storage = std::sync::Arc::new(storage);
+
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let s = storage.clone();
+                tokio::spawn(async move {
+                    s.publish_plugin(
+                        "adi.tasks",
+                        "Tasks",
+                        "",
+                        "core",
+                        "2.0.0",
+                        &format!("linux-arch{}", i),
+                        b"bin",
+                        "ADI Team",
+                        vec![],
+                    )
+                    .await
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        let info = storage.get_plugin_info("adi.tasks", "2.0.0").await.unwrap();
+        let platforms: BTreeSet<_> = info.platforms.iter().map(|p| p.platform.clone()).collect();
+        assert_eq!(platforms.len(), 16);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_platforms_respect_max_platforms() {
+        let tmp = tempfile::tempdir().unwrap();
+        let options = StorageOptions {
+            max_platforms: 4,
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        storage.init().await.unwrap();
+        let storage = std::sync::Arc::new(storage);
+
+        let handles: Vec<_> = (0..12)
+            .map(|i| {
+                let s = storage.clone();
+                tokio::spawn(async move {
+                    s.publish_package(
+                        "adi.suite",
+                        "Suite",
+                        "",
+                        "1.0.0",
+                        &format!("linux-arch{}", i),
+                        b"bin",
+                        "ADI Team",
+                        vec![],
+                        None,
+                    )
+                    .await
+                })
+            })
+            .collect();
+        let mut published = 0;
+        for handle in handles {
+            match handle.await.unwrap() {
+                Ok(()) => published += 1,
+                Err(e) => assert!(e.downcast_ref::<TooManyPlatforms>().is_some()),
+            }
+        }
+
+        assert_eq!(published, 4);
+        let info = storage
+            .get_package_info("adi.suite", "1.0.0")
+            .await
+            .unwrap();
+        assert_eq!(info.platforms.len(), 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_downloads_all_counted() {
+        let (storage, _tmp) = setup().await;
+        let storage = std::sync::Arc::new(storage);
+
+        let handles: Vec<_> = (0..50)
+            .map(|_| {
+                let s = storage.clone();
+                tokio::spawn(async move { s.increment_downloads("plugins", "adi.tasks").await })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        let index = storage.load_index().await.unwrap();
+        let entry = index.plugins.iter().find(|p| p.id == "adi.tasks").unwrap();
+        assert_eq!(entry.downloads, 50);
+    }
+
+    #[tokio::test]
+    async fn test_list_package_versions_newest_first() {
+        let (storage, _tmp) = setup().await;
+        for version in ["1.2.0", "nightly", "1.10.0", "beta", "0.9.0"] {
+            storage
+                .publish_package(
+                    "adi.suite",
+                    "Suite",
+                    "",
+                    version,
+                    "linux-x86_64",
+                    b"fake binary",
+                    "ADI Team",
+                    vec![],
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let versions = storage.list_package_versions("adi.suite").await.unwrap();
+        assert_eq!(versions, ["1.10.0", "1.2.0", "0.9.0", "beta", "nightly"]);
+        assert!(storage
+            .list_package_versions("adi.missing")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_versions_with_platform() {
+        let (storage, _tmp) = setup().await;
+        for (version, platform) in [
+            ("1.0.0", "linux-x86_64"),
+            ("1.0.0", "windows-x86_64"),
+            ("1.2.0", "linux-x86_64"),
+            ("1.10.0", "windows-x86_64"),
+        ] {
+            storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    platform,
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+
+        let versions = storage
+            .plugin_versions_with_platform("adi.tasks", "Windows-X86_64")
+            .await
+            .unwrap();
+        assert_eq!(versions, vec!["1.10.0", "1.0.0"]);
+        // setup() published darwin-aarch64 for 1.0.0 only
+        let versions = storage
+            .plugin_versions_with_platform("adi.tasks", "darwin-aarch64")
+            .await
+            .unwrap();
+        assert_eq!(versions, vec!["1.0.0"]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_ids_rejected() {
+        let (storage, tmp) = setup().await;
+        for id in [
+            "../etc",
+            "..",
+            ".hidden",
+            "/etc/passwd",
+            "adi/../../etc",
+            "adi\\tasks",
+            "ADI.tasks",
+            "adi.taskſ",
+            "adi\u{2024}tasks",
+            "",
+        ] {
+            assert!(!is_valid_id(id), "{:?}", id);
+            let err = storage
+                .publish_plugin(
+                    id,
+                    "Evil",
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"fake binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap_err();
+            assert!(err.downcast_ref::<InvalidId>().is_some());
+        }
+        assert!(!is_valid_id(&"a".repeat(MAX_ID_LEN + 1)));
+        assert!(is_valid_id("com.example.my-plugin_2"));
+        // Nothing was created outside (or inside) the data root
+        assert!(!tmp.path().join("etc").exists());
+        assert!(!tmp.path().join("plugins").join("..").join("etc").exists());
+    }
+
+    #[tokio::test]
+    async fn test_traversal_in_id_or_version_rejected() {
+        let (storage, tmp) = setup().await;
+        for (id, version) in [("../x", "1.0.0"), ("adi.tasks", "../x")] {
+            let rejected = |e: &anyhow::Error| {
+                e.downcast_ref::<InvalidId>().is_some()
+                    || e.downcast_ref::<InvalidVersion>().is_some()
+            };
+            let err = storage
+                .publish_plugin(
+                    id,
+                    "Evil",
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap_err();
+            assert!(rejected(&err), "{} {}", id, version);
+            let err = storage
+                .publish_package(
+                    id,
+                    "Evil",
+                    "",
+                    version,
+                    "linux-x86_64",
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                    None,
+                )
+                .await
+                .unwrap_err();
+            assert!(rejected(&err), "{} {}", id, version);
+            let err = storage
+                .replace_plugin_web_ui(id, version, b"export default {}")
+                .await
+                .unwrap_err();
+            assert!(rejected(&err), "{} {}", id, version);
+        }
+        for version in ["..", "1.0.0/..", "1.0\\0", "", ".1"] {
+            assert!(!is_valid_version(version), "{:?}", version);
+        }
+        assert!(is_valid_version("2024.01.15"));
+        assert!(is_valid_version("1.0.0-rc.1+build.5"));
+        assert!(!tmp.path().join("x").exists());
+        assert!(!tmp.path().join("plugins").join("x").exists());
+        assert!(!tmp.path().join("packages").join("x").exists());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_index_falls_back_to_backup() {
+        let (storage, tmp) = setup().await;
+        let index = storage.load_index().await.unwrap();
+        storage.save_index(&index).await.unwrap();
+        let index_path = tmp.path().join("index.json");
+        let full = std::fs::read(&index_path).unwrap();
+        assert_eq!(
+            std::fs::read(tmp.path().join("index.json.bak")).unwrap(),
+            full
+        );
+
+        // A write cut short, as if the process died mid-write
+        std::fs::write(&index_path, &full[..full.len() / 2]).unwrap();
+        let recovered = storage.load_index().await.unwrap();
+        assert_eq!(recovered.plugins.len(), index.plugins.len());
+        assert_eq!(recovered.plugins[0].id, "adi.tasks");
+
+        std::fs::remove_file(tmp.path().join("index.json.bak")).unwrap();
+        assert!(storage.load_index().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_entry_skipped() {
+        let (storage, tmp) = setup().await;
+        for id in ["adi.notes", "adi.lint"] {
+            storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    "1.0.0",
+                    "darwin-aarch64",
+                    b"fake binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let index_path = tmp.path().join("index.json");
+        let mut index: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&index_path).unwrap()).unwrap();
+        index["plugins"][1]["downloads"] = serde_json::json!("many");
+        std::fs::write(&index_path, index.to_string()).unwrap();
+
+        let served = storage.load_index().await.unwrap();
+        let ids: Vec<&str> = served.plugins.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["adi.tasks", "adi.lint"]);
+        // Writing now would drop adi.notes for good
+        assert!(storage.save_index(&served).await.is_err());
+        assert!(storage
+            .publish_plugin(
+                "adi.new",
+                "New",
+                "",
+                "core",
+                "1.0.0",
+                "darwin-aarch64",
+                b"x",
+                "ADI Team",
+                vec![]
+            )
+            .await
+            .is_err());
+
+        index["plugins"][1]["downloads"] = serde_json::json!(0);
+        std::fs::write(&index_path, index.to_string()).unwrap();
+        assert_eq!(storage.load_index().await.unwrap().plugins.len(), 3);
+        storage.save_index(&served).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_artifact_checksum_verification() {
+        let (storage, _tmp) = setup().await;
+        let info = storage.get_plugin_info("adi.tasks", "1.0.0").await.unwrap();
+        let checksum = &info.platforms[0].checksum;
+        let path = storage.plugin_artifact_path("adi.tasks", "1.0.0", "darwin-aarch64");
+
+        assert!(storage
+            .artifact_matches_checksum(&path, checksum)
+            .await
+            .unwrap());
+        std::fs::write(&path, b"trunc").unwrap();
+        assert!(!storage
+            .artifact_matches_checksum(&path, checksum)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_list_plugin_versions() {
+        let (storage, tmp) = setup().await;
+        for version in ["1.2.0", "1.10.0"] {
+            storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        storage
+            .publish_plugin_web_ui("adi.tasks", "1.2.0", b"export default {};")
+            .await
+            .unwrap();
+        // An interrupted upload leaves a version directory without info.json
+        std::fs::create_dir_all(tmp.path().join("plugins/adi.tasks/2.0.0")).unwrap();
+
+        let versions = storage.list_plugin_versions("adi.tasks").await.unwrap();
+        let summary = |version: &str, has_web_ui| PluginVersionSummary {
+            version: version.to_string(),
+            has_web_ui,
+        };
+        assert_eq!(
+            versions,
+            [
+                summary("1.10.0", false),
+                summary("1.2.0", true),
+                summary("1.0.0", false),
+            ]
+        );
+    }
+
+    async fn latest_plugin_version(storage: &RegistryStorage, id: &str) -> Option<String> {
+        let index = storage.load_index().await.unwrap();
+        index
+            .plugins
+            .into_iter()
+            .find(|p| p.id == id)
+            .map(|p| p.latest_version)
+    }
+
+    #[tokio::test]
+    async fn test_delete_plugin_version() {
+        let (storage, tmp) = setup().await;
+        for version in ["1.1.0", "1.2.0"] {
+            storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let delete = |version| storage.delete_plugin_version("adi.tasks", version);
+        assert!(!delete("3.0.0").await.unwrap());
+        assert!(!delete("..").await.unwrap());
+
+        // Deleting the latest makes the newest remaining version latest
+        assert!(delete("1.2.0").await.unwrap());
+        assert!(!tmp.path().join("plugins/adi.tasks/1.2.0").exists());
+        let latest = latest_plugin_version(&storage, "adi.tasks").await;
+        assert_eq!(latest.as_deref(), Some("1.1.0"));
+
+        // Deleting an older version leaves latest alone
+        assert!(delete("1.0.0").await.unwrap());
+        let latest = latest_plugin_version(&storage, "adi.tasks").await;
+        assert_eq!(latest.as_deref(), Some("1.1.0"));
+        assert_eq!(
+            storage.list_versions("plugins", "adi.tasks").await.unwrap(),
+            ["1.1.0"]
+        );
+
+        // Deleting the last version removes the plugin and leaves a tombstone
+        assert!(delete("1.1.0").await.unwrap());
+        assert_eq!(latest_plugin_version(&storage, "adi.tasks").await, None);
+        assert!(storage
+            .deleted_at("plugins", "adi.tasks")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(!delete("1.1.0").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_repair_package_links() {
+        let (storage, _tmp) = setup().await;
+        storage
+            .publish_package(
+                "adi.core",
+                "Core",
+                "Core package",
+                "1.0.0",
+                "linux-x86_64",
+                b"pkg",
+                "ADI Team",
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let mut index = storage.load_index().await.unwrap();
+        index.plugins[0].package_id = Some("adi.core".to_string());
+        index.packages[0].plugin_count = 7;
+        storage.save_index(&index).await.unwrap();
+
+        assert_eq!(storage.repair_package_links().await.unwrap(), 1);
+        let index = storage.load_index().await.unwrap();
+        assert_eq!(index.packages[0].plugin_count, 1);
+        assert_eq!(index.packages[0].plugin_ids, vec!["adi.tasks".to_string()]);
+
+        // Already consistent: nothing left to correct
+        assert_eq!(storage.repair_package_links().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_root_fallback() {
+        // The snapshot holds adi.tasks 1.0.0; the write root starts empty
+        let (_snapshot, read_tmp) = setup().await;
+        let write_tmp = tempfile::tempdir().unwrap();
+        let options = StorageOptions {
+            read_root: Some(read_tmp.path().to_path_buf()),
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(write_tmp.path().to_path_buf(), options);
+        storage.init().await.unwrap();
+
+        let info = storage.get_plugin_info("adi.tasks", "1.0.0").await.unwrap();
+        assert_eq!(info.platforms.len(), 1);
+        assert!(storage
+            .plugin_artifact_path("adi.tasks", "1.0.0", "darwin-aarch64")
+            .starts_with(read_tmp.path()));
+
+        storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"linux",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let written = write_tmp.path().join("plugins/adi.tasks/1.0.0");
+        assert!(written.join("linux-x86_64.tar.gz").exists());
+        assert!(written.join("info.json").exists());
+        assert!(write_tmp.path().join("index.json").exists());
+        assert!(!read_tmp
+            .path()
+            .join("plugins/adi.tasks/1.0.0/linux-x86_64.tar.gz")
+            .exists());
+
+        // The merged info keeps the snapshot's platform alongside the new one
+        let info = storage.get_plugin_info("adi.tasks", "1.0.0").await.unwrap();
+        assert_eq!(info.platforms.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_root_validated_at_init() {
+        let tmp = tempfile::tempdir().unwrap();
+        let missing = StorageOptions {
+            read_root: Some(tmp.path().join("missing")),
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), missing);
+        assert!(storage.init().await.is_err());
+
+        let same = StorageOptions {
+            read_root: Some(tmp.path().to_path_buf()),
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), same);
+        assert!(storage.init().await.is_err());
+    }
 }
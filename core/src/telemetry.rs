@@ -0,0 +1,134 @@
+//! Install outcomes reported by clients, aggregated per plugin version.
+
+use crate::storage::{write_atomic, RegistryStorage};
+use crate::text::truncate_chars;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::fs;
+
+/// Distinct error messages kept per platform; further ones are counted under `other`.
+const MAX_ERRORS: usize = 20;
+const MAX_ERROR_CHARS: usize = 120;
+const OTHER_ERRORS: &str = "other";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallOutcome {
+    Success,
+    Failure,
+}
+
+/// Install counts for one platform build.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallCounts {
+    pub success: u64,
+    pub failure: u64,
+    /// Failures by the first line of their error message, where one was given.
+    #[serde(default)]
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl InstallCounts {
+    fn record(&mut self, outcome: InstallOutcome, error: Option<&str>) {
+        match outcome {
+            InstallOutcome::Success => self.success += 1,
+            InstallOutcome::Failure => self.failure += 1,
+        }
+        let error = error
+            .filter(|_| outcome == InstallOutcome::Failure)
+            .and_then(|e| e.lines().next())
+            .map(str::trim)
+            .filter(|e| !e.is_empty());
+        if let Some(error) = error {
+            let mut key = truncate_chars(error, MAX_ERROR_CHARS);
+            if !self.errors.contains_key(&key) && self.errors.len() >= MAX_ERRORS {
+                key = OTHER_ERRORS.to_string();
+            }
+            *self.errors.entry(key).or_default() += 1;
+        }
+    }
+}
+
+/// Aggregated install telemetry of a plugin version, stored as `telemetry.json`.
+///
+/// Only counts are kept; nothing identifies the reporting client.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallTelemetry {
+    /// Counts keyed by platform.
+    pub platforms: BTreeMap<String, InstallCounts>,
+}
+
+impl RegistryStorage {
+    /// Install telemetry recorded for a plugin version; empty if none was reported.
+    pub async fn install_telemetry(&self, id: &str, version: &str) -> Result<InstallTelemetry> {
+        let path = self.resolve(self.plugin_version_dir(id, version).join("telemetry.json"));
+        if !path.exists() {
+            return Ok(InstallTelemetry::default());
+        }
+        let data = fs::read_to_string(&path).await?;
+        serde_json::from_str(&data).context("Failed to parse telemetry.json")
+    }
+
+    /// Count one reported install of a plugin build. `error` is only kept for failures,
+    /// cut to its first line.
+    pub async fn record_install(
+        &self,
+        id: &str,
+        version: &str,
+        platform: &str,
+        outcome: InstallOutcome,
+        error: Option<&str>,
+    ) -> Result<InstallTelemetry> {
+        let _lock = self.lock_index().await?;
+        let mut telemetry = self.install_telemetry(id, version).await?;
+        telemetry
+            .platforms
+            .entry(platform.to_string())
+            .or_default()
+            .record(outcome, error);
+
+        let dir = self.plugin_version_dir(id, version);
+        fs::create_dir_all(&dir).await?;
+        let json = serde_json::to_string_pretty(&telemetry)?;
+        write_atomic(&dir.join("telemetry.json"), json.as_bytes()).await?;
+        Ok(telemetry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_install() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+
+        let errors: Vec<String> = (0..MAX_ERRORS + 2)
+            .map(|i| format!("error {}", i))
+            .collect();
+        let record = |outcome, error| {
+            storage.record_install("adi.tasks", "1.0.0", "linux-x86_64", outcome, error)
+        };
+        record(InstallOutcome::Success, Some("ignored"))
+            .await
+            .unwrap();
+        record(InstallOutcome::Failure, Some("disk full\n/home/me/.adi"))
+            .await
+            .unwrap();
+        for error in &errors {
+            record(InstallOutcome::Failure, Some(error)).await.unwrap();
+        }
+
+        let telemetry = storage
+            .install_telemetry("adi.tasks", "1.0.0")
+            .await
+            .unwrap();
+        let counts = &telemetry.platforms["linux-x86_64"];
+        assert_eq!((counts.success, counts.failure), (1, MAX_ERRORS as u64 + 3));
+        assert_eq!(counts.errors["disk full"], 1);
+        assert_eq!(counts.errors.len(), MAX_ERRORS + 1);
+        assert_eq!(counts.errors[OTHER_ERRORS], 3);
+    }
+}
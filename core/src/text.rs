@@ -0,0 +1,132 @@
+/// Fold a string for case-insensitive matching.
+///
+/// Plain `to_lowercase()` turns the Turkish `İ` into `i` followed by a combining dot,
+/// so `İstanbul` would never match `istanbul`. Dotted/dotless `i` variants collapse to
+/// `i`, stray combining dots are dropped and `ß` expands to `ss`, as in full case folding.
+pub fn fold_case(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'İ' | 'ı' => folded.push('i'),
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            '\u{0307}' => {}
+            c => folded.extend(c.to_lowercase().filter(|&l| l != '\u{0307}')),
+        }
+    }
+    folded
+}
+
+/// Shortest search term considered; shorter tokens match almost everything.
+const MIN_TERM_CHARS: usize = 2;
+
+/// Split a search query into case-folded terms.
+///
+/// Empty and single-character tokens and duplicates are ignored, and only the first
+/// `max_terms` remaining terms are kept.
+pub fn search_terms(query: &str, max_terms: usize) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in query.split_whitespace().map(fold_case) {
+        if terms.len() == max_terms {
+            break;
+        }
+        if term.chars().count() >= MIN_TERM_CHARS && !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+/// Check that every term occurs in at least one of the fields (AND across terms).
+pub fn matches_terms<'a>(fields: impl IntoIterator<Item = &'a str>, terms: &[String]) -> bool {
+    let fields: Vec<String> = fields.into_iter().map(fold_case).collect();
+    terms
+        .iter()
+        .all(|term| fields.iter().any(|field| field.contains(term.as_str())))
+}
+
+/// Split a comma-separated tag filter into case-folded tags, ignoring empty ones.
+pub fn tag_filter(tags: &str) -> Vec<String> {
+    let mut filter: Vec<String> = Vec::new();
+    for tag in tags.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let tag = fold_case(tag);
+        if !filter.contains(&tag) {
+            filter.push(tag);
+        }
+    }
+    filter
+}
+
+/// Check that `tags` contains every tag of the filter, ignoring case (AND across tags).
+pub fn has_all_tags(tags: &[String], filter: &[String]) -> bool {
+    let tags: Vec<String> = tags.iter().map(|t| fold_case(t)).collect();
+    filter.iter().all(|wanted| tags.contains(wanted))
+}
+
+/// Truncate to at most `max_chars` characters, never splitting a code point.
+///
+/// An ellipsis is appended when anything was cut.
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_turkish_dotted_i() {
+        assert_eq!(fold_case("İstanbul"), "istanbul");
+        assert!(fold_case("Plugins for İstanbul").contains(&fold_case("İSTANBUL")));
+        assert!(fold_case("istanbul tools").contains(&fold_case("İstanbul")));
+        assert_eq!(fold_case("Straße"), "strasse");
+    }
+
+    #[test]
+    fn test_truncate_on_char_boundary() {
+        // 'é' is two bytes; byte-index slicing at 3 would panic
+        let text = "aéé😀z";
+        assert_eq!(truncate_chars(text, 2), "aé…");
+        assert_eq!(truncate_chars(text, 4), "aéé😀…");
+        assert_eq!(truncate_chars(text, 5), text);
+        assert_eq!(truncate_chars("", 3), "");
+    }
+
+    #[test]
+    fn test_search_terms_capped() {
+        assert_eq!(
+            search_terms("  Task  x MANAGER task ", 16),
+            vec!["task", "manager"]
+        );
+        let long = (0..100)
+            .map(|i| format!("t{}", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let terms = search_terms(&long, 16);
+        assert_eq!(terms.len(), 16);
+        assert_eq!(terms[15], "t15");
+    }
+
+    #[test]
+    fn test_matches_all_terms() {
+        let terms = search_terms("task board", 16);
+        assert!(matches_terms(["adi.tasks", "Task Board"], &terms));
+        assert!(matches_terms(["adi.tasks", "Kanban board"], &terms));
+        assert!(!matches_terms(["adi.tasks", "Task management"], &terms));
+        assert!(matches_terms(["anything"], &[]));
+    }
+
+    #[test]
+    fn test_has_all_tags() {
+        let tags = ["Productivity".to_string(), "cli".to_string()];
+        assert!(has_all_tags(&tags, &tag_filter("productivity")));
+        assert!(has_all_tags(&tags, &tag_filter("CLI, productivity")));
+        assert!(!has_all_tags(&tags, &tag_filter("productivity,themes")));
+        // Tags match whole, not as substrings
+        assert!(!has_all_tags(&tags, &tag_filter("product")));
+        assert!(tag_filter(" , ,").is_empty());
+        assert!(has_all_tags(&[], &tag_filter("")));
+    }
+}
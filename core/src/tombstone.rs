@@ -0,0 +1,154 @@
+use crate::storage::{now_unix, write_atomic, RegistryStorage};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::fs;
+
+/// Deletion times (unix seconds) of removed ids, stored as `tombstones.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Tombstones {
+    #[serde(default)]
+    packages: BTreeMap<String, u64>,
+    #[serde(default)]
+    plugins: BTreeMap<String, u64>,
+}
+
+impl Tombstones {
+    fn kind_mut(&mut self, kind: &str) -> Result<&mut BTreeMap<String, u64>> {
+        match kind {
+            "packages" => Ok(&mut self.packages),
+            "plugins" => Ok(&mut self.plugins),
+            _ => anyhow::bail!("Unknown kind: {}", kind),
+        }
+    }
+}
+
+impl RegistryStorage {
+    async fn load_tombstones(&self) -> Result<Tombstones> {
+        let path = self.resolve(self.root().join("tombstones.json"));
+        if !path.exists() {
+            return Ok(Tombstones::default());
+        }
+        let data = fs::read_to_string(&path).await?;
+        serde_json::from_str(&data).context("Failed to parse tombstones.json")
+    }
+
+    async fn save_tombstones(&self, tombstones: &Tombstones) -> Result<()> {
+        let path = self.root().join("tombstones.json");
+        write_atomic(&path, serde_json::to_string_pretty(tombstones)?.as_bytes()).await
+    }
+
+    /// Remember that `id` was deleted, so lookups can answer "gone" instead of "not found"
+    /// for [`StorageOptions::tombstone_retention`](crate::StorageOptions::tombstone_retention).
+    /// `kind` is `packages` or `plugins`.
+    pub async fn record_deleted(&self, kind: &str, id: &str) -> Result<()> {
+        let _lock = self.lock_index().await?;
+        self.add_tombstone(kind, id).await
+    }
+
+    /// [`Self::record_deleted`] for callers already holding [`Self::lock_index`].
+    pub(crate) async fn add_tombstone(&self, kind: &str, id: &str) -> Result<()> {
+        let mut tombstones = self.load_tombstones().await?;
+        let now = now_unix();
+        let retention = self.options().tombstone_retention.as_secs();
+        for entries in [&mut tombstones.packages, &mut tombstones.plugins] {
+            entries.retain(|_, deleted_at| now.saturating_sub(*deleted_at) < retention);
+        }
+        tombstones.kind_mut(kind)?.insert(id.to_string(), now);
+        self.save_tombstones(&tombstones).await
+    }
+
+    /// When `id` was deleted, if that happened within the retention period.
+    pub async fn deleted_at(&self, kind: &str, id: &str) -> Result<Option<u64>> {
+        let mut tombstones = self.load_tombstones().await?;
+        let retention = self.options().tombstone_retention.as_secs();
+        Ok(tombstones
+            .kind_mut(kind)?
+            .get(id)
+            .copied()
+            .filter(|&deleted_at| now_unix().saturating_sub(deleted_at) < retention))
+    }
+
+    /// Forget a deletion once the id is published again. Callers hold [`Self::lock_index`].
+    pub(crate) async fn clear_tombstone(&self, kind: &str, id: &str) -> Result<()> {
+        let mut tombstones = self.load_tombstones().await?;
+        if tombstones.kind_mut(kind)?.remove(id).is_some() {
+            self.save_tombstones(&tombstones).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StorageOptions;
+    use std::time::Duration;
+
+    async fn publish(storage: &RegistryStorage, id: &str) {
+        storage
+            .publish_plugin(
+                id,
+                id,
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"bin",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tombstone_cleared_by_republish() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+
+        storage
+            .record_deleted("plugins", "adi.tasks")
+            .await
+            .unwrap();
+        assert!(storage
+            .deleted_at("plugins", "adi.tasks")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(storage
+            .deleted_at("packages", "adi.tasks")
+            .await
+            .unwrap()
+            .is_none());
+
+        publish(&storage, "adi.tasks").await;
+        assert!(storage
+            .deleted_at("plugins", "adi.tasks")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tombstone_expires() {
+        let tmp = tempfile::tempdir().unwrap();
+        let options = StorageOptions {
+            tombstone_retention: Duration::ZERO,
+            ..Default::default()
+        };
+        let storage = RegistryStorage::with_options(tmp.path().to_path_buf(), options);
+        storage.init().await.unwrap();
+
+        storage
+            .record_deleted("plugins", "adi.tasks")
+            .await
+            .unwrap();
+        assert!(storage
+            .deleted_at("plugins", "adi.tasks")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
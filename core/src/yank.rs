@@ -0,0 +1,239 @@
+//! Yanked versions: still downloadable when asked for by version, but never resolved
+//! as latest or listed in search.
+
+use crate::storage::{now_unix, semver_cmp, semver_greater, write_atomic, RegistryStorage};
+use anyhow::{Context, Result};
+use lib_plugin_registry::RegistryIndex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use tokio::fs;
+
+/// Yanked versions by id, stored as `yanked.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Yanked {
+    #[serde(default)]
+    packages: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    plugins: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Yanked {
+    fn kind_mut(&mut self, kind: &str) -> Result<&mut BTreeMap<String, BTreeSet<String>>> {
+        match kind {
+            "packages" => Ok(&mut self.packages),
+            "plugins" => Ok(&mut self.plugins),
+            _ => anyhow::bail!("Unknown kind: {}", kind),
+        }
+    }
+
+    pub(crate) fn contains(&self, kind: &str, id: &str, version: &str) -> bool {
+        let versions = match kind {
+            "packages" => self.packages.get(id),
+            "plugins" => self.plugins.get(id),
+            _ => None,
+        };
+        versions.is_some_and(|v| v.contains(version))
+    }
+
+    /// Mark or unmark a version; `true` if that changed anything.
+    pub(crate) fn set(
+        &mut self,
+        kind: &str,
+        id: &str,
+        version: &str,
+        yanked: bool,
+    ) -> Result<bool> {
+        let entries = self.kind_mut(kind)?;
+        if yanked {
+            return Ok(entries
+                .entry(id.to_string())
+                .or_default()
+                .insert(version.to_string()));
+        }
+        let Some(versions) = entries.get_mut(id) else {
+            return Ok(false);
+        };
+        let removed = versions.remove(version);
+        if versions.is_empty() {
+            entries.remove(id);
+        }
+        Ok(removed)
+    }
+
+    /// The version that should be latest: the newest one not yanked, or the newest
+    /// overall once every version is.
+    pub(crate) fn newest<'a>(
+        &self,
+        kind: &str,
+        id: &str,
+        versions: &'a [String],
+    ) -> Option<&'a str> {
+        let newest = |yanked: bool| {
+            versions
+                .iter()
+                .filter(|v| yanked || !self.contains(kind, id, v))
+                .max_by(|a, b| semver_cmp(a, b))
+        };
+        newest(false).or_else(|| newest(true)).map(String::as_str)
+    }
+
+    /// Whether a newly published `version` should replace `latest`.
+    ///
+    /// A yanked `latest` means every version is yanked, so any version that isn't wins.
+    pub(crate) fn supersedes(&self, kind: &str, id: &str, version: &str, latest: &str) -> bool {
+        !self.contains(kind, id, version)
+            && (self.contains(kind, id, latest) || semver_greater(version, latest))
+    }
+}
+
+impl RegistryStorage {
+    pub(crate) async fn load_yanked(&self) -> Result<Yanked> {
+        let path = self.resolve(self.root().join("yanked.json"));
+        if !path.exists() {
+            return Ok(Yanked::default());
+        }
+        let data = fs::read_to_string(&path).await?;
+        serde_json::from_str(&data).context("Failed to parse yanked.json")
+    }
+
+    pub(crate) async fn save_yanked(&self, yanked: &Yanked) -> Result<()> {
+        let path = self.root().join("yanked.json");
+        write_atomic(&path, serde_json::to_string_pretty(yanked)?.as_bytes()).await?;
+        self.record_change();
+        Ok(())
+    }
+
+    /// Whether a version has been yanked. `kind` is `packages` or `plugins`.
+    pub async fn is_yanked(&self, kind: &str, id: &str, version: &str) -> Result<bool> {
+        Ok(self.load_yanked().await?.contains(kind, id, version))
+    }
+
+    /// Yank or unyank a version; `false` if the version doesn't exist.
+    ///
+    /// The index's `latest_version` moves to the newest version that isn't yanked. Once
+    /// every version is yanked it stays on the newest one, and the entry drops out of
+    /// search and latest lookups.
+    pub async fn set_yanked(
+        &self,
+        kind: &str,
+        id: &str,
+        version: &str,
+        yanked: bool,
+    ) -> Result<bool> {
+        let _lock = self.lock_index().await?;
+        let versions = self.list_versions(kind, id).await?;
+        if !versions.iter().any(|v| v == version) {
+            return Ok(false);
+        }
+
+        let mut all = self.load_yanked().await?;
+        if !all.set(kind, id, version, yanked)? {
+            return Ok(true);
+        }
+        self.save_yanked(&all).await?;
+
+        let mut index = self.load_index().await?;
+        let latest = match kind {
+            "packages" => index
+                .packages
+                .iter_mut()
+                .find(|p| p.id == id)
+                .map(|p| &mut p.latest_version),
+            _ => index
+                .plugins
+                .iter_mut()
+                .find(|p| p.id == id)
+                .map(|p| &mut p.latest_version),
+        };
+        // Versions registered from metadata alone may have no index entry yet
+        let Some(latest) = latest else {
+            return Ok(true);
+        };
+        if let Some(newest) = all.newest(kind, id, &versions) {
+            *latest = newest.to_string();
+        }
+        index.updated_at = now_unix();
+        self.touch_entry(kind, id, index.updated_at).await?;
+        self.save_index(&index).await?;
+        // The latest version's dependencies count towards dependents
+        self.dependents_cache().invalidate();
+        Ok(true)
+    }
+
+    /// Drop index entries whose every version is yanked.
+    pub async fn drop_fully_yanked(&self, index: &mut RegistryIndex) -> Result<()> {
+        let yanked = self.load_yanked().await?;
+        // `latest_version` is only yanked once all versions are
+        index
+            .packages
+            .retain(|p| !yanked.contains("packages", &p.id, &p.latest_version));
+        index
+            .plugins
+            .retain(|p| !yanked.contains("plugins", &p.id, &p.latest_version));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn publish(storage: &RegistryStorage, version: &str, platform: &str) {
+        storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                version,
+                platform,
+                b"bin",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+    }
+
+    async fn latest(storage: &RegistryStorage) -> String {
+        storage
+            .get_plugin_latest("adi.tasks")
+            .await
+            .unwrap()
+            .version
+    }
+
+    #[tokio::test]
+    async fn test_yanked_versions_skipped_as_latest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = RegistryStorage::new(tmp.path().to_path_buf());
+        storage.init().await.unwrap();
+        publish(&storage, "1.0.0", "linux-x86_64").await;
+        publish(&storage, "1.1.0", "linux-x86_64").await;
+        let yank = |version, yanked| storage.set_yanked("plugins", "adi.tasks", version, yanked);
+
+        assert!(!yank("2.0.0", true).await.unwrap());
+        assert!(yank("1.1.0", true).await.unwrap());
+        assert_eq!(latest(&storage).await, "1.0.0");
+        assert!(storage
+            .is_yanked("plugins", "adi.tasks", "1.1.0")
+            .await
+            .unwrap());
+        // Another build of a yanked version doesn't bring it back as latest
+        publish(&storage, "1.1.0", "darwin-aarch64").await;
+        assert_eq!(latest(&storage).await, "1.0.0");
+
+        // With every version yanked, nothing resolves as latest or shows in search
+        assert!(yank("1.0.0", true).await.unwrap());
+        assert!(storage.get_plugin_latest("adi.tasks").await.is_err());
+        let mut index = storage.load_index().await.unwrap();
+        storage.drop_fully_yanked(&mut index).await.unwrap();
+        assert!(index.plugins.is_empty());
+
+        // A new version, or unyanking, makes it resolvable again
+        publish(&storage, "0.9.0", "linux-x86_64").await;
+        assert_eq!(latest(&storage).await, "0.9.0");
+        assert!(yank("1.1.0", false).await.unwrap());
+        assert_eq!(latest(&storage).await, "1.1.0");
+    }
+}
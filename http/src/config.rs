@@ -0,0 +1,548 @@
+//! Server configuration, read from the environment once at startup.
+
+use crate::ip_filter::{Cidr, IpRules};
+use crate::pagination::PageLimits;
+use plugin_registry_core::{PublishKey, StaticKey, StorageOptions};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Every setting the server takes from the environment, parsed and validated up front.
+#[derive(Debug, Clone)]
+pub struct RegistryConfig {
+    /// `REGISTRY_DATA_DIR`; a command-line argument takes precedence.
+    pub data_dir: PathBuf,
+    pub port: u16,
+    /// One JSON object per log line (`REGISTRY_LOG_FORMAT=json`).
+    pub json_logs: bool,
+    /// Enables the admin routes when set.
+    pub admin_key: Option<String>,
+    /// Publish keys accepted alongside `keys.json`, each limited to an id prefix;
+    /// setting any closes publishing.
+    pub api_keys: Vec<StaticKey>,
+    /// HS256 secret for JWT bearer tokens; API keys keep working alongside.
+    pub jwt_secret: Option<String>,
+    /// PEM file with the RSA public key that RS256 JWT bearer tokens are checked against.
+    pub jwt_public_key_file: Option<PathBuf>,
+    /// JWKS URL serving the RSA keys that RS256 JWT bearer tokens are checked against.
+    pub jwt_jwks_url: Option<String>,
+    /// Required `aud` claim of JWT bearer tokens.
+    pub jwt_audience: Option<String>,
+    /// Ed25519 key every published artifact's signature must verify against.
+    pub publish_pubkey: Option<PublishKey>,
+    pub storage: StorageOptions,
+    /// How often the background integrity scrub runs; `None` disables it.
+    pub scrub_interval: Option<Duration>,
+    /// Answer 406 when a download's `Accept` header rules out gzip.
+    pub strict_accept: bool,
+    /// Base URL prepended to links in bundle manifests; empty keeps them relative.
+    pub public_url: String,
+    /// Honor `X-Forwarded-Proto`/`X-Forwarded-Host` from a reverse proxy.
+    pub trust_proxy: bool,
+    /// Reject publishes that did not arrive over HTTPS.
+    pub force_https: bool,
+    /// Add baseline security headers to every response.
+    pub security_headers: bool,
+    /// `Strict-Transport-Security: max-age`, sent on HTTPS responses when set.
+    pub hsts_max_age: Option<u64>,
+    pub pages: PageLimits,
+    /// Search query terms beyond this many are dropped.
+    pub max_search_terms: usize,
+    /// Search `kind` used when a request doesn't give one: `all`, `package` or `plugin`.
+    pub default_search_kind: String,
+    /// `Cache-Control: max-age` for `/v1/index.json`, in seconds.
+    pub index_max_age: u64,
+    /// Bytes read from disk per chunk when streaming artifacts.
+    pub download_chunk_size: usize,
+    /// Largest artifact served embedded in JSON by `base64.json`, in bytes.
+    pub max_base64_bytes: u64,
+    /// Client addresses allowed to reach publish and admin routes.
+    pub publish_ips: IpRules,
+    /// Re-hash artifacts against their recorded checksum before streaming them.
+    pub verify_on_download: bool,
+    /// Accept install telemetry reports from clients.
+    pub enable_telemetry: bool,
+    /// Telemetry reports accepted per client address per minute.
+    pub telemetry_rate_limit: u32,
+    /// Largest artifact the server downloads for a publish from a URL, in bytes.
+    pub fetch_max_bytes: u64,
+    /// Time allowed for downloading an artifact published from a URL.
+    pub fetch_timeout: Duration,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("/data"),
+            port: 8080,
+            json_logs: false,
+            admin_key: None,
+            api_keys: Vec::new(),
+            jwt_secret: None,
+            jwt_public_key_file: None,
+            jwt_jwks_url: None,
+            jwt_audience: None,
+            publish_pubkey: None,
+            storage: StorageOptions::default(),
+            scrub_interval: None,
+            strict_accept: true,
+            public_url: String::new(),
+            trust_proxy: false,
+            force_https: false,
+            security_headers: true,
+            hsts_max_age: None,
+            pages: PageLimits::default(),
+            max_search_terms: 16,
+            default_search_kind: "all".to_string(),
+            index_max_age: 60,
+            download_chunk_size: 64 * 1024,
+            max_base64_bytes: 256 * 1024,
+            publish_ips: IpRules::default(),
+            verify_on_download: false,
+            enable_telemetry: false,
+            telemetry_rate_limit: 30,
+            fetch_max_bytes: 100 * 1024 * 1024,
+            fetch_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Every invalid setting found, reported together so they can be fixed in one go.
+#[derive(Debug)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid configuration")?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Reads variables through `lookup`, collecting a problem for each one that fails to parse.
+struct Reader<F> {
+    lookup: F,
+    problems: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Reader<F> {
+    /// A set, non-empty variable.
+    fn string(&self, name: &str) -> Option<String> {
+        (self.lookup)(name).filter(|v| !v.trim().is_empty())
+    }
+
+    fn parse<T: FromStr>(&mut self, name: &str, expected: &str) -> Option<T> {
+        let value = self.string(name)?;
+        match value.trim().parse() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                self.problems
+                    .push(format!("{} must be {}, got {:?}", name, expected, value));
+                None
+            }
+        }
+    }
+
+    /// A number that must be at least 1.
+    fn positive<T: FromStr + Default + PartialEq>(&mut self, name: &str) -> Option<T> {
+        let value = self.parse::<T>(name, "a positive integer")?;
+        if value == T::default() {
+            self.problems
+                .push(format!("{} must be greater than 0", name));
+            return None;
+        }
+        Some(value)
+    }
+
+    /// A comma-separated list; unset is empty.
+    fn list(&self, name: &str) -> Vec<String> {
+        self.string(name)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// A comma-separated list of `key` or `key:prefix` entries; unset is empty.
+    fn api_keys(&mut self, name: &str) -> Vec<StaticKey> {
+        let mut keys = Vec::new();
+        for entry in self.list(name) {
+            match StaticKey::parse(&entry) {
+                Some(key) => keys.push(key),
+                None => self
+                    .problems
+                    .push(format!("{} has an entry without a key: {:?}", name, entry)),
+            }
+        }
+        keys
+    }
+
+    /// Base64 of a raw Ed25519 public key.
+    fn publish_key(&mut self, name: &str) -> Option<PublishKey> {
+        let value = self.string(name)?;
+        let key = PublishKey::parse(&value);
+        if key.is_none() {
+            self.problems.push(format!(
+                "{} must be base64 of a 32-byte Ed25519 public key",
+                name
+            ));
+        }
+        key
+    }
+
+    /// A comma-separated list of CIDR ranges; unset is empty.
+    fn cidrs(&mut self, name: &str) -> Vec<Cidr> {
+        let Some(value) = self.string(name) else {
+            return Vec::new();
+        };
+        let mut ranges = Vec::new();
+        for range in value.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            match range.parse() {
+                Ok(cidr) => ranges.push(cidr),
+                Err(e) => self.problems.push(format!("{}: {}", name, e)),
+            }
+        }
+        ranges
+    }
+
+    /// `1`/`true`/`yes` or `0`/`false`/`no`; unset is `false`.
+    fn flag(&mut self, name: &str) -> bool {
+        let Some(value) = self.string(name) else {
+            return false;
+        };
+        match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" => true,
+            "0" | "false" | "no" => false,
+            _ => {
+                self.problems.push(format!(
+                    "{} must be one of 1, true, yes, 0, false, no, got {:?}",
+                    name, value
+                ));
+                false
+            }
+        }
+    }
+}
+
+impl RegistryConfig {
+    /// Read the configuration from the process environment.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Read the configuration through `lookup`, failing with every invalid setting at once.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let mut env = Reader {
+            lookup,
+            problems: Vec::new(),
+        };
+        let defaults = Self::default();
+
+        let storage = StorageOptions {
+            count_downloads: !env.flag("REGISTRY_DISABLE_DOWNLOAD_COUNTS"),
+            artifactless_latest: env.flag("REGISTRY_ARTIFACTLESS_LATEST"),
+            // 0 keeps writes synchronous
+            index_flush_delay: env
+                .parse::<u64>("REGISTRY_INDEX_FLUSH_MS", "a number of milliseconds")
+                .filter(|&ms| ms > 0)
+                .map(Duration::from_millis),
+            max_platforms: env
+                .positive("REGISTRY_MAX_PLATFORMS")
+                .unwrap_or(defaults.storage.max_platforms),
+            read_root: env.string("REGISTRY_READ_DATA_DIR").map(PathBuf::from),
+            validate_dependencies: env.flag("REGISTRY_VALIDATE_DEPS_ON_PUBLISH"),
+            immutable_web_ui: env.flag("REGISTRY_IMMUTABLE_WEB_UI"),
+            cache_index: env.flag("REGISTRY_CACHE_INDEX"),
+            tombstone_retention: env
+                .parse::<u64>("REGISTRY_TOMBSTONE_DAYS", "a number of days")
+                .map(|days| Duration::from_secs(days * 24 * 60 * 60))
+                .unwrap_or(defaults.storage.tombstone_retention),
+        };
+
+        let max_page_size = env
+            .positive("REGISTRY_MAX_PAGE_SIZE")
+            .unwrap_or(defaults.pages.max_page_size);
+        let default_page_size = env
+            .positive("REGISTRY_DEFAULT_PAGE_SIZE")
+            .unwrap_or(defaults.pages.default_page_size.min(max_page_size));
+        if default_page_size > max_page_size {
+            env.problems.push(format!(
+                "REGISTRY_DEFAULT_PAGE_SIZE ({}) exceeds REGISTRY_MAX_PAGE_SIZE ({})",
+                default_page_size, max_page_size
+            ));
+        }
+
+        let public_url = env.string("REGISTRY_PUBLIC_URL").unwrap_or_default();
+        if !public_url.is_empty()
+            && !public_url.starts_with("http://")
+            && !public_url.starts_with("https://")
+        {
+            env.problems.push(format!(
+                "REGISTRY_PUBLIC_URL must start with http:// or https://, got {:?}",
+                public_url
+            ));
+        }
+
+        let jwt_secret = env.string("REGISTRY_JWT_SECRET");
+        let jwt_public_key_file = env
+            .string("REGISTRY_JWT_PUBLIC_KEY_FILE")
+            .map(PathBuf::from);
+        let jwt_jwks_url = env.string("REGISTRY_JWT_JWKS_URL");
+        let jwt_key_sources = [
+            jwt_secret.is_some(),
+            jwt_public_key_file.is_some(),
+            jwt_jwks_url.is_some(),
+        ];
+        if jwt_key_sources.iter().filter(|&&set| set).count() > 1 {
+            env.problems.push(
+                "Set only one of REGISTRY_JWT_SECRET, REGISTRY_JWT_PUBLIC_KEY_FILE and \
+                 REGISTRY_JWT_JWKS_URL"
+                    .to_string(),
+            );
+        }
+        if let Some(url) = jwt_jwks_url
+            .as_deref()
+            .filter(|u| !u.starts_with("https://"))
+        {
+            env.problems.push(format!(
+                "REGISTRY_JWT_JWKS_URL must start with https://, got {:?}",
+                url
+            ));
+        }
+
+        let log_format = env.string("REGISTRY_LOG_FORMAT");
+        if let Some(format) = log_format
+            .as_deref()
+            .filter(|f| !matches!(*f, "json" | "text"))
+        {
+            env.problems.push(format!(
+                "REGISTRY_LOG_FORMAT must be json or text, got {:?}",
+                format
+            ));
+        }
+
+        let default_search_kind = env
+            .string("REGISTRY_DEFAULT_SEARCH_KIND")
+            .unwrap_or(defaults.default_search_kind);
+        if !matches!(default_search_kind.as_str(), "all" | "package" | "plugin") {
+            env.problems.push(format!(
+                "REGISTRY_DEFAULT_SEARCH_KIND must be all, package or plugin, got {:?}",
+                default_search_kind
+            ));
+        }
+
+        let config = Self {
+            data_dir: env
+                .string("REGISTRY_DATA_DIR")
+                .map(PathBuf::from)
+                .unwrap_or(defaults.data_dir),
+            port: env.parse("PORT", "a port number").unwrap_or(defaults.port),
+            json_logs: log_format.as_deref() == Some("json"),
+            admin_key: env.string("REGISTRY_ADMIN_KEY"),
+            api_keys: env.api_keys("REGISTRY_API_KEYS"),
+            jwt_secret,
+            jwt_public_key_file,
+            jwt_jwks_url,
+            jwt_audience: env.string("REGISTRY_JWT_AUDIENCE"),
+            publish_pubkey: env.publish_key("REGISTRY_PUBLISH_PUBKEY"),
+            storage,
+            // 0 disables the scrub
+            scrub_interval: env
+                .parse::<u64>("REGISTRY_SCRUB_INTERVAL", "a number of seconds")
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            strict_accept: !env.flag("REGISTRY_IGNORE_ACCEPT"),
+            public_url,
+            trust_proxy: env.flag("REGISTRY_TRUST_PROXY"),
+            force_https: env.flag("REGISTRY_FORCE_HTTPS"),
+            security_headers: !env.flag("REGISTRY_NO_SECURITY_HEADERS"),
+            hsts_max_age: env.parse("REGISTRY_HSTS_MAX_AGE", "a number of seconds"),
+            pages: PageLimits {
+                default_page_size,
+                max_page_size,
+            },
+            max_search_terms: env
+                .positive("REGISTRY_MAX_SEARCH_TERMS")
+                .unwrap_or(defaults.max_search_terms),
+            default_search_kind,
+            index_max_age: env
+                .parse("REGISTRY_INDEX_MAX_AGE", "a number of seconds")
+                .unwrap_or(defaults.index_max_age),
+            download_chunk_size: env
+                .positive::<usize>("REGISTRY_DOWNLOAD_CHUNK_KB")
+                .map(|kb| kb * 1024)
+                .unwrap_or(defaults.download_chunk_size),
+            max_base64_bytes: env
+                .parse::<u64>("REGISTRY_MAX_BASE64_KB", "a number of KiB")
+                .map(|kb| kb * 1024)
+                .unwrap_or(defaults.max_base64_bytes),
+            publish_ips: IpRules {
+                allow: env.cidrs("REGISTRY_PUBLISH_ALLOW_CIDRS"),
+                deny: env.cidrs("REGISTRY_PUBLISH_DENY_CIDRS"),
+            },
+            verify_on_download: env.flag("REGISTRY_VERIFY_ON_DOWNLOAD"),
+            enable_telemetry: env.flag("REGISTRY_ENABLE_TELEMETRY"),
+            telemetry_rate_limit: env
+                .positive("REGISTRY_TELEMETRY_RATE_LIMIT")
+                .unwrap_or(defaults.telemetry_rate_limit),
+            fetch_max_bytes: env
+                .positive::<u64>("REGISTRY_FETCH_MAX_MB")
+                .map(|mb| mb * 1024 * 1024)
+                .unwrap_or(defaults.fetch_max_bytes),
+            fetch_timeout: env
+                .positive("REGISTRY_FETCH_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.fetch_timeout),
+        };
+
+        if env.problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError {
+                problems: env.problems,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn parse(vars: &[(&str, &str)]) -> Result<RegistryConfig, ConfigError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        RegistryConfig::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_defaults_when_unset() {
+        let config = parse(&[]).unwrap();
+        assert_eq!(config.data_dir, PathBuf::from("/data"));
+        assert_eq!(config.port, 8080);
+        assert!(config.strict_accept);
+        assert!(config.security_headers);
+        assert!(config.storage.count_downloads);
+        assert_eq!(config.pages, PageLimits::default());
+        assert_eq!(config.scrub_interval, None);
+    }
+
+    #[test]
+    fn test_valid_settings() {
+        let config = parse(&[
+            ("PORT", "9000"),
+            ("REGISTRY_DATA_DIR", "/srv/registry"),
+            ("REGISTRY_LOG_FORMAT", "json"),
+            ("REGISTRY_ADMIN_KEY", "secret"),
+            ("REGISTRY_API_KEYS", "ci-secret, acme-secret:acme.,"),
+            ("REGISTRY_DISABLE_DOWNLOAD_COUNTS", "true"),
+            ("REGISTRY_INDEX_FLUSH_MS", "250"),
+            ("REGISTRY_SCRUB_INTERVAL", "0"),
+            ("REGISTRY_MAX_PAGE_SIZE", "50"),
+            ("REGISTRY_TRUST_PROXY", "YES"),
+            ("REGISTRY_IGNORE_ACCEPT", "0"),
+            ("REGISTRY_PUBLIC_URL", "https://plugins.example.com"),
+            ("REGISTRY_DOWNLOAD_CHUNK_KB", "256"),
+            ("REGISTRY_PUBLISH_ALLOW_CIDRS", "10.0.0.0/8, 2001:db8::/32"),
+            ("REGISTRY_DEFAULT_SEARCH_KIND", "plugin"),
+            ("REGISTRY_FETCH_MAX_MB", "20"),
+            ("REGISTRY_FETCH_TIMEOUT_SECS", "15"),
+            (
+                "REGISTRY_PUBLISH_PUBKEY",
+                "11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=",
+            ),
+        ])
+        .unwrap();
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.data_dir, PathBuf::from("/srv/registry"));
+        assert!(config.json_logs);
+        assert_eq!(config.admin_key.as_deref(), Some("secret"));
+        let api_keys: Vec<_> = config
+            .api_keys
+            .iter()
+            .map(|k| (k.key.as_str(), k.prefix.as_str()))
+            .collect();
+        assert_eq!(api_keys, [("ci-secret", ""), ("acme-secret", "acme.")]);
+        assert!(!config.storage.count_downloads);
+        assert_eq!(
+            config.storage.index_flush_delay,
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(config.scrub_interval, None);
+        // The default page size follows a lowered cap
+        assert_eq!(
+            config.pages,
+            PageLimits {
+                default_page_size: 50,
+                max_page_size: 50,
+            }
+        );
+        assert!(config.trust_proxy);
+        assert!(config.strict_accept);
+        assert_eq!(config.download_chunk_size, 256 * 1024);
+        assert_eq!(config.publish_ips.allow.len(), 2);
+        assert!(config.publish_ips.deny.is_empty());
+        assert_eq!(config.default_search_kind, "plugin");
+        assert!(config.publish_pubkey.is_some());
+        assert_eq!(config.fetch_max_bytes, 20 * 1024 * 1024);
+        assert_eq!(config.fetch_timeout, Duration::from_secs(15));
+    }
+
+    #[test]
+    fn test_invalid_settings_reported_together() {
+        let err = parse(&[
+            ("PORT", "http"),
+            ("REGISTRY_MAX_SEARCH_TERMS", "0"),
+            ("REGISTRY_TRUST_PROXY", "maybe"),
+            ("REGISTRY_DEFAULT_PAGE_SIZE", "500"),
+            ("REGISTRY_MAX_PAGE_SIZE", "100"),
+            ("REGISTRY_PUBLIC_URL", "plugins.example.com"),
+            ("REGISTRY_PUBLISH_DENY_CIDRS", "10.0.0.0/8,10.0.0.0/40"),
+            ("REGISTRY_API_KEYS", "ci-secret,:acme."),
+            ("REGISTRY_PUBLISH_PUBKEY", "c2hvcnQ="),
+        ])
+        .unwrap_err();
+        assert_eq!(err.problems.len(), 8, "{}", err);
+        let message = err.to_string();
+        for name in [
+            "PORT",
+            "REGISTRY_MAX_SEARCH_TERMS",
+            "REGISTRY_TRUST_PROXY",
+            "REGISTRY_DEFAULT_PAGE_SIZE",
+            "REGISTRY_PUBLIC_URL",
+            "REGISTRY_PUBLISH_DENY_CIDRS",
+            "REGISTRY_API_KEYS",
+            "REGISTRY_PUBLISH_PUBKEY",
+        ] {
+            assert!(message.contains(name), "{} missing from {}", name, message);
+        }
+    }
+
+    #[test]
+    fn test_one_jwt_key_source() {
+        let config =
+            parse(&[("REGISTRY_JWT_JWKS_URL", "https://id.example.com/jwks.json")]).unwrap();
+        assert_eq!(
+            config.jwt_jwks_url.as_deref(),
+            Some("https://id.example.com/jwks.json")
+        );
+
+        let err = parse(&[
+            ("REGISTRY_JWT_SECRET", "s3cret"),
+            ("REGISTRY_JWT_JWKS_URL", "http://id.example.com/jwks.json"),
+        ])
+        .unwrap_err();
+        assert_eq!(err.problems.len(), 2, "{}", err);
+    }
+}
@@ -0,0 +1,187 @@
+//! Server-side download of artifacts published from a URL.
+
+use std::time::Duration;
+
+/// Redirects followed before a source is given up on.
+const MAX_REDIRECTS: usize = 5;
+
+/// Why an artifact could not be fetched from its source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchError {
+    /// Not an absolute `https://` URL.
+    InvalidUrl(String),
+    /// Connecting or reading failed, or the transfer ran past the timeout.
+    Unreachable(String),
+    /// The source answered with a status other than 2xx.
+    Status(u16),
+    /// The body is larger than the cap, in bytes.
+    TooLarge(u64),
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidUrl(msg) => write!(f, "Invalid source URL: {}", msg),
+            Self::Unreachable(msg) => write!(f, "Failed to fetch source: {}", msg),
+            Self::Status(status) => write!(f, "Source answered with HTTP {}", status),
+            Self::TooLarge(max) => write!(f, "Source is larger than {} bytes", max),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Downloads artifacts over HTTPS with a size cap and an overall timeout.
+pub struct SourceFetcher {
+    client: reqwest::Client,
+    max_bytes: u64,
+    allow_http: bool,
+}
+
+impl SourceFetcher {
+    pub fn new(max_bytes: u64, timeout: Duration) -> anyhow::Result<Self> {
+        Self::build(max_bytes, timeout, false)
+    }
+
+    /// Also accepts `http://` sources, for tests against a local mock server.
+    #[cfg(test)]
+    pub fn allowing_http(max_bytes: u64, timeout: Duration) -> anyhow::Result<Self> {
+        Self::build(max_bytes, timeout, true)
+    }
+
+    fn build(max_bytes: u64, timeout: Duration, allow_http: bool) -> anyhow::Result<Self> {
+        // A redirect must not downgrade the transfer to plain HTTP
+        let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if attempt.url().scheme() != "https" && !allow_http {
+                attempt.error("redirected away from https")
+            } else {
+                attempt.follow()
+            }
+        });
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .redirect(redirects)
+            .build()?;
+        Ok(Self {
+            client,
+            max_bytes,
+            allow_http,
+        })
+    }
+
+    /// Check `url` before anything is requested.
+    pub fn validate(&self, url: &str) -> Result<reqwest::Url, FetchError> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
+        let allowed = parsed.scheme() == "https" || (self.allow_http && parsed.scheme() == "http");
+        if !allowed {
+            return Err(FetchError::InvalidUrl(
+                "only https:// is accepted".to_string(),
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// The body at `url`, read until it ends or passes the size cap.
+    pub async fn fetch(&self, url: &str) -> Result<Vec<u8>, FetchError> {
+        let url = self.validate(url)?;
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| FetchError::Unreachable(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(FetchError::Status(response.status().as_u16()));
+        }
+        // The declared length is only a hint; the cap is enforced on what arrives
+        if response
+            .content_length()
+            .is_some_and(|len| len > self.max_bytes)
+        {
+            return Err(FetchError::TooLarge(self.max_bytes));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| FetchError::Unreachable(e.to_string()))?
+        {
+            if (body.len() + chunk.len()) as u64 > self.max_bytes {
+                return Err(FetchError::TooLarge(self.max_bytes));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn source(status: u16, body: &[u8]) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tasks.tar.gz"))
+            .respond_with(ResponseTemplate::new(status).set_body_bytes(body.to_vec()))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[test]
+    fn test_only_https_accepted() {
+        let fetcher = SourceFetcher::new(1024, Duration::from_secs(5)).unwrap();
+        assert!(fetcher.validate("https://example.com/a.tar.gz").is_ok());
+        for url in [
+            "http://example.com/a.tar.gz",
+            "file:///etc/passwd",
+            "ftp://example.com/a",
+            "example.com/a.tar.gz",
+        ] {
+            assert!(
+                matches!(fetcher.validate(url), Err(FetchError::InvalidUrl(_))),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_bounded_by_size_and_status() {
+        let fetcher = SourceFetcher::allowing_http(8, Duration::from_secs(5)).unwrap();
+
+        let server = source(200, b"artifact").await;
+        let url = format!("{}/tasks.tar.gz", server.uri());
+        assert_eq!(fetcher.fetch(&url).await.unwrap(), b"artifact");
+
+        let server = source(200, b"artifact!").await;
+        let url = format!("{}/tasks.tar.gz", server.uri());
+        assert_eq!(fetcher.fetch(&url).await, Err(FetchError::TooLarge(8)));
+
+        let server = source(404, b"").await;
+        let url = format!("{}/tasks.tar.gz", server.uri());
+        assert_eq!(fetcher.fetch(&url).await, Err(FetchError::Status(404)));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+        let fetcher = SourceFetcher::allowing_http(1024, Duration::from_millis(200)).unwrap();
+
+        let err = fetcher
+            .fetch(&format!("{}/tasks.tar.gz", server.uri()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FetchError::Unreachable(_)), "{:?}", err);
+    }
+}
@@ -9,10 +9,11 @@ use super::models::*;
 use super::enums::*;
 use async_trait::async_trait;
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::routing::{delete, get, patch, post, put};
 use axum::{Json, Router};
 use serde::Deserialize;
+use plugin_registry_core::Identity;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -34,24 +35,91 @@ impl axum::response::IntoResponse for ApiError {
 
 #[async_trait]
 pub trait IndexServiceHandler: Send + Sync + 'static {
-    async fn get_index(&self) -> Result<RegistryIndex, ApiError>;
+    async fn get_index(&self, query: IndexServiceGetIndexQuery, if_none_match: Option<String>, accept_encoding: Option<String>, accept_language: Option<String>) -> Result<axum::response::Response, ApiError>;
+    async fn get_checksum(&self) -> Result<axum::response::Response, ApiError>;
+    async fn compare(&self, body: IndexCompareRequest) -> Result<IndexComparison, ApiError>;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexServiceGetIndexQuery {
+    #[serde(rename = "updated_since")]
+    pub updated_since: Option<u64>,
+    pub since: Option<u64>,
+    pub lang: Option<String>,
 }
 
 async fn index_service_get_index<S: IndexServiceHandler>(
     State(state): State<Arc<S>>,
-) -> Result<Json<RegistryIndex>, ApiError> {
-    let result = state.get_index().await?;
+    Query(query): Query<IndexServiceGetIndexQuery>,
+    headers: HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let accept_encoding = headers.get("accept-encoding").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let accept_language = headers.get("accept-language").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.get_index(query, if_none_match, accept_encoding, accept_language).await?;
+    Ok(result)
+}
+
+async fn index_service_get_checksum<S: IndexServiceHandler>(
+    State(state): State<Arc<S>>,
+) -> Result<axum::response::Response, ApiError> {
+    let result = state.get_checksum().await?;
+    Ok(result)
+}
+
+async fn index_service_compare<S: IndexServiceHandler>(
+    State(state): State<Arc<S>>,
+    Json(body): Json<IndexCompareRequest>,
+) -> Result<Json<IndexComparison>, ApiError> {
+    let result = state.compare(body).await?;
     Ok(Json(result))
 }
 
 pub fn index_service_routes<S: IndexServiceHandler>() -> Router<Arc<S>> {
     Router::new()
         .route("/v1/index.json", get(index_service_get_index::<S>))
+        .route("/v1/index.sha256", get(index_service_get_checksum::<S>))
+        .route("/v1/index/compare", post(index_service_compare::<S>))
+}
+
+#[async_trait]
+pub trait CapabilitiesServiceHandler: Send + Sync + 'static {
+    async fn get(&self) -> Result<Capabilities, ApiError>;
+}
+
+async fn capabilities_service_get<S: CapabilitiesServiceHandler>(
+    State(state): State<Arc<S>>,
+) -> Result<Json<Capabilities>, ApiError> {
+    let result = state.get().await?;
+    Ok(Json(result))
+}
+
+pub fn capabilities_service_routes<S: CapabilitiesServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/capabilities.json", get(capabilities_service_get::<S>))
+}
+
+#[async_trait]
+pub trait StatsServiceHandler: Send + Sync + 'static {
+    async fn get(&self) -> Result<RegistryStats, ApiError>;
+}
+
+async fn stats_service_get<S: StatsServiceHandler>(
+    State(state): State<Arc<S>>,
+) -> Result<Json<RegistryStats>, ApiError> {
+    let result = state.get().await?;
+    Ok(Json(result))
+}
+
+pub fn stats_service_routes<S: StatsServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/stats.json", get(stats_service_get::<S>))
 }
 
 #[async_trait]
 pub trait SearchServiceHandler: Send + Sync + 'static {
-    async fn search(&self, query: SearchServiceSearchQuery) -> Result<SearchResults, ApiError>;
+    async fn search(&self, query: SearchServiceSearchQuery, accept_language: Option<String>, if_none_match: Option<String>) -> Result<axum::response::Response, ApiError>;
 }
 
 #[derive(Debug, Deserialize)]
@@ -59,14 +127,24 @@ pub trait SearchServiceHandler: Send + Sync + 'static {
 pub struct SearchServiceSearchQuery {
     pub q: String,
     pub kind: Option<String>,
+    pub lang: Option<String>,
+    #[serde(rename = "group_by")]
+    pub group_by: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub sort: Option<String>,
+    pub tags: Option<String>,
 }
 
 async fn search_service_search<S: SearchServiceHandler>(
     State(state): State<Arc<S>>,
     Query(query): Query<SearchServiceSearchQuery>,
-) -> Result<Json<SearchResults>, ApiError> {
-    let result = state.search(query).await?;
-    Ok(Json(result))
+    headers: HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let accept_language = headers.get("accept-language").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.search(query, accept_language, if_none_match).await?;
+    Ok(result)
 }
 
 pub fn search_service_routes<S: SearchServiceHandler>() -> Router<Arc<S>> {
@@ -76,45 +154,93 @@ pub fn search_service_routes<S: SearchServiceHandler>() -> Router<Arc<S>> {
 
 #[async_trait]
 pub trait PackageServiceHandler: Send + Sync + 'static {
-    async fn get_latest(&self, id: String) -> Result<PackageInfo, ApiError>;
-    async fn get_version(&self, id: String, version: String) -> Result<PackageInfo, ApiError>;
-    async fn download(&self, id: String, version: String, platform: String) -> Result<axum::response::Response, ApiError>;
+    async fn get_latest(&self, id: String, query: PackageServiceGetLatestQuery) -> Result<PackageInfo, ApiError>;
+    async fn get_latest_batch(&self, body: Vec<String>) -> Result<std::collections::HashMap<String, PackageInfo>, ApiError>;
+    async fn list_versions(&self, id: String) -> Result<Vec<String>, ApiError>;
+    async fn get_version(&self, id: String, version: String, query: PackageServiceGetVersionQuery, if_none_match: Option<String>) -> Result<axum::response::Response, ApiError>;
+    async fn download(&self, id: String, version: String, platform: String, query: PackageServiceDownloadQuery, accept: Option<String>, if_none_match: Option<String>, range: Option<String>) -> Result<axum::response::Response, ApiError>;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageServiceGetLatestQuery {
+    pub platform: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageServiceGetVersionQuery {
+    pub platform: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageServiceDownloadQuery {
+    pub verify: Option<bool>,
+    pub filename: Option<String>,
 }
 
 async fn package_service_get_latest<S: PackageServiceHandler>(
     State(state): State<Arc<S>>,
     Path(id): Path<String>,
+    Query(query): Query<PackageServiceGetLatestQuery>,
 ) -> Result<Json<PackageInfo>, ApiError> {
-    let result = state.get_latest(id).await?;
+    let result = state.get_latest(id, query).await?;
+    Ok(Json(result))
+}
+
+async fn package_service_get_latest_batch<S: PackageServiceHandler>(
+    State(state): State<Arc<S>>,
+    Json(body): Json<Vec<String>>,
+) -> Result<Json<std::collections::HashMap<String, PackageInfo>>, ApiError> {
+    let result = state.get_latest_batch(body).await?;
+    Ok(Json(result))
+}
+
+async fn package_service_list_versions<S: PackageServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let result = state.list_versions(id).await?;
     Ok(Json(result))
 }
 
 async fn package_service_get_version<S: PackageServiceHandler>(
     State(state): State<Arc<S>>,
     Path((id, version)):  Path<(String, String)>,
-) -> Result<Json<PackageInfo>, ApiError> {
-    let result = state.get_version(id, version).await?;
-    Ok(Json(result))
+    Query(query): Query<PackageServiceGetVersionQuery>,
+    headers: HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.get_version(id, version, query, if_none_match).await?;
+    Ok(result)
 }
 
 async fn package_service_download<S: PackageServiceHandler>(
     State(state): State<Arc<S>>,
     Path((id, version, platform)):  Path<(String, String, String)>,
+    Query(query): Query<PackageServiceDownloadQuery>,
+    headers: HeaderMap,
 ) -> Result<axum::response::Response, ApiError> {
-    let result = state.download(id, version, platform).await?;
+    let accept = headers.get("accept").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let range = headers.get("range").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.download(id, version, platform, query, accept, if_none_match, range).await?;
     Ok(result)
 }
 
 pub fn package_service_routes<S: PackageServiceHandler>() -> Router<Arc<S>> {
     Router::new()
         .route("/v1/packages/:id/latest.json", get(package_service_get_latest::<S>))
-        .route("/v1/packages/:id/{version}.json", get(package_service_get_version::<S>))
-        .route("/v1/packages/:id/:version/{platform}.tar.gz", get(package_service_download::<S>))
+        .route("/v1/packages/batch", post(package_service_get_latest_batch::<S>))
+        .route("/v1/packages/:id/versions.json", get(package_service_list_versions::<S>))
+        .route("/v1/packages/:id/:version", get(package_service_get_version::<S>))
+        .route("/v1/packages/:id/:version/:platform", get(package_service_download::<S>))
 }
 
 #[async_trait]
 pub trait PackagePublishServiceHandler: Send + Sync + 'static {
-    async fn publish(&self, id: String, version: String, platform: String, query: PackagePublishServicePublishQuery, body: Vec<u8>) -> Result<PublishResponse, ApiError>;
+    async fn publish(&self, id: String, version: String, platform: String, query: PackagePublishServicePublishQuery, body: Vec<u8>, identity: Identity) -> Result<PublishResponse, ApiError>;
 }
 
 #[derive(Debug, Deserialize)]
@@ -124,15 +250,22 @@ pub struct PackagePublishServicePublishQuery {
     pub description: Option<String>,
     pub plugin_type: Option<String>,
     pub author: Option<String>,
+    #[serde(rename = "published_at")]
+    pub published_at: Option<u64>,
+    pub overwrite: Option<bool>,
+    pub changelog: Option<String>,
+    pub dependencies: Option<String>,
+    pub signature: Option<String>,
 }
 
 async fn package_publish_service_publish<S: PackagePublishServiceHandler>(
     State(state): State<Arc<S>>,
     Path((id, version, platform)):  Path<(String, String, String)>,
     Query(query): Query<PackagePublishServicePublishQuery>,
+    axum::Extension(identity): axum::Extension<Identity>,
     body: axum::body::Bytes,
 ) -> Result<(StatusCode, Json<PublishResponse>), ApiError> {
-    let result = state.publish(id, version, platform, query, body.to_vec()).await?;
+    let result = state.publish(id, version, platform, query, body.to_vec(), identity).await?;
     Ok((StatusCode::CREATED, Json(result)))
 }
 
@@ -143,45 +276,195 @@ pub fn package_publish_service_routes<S: PackagePublishServiceHandler>() -> Rout
 
 #[async_trait]
 pub trait PluginServiceHandler: Send + Sync + 'static {
-    async fn get_latest(&self, id: String) -> Result<PluginInfo, ApiError>;
-    async fn get_version(&self, id: String, version: String) -> Result<PluginInfo, ApiError>;
-    async fn download(&self, id: String, version: String, platform: String) -> Result<axum::response::Response, ApiError>;
+    async fn get_latest(&self, id: String, query: PluginServiceGetLatestQuery) -> Result<PluginInfo, ApiError>;
+    async fn get_latest_batch(&self, body: Vec<String>) -> Result<std::collections::HashMap<String, PluginInfo>, ApiError>;
+    async fn get_latest_version(&self, id: String, if_none_match: Option<String>) -> Result<axum::response::Response, ApiError>;
+    async fn get_bundle(&self, id: String, host: Option<String>, x_forwarded_proto: Option<String>, x_forwarded_host: Option<String>) -> Result<PluginBundle, ApiError>;
+    async fn get_dependents(&self, id: String) -> Result<PluginDependents, ApiError>;
+    async fn list_versions(&self, id: String, if_none_match: Option<String>) -> Result<axum::response::Response, ApiError>;
+    async fn get_platform_versions(&self, id: String, platform: String) -> Result<PlatformVersions, ApiError>;
+    async fn get_version(&self, id: String, version: String, query: PluginServiceGetVersionQuery, if_none_match: Option<String>) -> Result<axum::response::Response, ApiError>;
+    async fn get_raw_info(&self, id: String, version: String) -> Result<axum::response::Response, ApiError>;
+    async fn list_platforms(&self, id: String, version: String) -> Result<Vec<String>, ApiError>;
+    async fn resolve_dependencies(&self, id: String, version: String) -> Result<Vec<ResolvedDependency>, ApiError>;
+    async fn download_asset(&self, id: String, version: String, name: String) -> Result<axum::response::Response, ApiError>;
+    async fn download(&self, id: String, version: String, platform: String, query: PluginServiceDownloadQuery, accept: Option<String>, if_none_match: Option<String>, range: Option<String>) -> Result<axum::response::Response, ApiError>;
+    async fn get_artifact_base64(&self, id: String, version: String, platform: String) -> Result<ArtifactBase64, ApiError>;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginServiceGetLatestQuery {
+    pub platform: Option<String>,
+    pub req: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginServiceGetVersionQuery {
+    pub platform: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginServiceDownloadQuery {
+    pub verify: Option<bool>,
+    pub filename: Option<String>,
 }
 
 async fn plugin_service_get_latest<S: PluginServiceHandler>(
     State(state): State<Arc<S>>,
     Path(id): Path<String>,
+    Query(query): Query<PluginServiceGetLatestQuery>,
 ) -> Result<Json<PluginInfo>, ApiError> {
-    let result = state.get_latest(id).await?;
+    let result = state.get_latest(id, query).await?;
+    Ok(Json(result))
+}
+
+async fn plugin_service_get_latest_batch<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Json(body): Json<Vec<String>>,
+) -> Result<Json<std::collections::HashMap<String, PluginInfo>>, ApiError> {
+    let result = state.get_latest_batch(body).await?;
+    Ok(Json(result))
+}
+
+async fn plugin_service_get_latest_version<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.get_latest_version(id, if_none_match).await?;
+    Ok(result)
+}
+
+async fn plugin_service_get_bundle<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<PluginBundle>, ApiError> {
+    let host = headers.get("host").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let x_forwarded_proto = headers.get("x-forwarded-proto").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let x_forwarded_host = headers.get("x-forwarded-host").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.get_bundle(id, host, x_forwarded_proto, x_forwarded_host).await?;
+    Ok(Json(result))
+}
+
+async fn plugin_service_get_dependents<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path(id): Path<String>,
+) -> Result<Json<PluginDependents>, ApiError> {
+    let result = state.get_dependents(id).await?;
+    Ok(Json(result))
+}
+
+async fn plugin_service_list_versions<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.list_versions(id, if_none_match).await?;
+    Ok(result)
+}
+
+async fn plugin_service_get_platform_versions<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, platform)):  Path<(String, String)>,
+) -> Result<Json<PlatformVersions>, ApiError> {
+    let result = state.get_platform_versions(id, platform).await?;
     Ok(Json(result))
 }
 
 async fn plugin_service_get_version<S: PluginServiceHandler>(
     State(state): State<Arc<S>>,
     Path((id, version)):  Path<(String, String)>,
-) -> Result<Json<PluginInfo>, ApiError> {
-    let result = state.get_version(id, version).await?;
+    Query(query): Query<PluginServiceGetVersionQuery>,
+    headers: HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.get_version(id, version, query, if_none_match).await?;
+    Ok(result)
+}
+
+async fn plugin_service_get_raw_info<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version)):  Path<(String, String)>,
+) -> Result<axum::response::Response, ApiError> {
+    let result = state.get_raw_info(id, version).await?;
+    Ok(result)
+}
+
+async fn plugin_service_list_platforms<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version)):  Path<(String, String)>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let result = state.list_platforms(id, version).await?;
     Ok(Json(result))
 }
 
+async fn plugin_service_resolve_dependencies<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version)):  Path<(String, String)>,
+) -> Result<Json<Vec<ResolvedDependency>>, ApiError> {
+    let result = state.resolve_dependencies(id, version).await?;
+    Ok(Json(result))
+}
+
+async fn plugin_service_download_asset<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version, name)):  Path<(String, String, String)>,
+) -> Result<axum::response::Response, ApiError> {
+    let result = state.download_asset(id, version, name).await?;
+    Ok(result)
+}
+
 async fn plugin_service_download<S: PluginServiceHandler>(
     State(state): State<Arc<S>>,
     Path((id, version, platform)):  Path<(String, String, String)>,
+    Query(query): Query<PluginServiceDownloadQuery>,
+    headers: HeaderMap,
 ) -> Result<axum::response::Response, ApiError> {
-    let result = state.download(id, version, platform).await?;
+    let accept = headers.get("accept").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let range = headers.get("range").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.download(id, version, platform, query, accept, if_none_match, range).await?;
     Ok(result)
 }
 
+async fn plugin_service_get_artifact_base64<S: PluginServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version, platform)):  Path<(String, String, String)>,
+) -> Result<Json<ArtifactBase64>, ApiError> {
+    let result = state.get_artifact_base64(id, version, platform).await?;
+    Ok(Json(result))
+}
+
 pub fn plugin_service_routes<S: PluginServiceHandler>() -> Router<Arc<S>> {
     Router::new()
         .route("/v1/plugins/:id/latest.json", get(plugin_service_get_latest::<S>))
-        .route("/v1/plugins/:id/{version}.json", get(plugin_service_get_version::<S>))
-        .route("/v1/plugins/:id/:version/{platform}.tar.gz", get(plugin_service_download::<S>))
+        .route("/v1/plugins/batch", post(plugin_service_get_latest_batch::<S>))
+        .route("/v1/plugins/:id/latest.txt", get(plugin_service_get_latest_version::<S>))
+        .route("/v1/plugins/:id/bundle.json", get(plugin_service_get_bundle::<S>))
+        .route("/v1/plugins/:id/dependents.json", get(plugin_service_get_dependents::<S>))
+        .route("/v1/plugins/:id/versions.json", get(plugin_service_list_versions::<S>))
+        .route("/v1/plugins/:id/platform/:platform/versions.json", get(plugin_service_get_platform_versions::<S>))
+        .route("/v1/plugins/:id/:version", get(plugin_service_get_version::<S>))
+        .route("/v1/plugins/:id/:version/info.raw.json", get(plugin_service_get_raw_info::<S>))
+        .route("/v1/plugins/:id/:version/platforms.json", get(plugin_service_list_platforms::<S>))
+        .route("/v1/plugins/:id/:version/resolve.json", get(plugin_service_resolve_dependencies::<S>))
+        .route("/v1/plugins/:id/:version/assets/:name", get(plugin_service_download_asset::<S>))
+        .route("/v1/plugins/:id/:version/:platform", get(plugin_service_download::<S>))
+        .route("/v1/plugins/:id/:version/:platform/base64.json", get(plugin_service_get_artifact_base64::<S>))
 }
 
 #[async_trait]
 pub trait PluginPublishServiceHandler: Send + Sync + 'static {
-    async fn publish(&self, id: String, version: String, platform: String, query: PluginPublishServicePublishQuery, body: Vec<u8>) -> Result<PublishResponse, ApiError>;
+    async fn publish(&self, id: String, version: String, platform: String, query: PluginPublishServicePublishQuery, body: Vec<u8>, identity: Identity) -> Result<PublishResponse, ApiError>;
+    async fn publish_batch(&self, id: String, version: String, query: PluginPublishServicePublishBatchQuery, body: axum::extract::Multipart, identity: Identity) -> Result<axum::response::Response, ApiError>;
+    async fn publish_from_url(&self, id: String, version: String, platform: String, query: PluginPublishServicePublishFromUrlQuery, body: UrlPublishRequest, identity: Identity) -> Result<UrlPublishResponse, ApiError>;
+    async fn publish_asset(&self, id: String, version: String, name: String, body: Vec<u8>, identity: Identity) -> Result<AssetMeta, ApiError>;
 }
 
 #[derive(Debug, Deserialize)]
@@ -191,34 +474,180 @@ pub struct PluginPublishServicePublishQuery {
     pub description: Option<String>,
     pub plugin_type: Option<String>,
     pub author: Option<String>,
+    #[serde(rename = "published_at")]
+    pub published_at: Option<u64>,
+    pub overwrite: Option<bool>,
+    pub changelog: Option<String>,
+    pub dependencies: Option<String>,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginPublishServicePublishBatchQuery {
+    pub name: String,
+    pub description: Option<String>,
+    pub plugin_type: Option<String>,
+    pub author: Option<String>,
+    #[serde(rename = "published_at")]
+    pub published_at: Option<u64>,
+    pub overwrite: Option<bool>,
+    pub changelog: Option<String>,
+    pub dependencies: Option<String>,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginPublishServicePublishFromUrlQuery {
+    pub name: String,
+    pub description: Option<String>,
+    pub plugin_type: Option<String>,
+    pub author: Option<String>,
+    #[serde(rename = "published_at")]
+    pub published_at: Option<u64>,
+    pub overwrite: Option<bool>,
+    pub changelog: Option<String>,
+    pub dependencies: Option<String>,
+    pub signature: Option<String>,
 }
 
 async fn plugin_publish_service_publish<S: PluginPublishServiceHandler>(
     State(state): State<Arc<S>>,
     Path((id, version, platform)):  Path<(String, String, String)>,
     Query(query): Query<PluginPublishServicePublishQuery>,
+    axum::Extension(identity): axum::Extension<Identity>,
     body: axum::body::Bytes,
 ) -> Result<(StatusCode, Json<PublishResponse>), ApiError> {
-    let result = state.publish(id, version, platform, query, body.to_vec()).await?;
+    let result = state.publish(id, version, platform, query, body.to_vec(), identity).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+async fn plugin_publish_service_publish_batch<S: PluginPublishServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version)):  Path<(String, String)>,
+    Query(query): Query<PluginPublishServicePublishBatchQuery>,
+    axum::Extension(identity): axum::Extension<Identity>,
+    body: axum::extract::Multipart,
+) -> Result<axum::response::Response, ApiError> {
+    let result = state.publish_batch(id, version, query, body, identity).await?;
+    Ok(result)
+}
+
+async fn plugin_publish_service_publish_from_url<S: PluginPublishServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version, platform)):  Path<(String, String, String)>,
+    Query(query): Query<PluginPublishServicePublishFromUrlQuery>,
+    axum::Extension(identity): axum::Extension<Identity>,
+    Json(body): Json<UrlPublishRequest>,
+) -> Result<(StatusCode, Json<UrlPublishResponse>), ApiError> {
+    let result = state.publish_from_url(id, version, platform, query, body, identity).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+async fn plugin_publish_service_publish_asset<S: PluginPublishServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version, name)):  Path<(String, String, String)>,
+    axum::Extension(identity): axum::Extension<Identity>,
+    body: axum::body::Bytes,
+) -> Result<(StatusCode, Json<AssetMeta>), ApiError> {
+    let result = state.publish_asset(id, version, name, body.to_vec(), identity).await?;
     Ok((StatusCode::CREATED, Json(result)))
 }
 
 pub fn plugin_publish_service_routes<S: PluginPublishServiceHandler>() -> Router<Arc<S>> {
     Router::new()
         .route("/v1/publish/plugins/:id/:version/:platform", post(plugin_publish_service_publish::<S>))
+        .route("/v1/publish/plugins/:id/:version", post(plugin_publish_service_publish_batch::<S>))
+        .route("/v1/publish/plugins/:id/:version/:platform/from-url", post(plugin_publish_service_publish_from_url::<S>))
+        .route("/v1/publish/plugins/:id/:version/assets/:name", post(plugin_publish_service_publish_asset::<S>))
+}
+
+#[async_trait]
+pub trait PluginMetaPublishServiceHandler: Send + Sync + 'static {
+    async fn publish(&self, id: String, version: String, body: PluginMetaRequest, identity: Identity) -> Result<PublishResponse, ApiError>;
+}
+
+async fn plugin_meta_publish_service_publish<S: PluginMetaPublishServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version)): Path<(String, String)>,
+    axum::Extension(identity): axum::Extension<Identity>,
+    Json(body): Json<PluginMetaRequest>,
+) -> Result<(StatusCode, Json<PublishResponse>), ApiError> {
+    let result = state.publish(id, version, body, identity).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+pub fn plugin_meta_publish_service_routes<S: PluginMetaPublishServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/plugins/:id/:version/meta", post(plugin_meta_publish_service_publish::<S>))
+}
+
+#[async_trait]
+pub trait PluginDeleteServiceHandler: Send + Sync + 'static {
+    async fn delete(&self, id: String, version: String, identity: Identity) -> Result<DeleteVersionResponse, ApiError>;
+}
+
+async fn plugin_delete_service_delete<S: PluginDeleteServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version)): Path<(String, String)>,
+    axum::Extension(identity): axum::Extension<Identity>,
+) -> Result<Json<DeleteVersionResponse>, ApiError> {
+    let result = state.delete(id, version, identity).await?;
+    Ok(Json(result))
+}
+
+pub fn plugin_delete_service_routes<S: PluginDeleteServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/plugins/:id/:version", delete(plugin_delete_service_delete::<S>))
+}
+
+#[async_trait]
+pub trait PluginYankServiceHandler: Send + Sync + 'static {
+    async fn yank(&self, id: String, version: String, query: PluginYankServiceYankQuery, identity: Identity) -> Result<YankResponse, ApiError>;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginYankServiceYankQuery {
+    pub undo: Option<bool>,
+}
+
+async fn plugin_yank_service_yank<S: PluginYankServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version)): Path<(String, String)>,
+    Query(query): Query<PluginYankServiceYankQuery>,
+    axum::Extension(identity): axum::Extension<Identity>,
+) -> Result<Json<YankResponse>, ApiError> {
+    let result = state.yank(id, version, query, identity).await?;
+    Ok(Json(result))
+}
+
+pub fn plugin_yank_service_routes<S: PluginYankServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/plugins/:id/:version/yank", post(plugin_yank_service_yank::<S>))
 }
 
 #[async_trait]
 pub trait PluginWebUiPublishServiceHandler: Send + Sync + 'static {
-    async fn publish(&self, id: String, version: String, body: Vec<u8>) -> Result<PublishResponse, ApiError>;
+    async fn publish(&self, id: String, version: String, query: PluginWebUiPublishServicePublishQuery, body: Vec<u8>, identity: Identity) -> Result<PublishResponse, ApiError>;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginWebUiPublishServicePublishQuery {
+    #[serde(rename = "allow_overwrite")]
+    pub allow_overwrite: Option<bool>,
 }
 
 async fn plugin_web_ui_publish_service_publish<S: PluginWebUiPublishServiceHandler>(
     State(state): State<Arc<S>>,
     Path((id, version)): Path<(String, String)>,
+    Query(query): Query<PluginWebUiPublishServicePublishQuery>,
+    axum::Extension(identity): axum::Extension<Identity>,
     body: axum::body::Bytes,
 ) -> Result<(StatusCode, Json<PublishResponse>), ApiError> {
-    let result = state.publish(id, version, body.to_vec()).await?;
+    let result = state.publish(id, version, query, body.to_vec(), identity).await?;
     Ok((StatusCode::CREATED, Json(result)))
 }
 
@@ -230,6 +659,7 @@ pub fn plugin_web_ui_publish_service_routes<S: PluginWebUiPublishServiceHandler>
 #[async_trait]
 pub trait PluginWebUiServiceHandler: Send + Sync + 'static {
     async fn download(&self, id: String, version: String) -> Result<axum::response::Response, ApiError>;
+    async fn get_import_map(&self, host: Option<String>, x_forwarded_proto: Option<String>, x_forwarded_host: Option<String>) -> Result<ImportMap, ApiError>;
 }
 
 async fn plugin_web_ui_service_download<S: PluginWebUiServiceHandler>(
@@ -240,19 +670,141 @@ async fn plugin_web_ui_service_download<S: PluginWebUiServiceHandler>(
     Ok(result)
 }
 
+async fn plugin_web_ui_service_get_import_map<S: PluginWebUiServiceHandler>(
+    State(state): State<Arc<S>>,
+    headers: HeaderMap,
+) -> Result<Json<ImportMap>, ApiError> {
+    let host = headers.get("host").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let x_forwarded_proto = headers.get("x-forwarded-proto").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let x_forwarded_host = headers.get("x-forwarded-host").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+    let result = state.get_import_map(host, x_forwarded_proto, x_forwarded_host).await?;
+    Ok(Json(result))
+}
+
 pub fn plugin_web_ui_service_routes<S: PluginWebUiServiceHandler>() -> Router<Arc<S>> {
     Router::new()
         .route("/v1/plugins/:id/:version/web.js", get(plugin_web_ui_service_download::<S>))
+        .route("/v1/plugins/importmap.json", get(plugin_web_ui_service_get_import_map::<S>))
+}
+
+#[async_trait]
+pub trait AdminKeyServiceHandler: Send + Sync + 'static {
+    async fn create(&self, body: CreateApiKeyRequest) -> Result<ApiKeyCreated, ApiError>;
+    async fn revoke(&self, label: String) -> Result<RevokeApiKeyResponse, ApiError>;
+}
+
+async fn admin_key_service_create<S: AdminKeyServiceHandler>(
+    State(state): State<Arc<S>>,
+    Json(body): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<ApiKeyCreated>), ApiError> {
+    let result = state.create(body).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+async fn admin_key_service_revoke<S: AdminKeyServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path(label): Path<String>,
+) -> Result<Json<RevokeApiKeyResponse>, ApiError> {
+    let result = state.revoke(label).await?;
+    Ok(Json(result))
+}
+
+pub fn admin_key_service_routes<S: AdminKeyServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/admin/keys", post(admin_key_service_create::<S>))
+        .route("/v1/admin/keys/:label", delete(admin_key_service_revoke::<S>))
+}
+
+#[async_trait]
+pub trait AdminAuditServiceHandler: Send + Sync + 'static {
+    async fn list(&self, query: AdminAuditServiceListQuery) -> Result<AuditLog, ApiError>;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAuditServiceListQuery {
+    pub kind: Option<String>,
+    pub id: Option<String>,
+    pub since: Option<u64>,
+    pub limit: Option<u32>,
+}
+
+async fn admin_audit_service_list<S: AdminAuditServiceHandler>(
+    State(state): State<Arc<S>>,
+    Query(query): Query<AdminAuditServiceListQuery>,
+) -> Result<Json<AuditLog>, ApiError> {
+    let result = state.list(query).await?;
+    Ok(Json(result))
+}
+
+pub fn admin_audit_service_routes<S: AdminAuditServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/admin/audit", get(admin_audit_service_list::<S>))
+}
+
+#[async_trait]
+pub trait TelemetryServiceHandler: Send + Sync + 'static {
+    async fn report(&self, id: String, version: String, platform: String, body: TelemetryReport) -> Result<InstallTelemetry, ApiError>;
+    async fn get(&self, id: String, version: String) -> Result<InstallTelemetry, ApiError>;
+}
+
+async fn telemetry_service_report<S: TelemetryServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version, platform)):  Path<(String, String, String)>,
+    Json(body): Json<TelemetryReport>,
+) -> Result<Json<InstallTelemetry>, ApiError> {
+    let result = state.report(id, version, platform, body).await?;
+    Ok(Json(result))
+}
+
+async fn telemetry_service_get<S: TelemetryServiceHandler>(
+    State(state): State<Arc<S>>,
+    Path((id, version)):  Path<(String, String)>,
+) -> Result<Json<InstallTelemetry>, ApiError> {
+    let result = state.get(id, version).await?;
+    Ok(Json(result))
+}
+
+pub fn telemetry_service_routes<S: TelemetryServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/plugins/:id/:version/:platform/telemetry", post(telemetry_service_report::<S>))
+        .route("/v1/plugins/:id/:version/telemetry.json", get(telemetry_service_get::<S>))
+}
+
+#[async_trait]
+pub trait AdminRepairServiceHandler: Send + Sync + 'static {
+    async fn repair_package_links(&self) -> Result<RepairReport, ApiError>;
+}
+
+async fn admin_repair_service_repair_package_links<S: AdminRepairServiceHandler>(
+    State(state): State<Arc<S>>,
+) -> Result<Json<RepairReport>, ApiError> {
+    let result = state.repair_package_links().await?;
+    Ok(Json(result))
+}
+
+pub fn admin_repair_service_routes<S: AdminRepairServiceHandler>() -> Router<Arc<S>> {
+    Router::new()
+        .route("/v1/admin/repair/package-links", post(admin_repair_service_repair_package_links::<S>))
 }
 
-pub fn create_router<S: IndexServiceHandler + SearchServiceHandler + PackageServiceHandler + PackagePublishServiceHandler + PluginServiceHandler + PluginPublishServiceHandler + PluginWebUiPublishServiceHandler + PluginWebUiServiceHandler>() -> Router<Arc<S>> {
+pub fn create_router<S: IndexServiceHandler + CapabilitiesServiceHandler + StatsServiceHandler + SearchServiceHandler + PackageServiceHandler + PackagePublishServiceHandler + PluginServiceHandler + PluginPublishServiceHandler + PluginMetaPublishServiceHandler + PluginDeleteServiceHandler + PluginYankServiceHandler + PluginWebUiPublishServiceHandler + PluginWebUiServiceHandler + AdminKeyServiceHandler + AdminAuditServiceHandler + TelemetryServiceHandler + AdminRepairServiceHandler>() -> Router<Arc<S>> {
     Router::new()
         .merge(index_service_routes())
+        .merge(capabilities_service_routes())
+        .merge(stats_service_routes())
         .merge(search_service_routes())
         .merge(package_service_routes())
         .merge(package_publish_service_routes())
         .merge(plugin_service_routes())
+        .merge(plugin_meta_publish_service_routes())
+        .merge(plugin_delete_service_routes())
+        .merge(plugin_yank_service_routes())
         .merge(plugin_web_ui_publish_service_routes())
         .merge(plugin_publish_service_routes())
         .merge(plugin_web_ui_service_routes())
+        .merge(admin_key_service_routes())
+        .merge(admin_audit_service_routes())
+        .merge(telemetry_service_routes())
+        .merge(admin_repair_service_routes())
 }
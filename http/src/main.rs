@@ -1,29 +1,270 @@
+mod config;
+mod fetch;
+#[allow(dead_code, unused_imports, clippy::too_many_arguments)]
 mod generated;
+mod ip_filter;
+mod metrics;
+mod pagination;
+mod rate_limit;
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use async_trait::async_trait;
 use axum::{
     body::Body,
-    http::{header, StatusCode},
+    extract::{ConnectInfo, MatchedPath, RawPathParams, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::IntoResponse,
     routing::get,
     Json, Router,
 };
+use config::RegistryConfig;
+use fetch::{FetchError, SourceFetcher};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use generated::models::*;
 use generated::server::*;
 use lib_http_common::version_header_layer;
-use plugin_registry_core::RegistryStorage;
-use std::net::SocketAddr;
-use std::path::PathBuf;
+use metrics::Metrics;
+use plugin_registry_core::{
+    accepts, content_etag, content_type_for, encode_base64, etag_matches, group_by_package,
+    has_all_tags, matches_terms, normalize_platform, parse_accept_language, sanitize_filename,
+    search_terms, sha256_hex, strip_extension, strong_etag, tag_filter, truncate_chars,
+    weak_content_etag, weak_etag, Action, ArtifactExists, AssetExists, AuditEntry, AuditFilter,
+    AuthError, Authorizer, DependencyCycle, Identity, InstallOutcome, InvalidAssetName, InvalidId,
+    InvalidVersion, InvalidVersionReq, JwtAuthorizer, KeyStore, LocalEntry, PluginVersionMeta,
+    PublishExtras, RegistryStorage, SearchSort, TokenFileAuthorizer, TooManyPlatforms,
+    UnsatisfiableDependency, UnsatisfiedDependencies, WebUiExists,
+};
+use rate_limit::RateLimiter;
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, ReadBuf};
 use tokio_util::io::ReaderStream;
+use tower::Layer;
+use tower_http::compression::predicate::{DefaultPredicate, NotForContentType, Predicate};
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 struct AppState {
-    storage: RegistryStorage,
+    storage: Arc<RegistryStorage>,
+    keys: Arc<KeyStore>,
+    /// Decides who may publish and use the admin API.
+    authorizer: Arc<dyn Authorizer>,
+    config: RegistryConfig,
+    /// When the server started, for `uptime_seconds` in `/v1/status.json`.
+    started_at: std::time::Instant,
+    /// Gzip-compressed index body, keyed by the ETag of the uncompressed JSON.
+    index_gzip: std::sync::Mutex<Option<(String, axum::body::Bytes)>>,
+    /// Caps install telemetry reports per client.
+    telemetry_limiter: RateLimiter,
+    /// Counters served by `/metrics`.
+    metrics: Arc<Metrics>,
+    /// Downloads artifacts for publishes from a URL.
+    fetcher: SourceFetcher,
+}
+
+impl AppState {
+    /// Compressed index for `etag`, reusing the previous buffer while the index is unchanged.
+    fn index_gzip(&self, etag: &str, json: &[u8]) -> std::io::Result<axum::body::Bytes> {
+        let mut cached = self.index_gzip.lock().unwrap();
+        if let Some((cached_etag, gzip)) = cached.as_ref() {
+            if cached_etag == etag {
+                return Ok(gzip.clone());
+            }
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json)?;
+        let gzip = axum::body::Bytes::from(encoder.finish()?);
+        *cached = Some((etag.to_string(), gzip.clone()));
+        Ok(gzip)
+    }
+
+    /// Unwrap a lookup of `id`, answering 410 instead of 404 for recently deleted ids.
+    async fn or_gone<T, E>(
+        &self,
+        result: Result<T, E>,
+        kind: &str,
+        id: &str,
+        msg: &str,
+    ) -> Result<T, ApiError> {
+        match result {
+            Ok(value) => Ok(value),
+            Err(_) => Err(self.missing(kind, id, msg).await),
+        }
+    }
+
+    async fn missing(&self, kind: &str, id: &str, msg: &str) -> ApiError {
+        match self.storage.deleted_at(kind, id).await {
+            Ok(Some(_)) => gone(&format!("{} has been deleted", id)),
+            _ => not_found(msg),
+        }
+    }
+
+    /// Flag platforms the background scrub found corrupt.
+    async fn mark_corrupt(
+        &self,
+        kind: &str,
+        id: &str,
+        version: &str,
+        platforms: &mut [PlatformBuild],
+    ) {
+        let Ok(corrupt) = self.storage.corrupt_platforms(kind, id, version).await else {
+            return;
+        };
+        for build in platforms {
+            if corrupt.contains(&build.platform) {
+                build.corrupt = Some(true);
+            }
+        }
+    }
+
+    /// The `yanked` field of version info; left out if `yanked.json` can't be read.
+    async fn yanked_flag(&self, kind: &str, id: &str, version: &str) -> Option<bool> {
+        self.storage.is_yanked(kind, id, version).await.ok()
+    }
+
+    /// The `changelog` field of plugin version info, kept in the version's `meta.json`.
+    async fn plugin_changelog(&self, id: &str, version: &str) -> Option<String> {
+        let meta = self.storage.get_plugin_meta(id, version).await.ok()??;
+        meta.changelog
+    }
+
+    /// The `dependencies` field of plugin version info; left out when none were declared.
+    async fn plugin_dependencies(&self, id: &str, version: &str) -> Option<Vec<Dependency>> {
+        let dependencies = self
+            .storage
+            .get_plugin_dependencies(id, version)
+            .await
+            .ok()?;
+        if dependencies.is_empty() {
+            return None;
+        }
+        Some(
+            dependencies
+                .into_iter()
+                .map(|d| Dependency {
+                    id: d.id,
+                    version_req: d.version_req,
+                    optional: Some(d.optional),
+                })
+                .collect(),
+        )
+    }
+
+    async fn plugin_assets(&self, id: &str, version: &str) -> Option<Vec<AssetMeta>> {
+        let assets = self
+            .storage
+            .list_assets("plugins", id, version)
+            .await
+            .ok()?;
+        if assets.is_empty() {
+            return None;
+        }
+        Some(
+            assets
+                .into_iter()
+                .map(|a| AssetMeta {
+                    name: a.name,
+                    size_bytes: a.size_bytes,
+                    checksum: a.checksum,
+                })
+                .collect(),
+        )
+    }
+
+    /// Recorded checksum of one platform build. `kind` is `packages` or `plugins`.
+    async fn build_checksum(
+        &self,
+        kind: &str,
+        id: &str,
+        version: &str,
+        platform: &str,
+    ) -> Option<String> {
+        let storage = &self.storage;
+        let platforms = match kind {
+            "packages" => storage.get_package_info(id, version).await.ok()?.platforms,
+            _ => storage.get_plugin_info(id, version).await.ok()?.platforms,
+        };
+        let platform = normalize_platform(platform);
+        platforms
+            .into_iter()
+            .find(|b| b.platform == platform)
+            .map(|build| build.checksum)
+    }
+
+    /// Refuse to stream an artifact whose bytes no longer match its recorded checksum.
+    async fn verify_artifact(&self, path: &Path, checksum: &str) -> Result<(), ApiError> {
+        match self.storage.artifact_matches_checksum(path, checksum).await {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                tracing::error!(path = %path.display(), "Artifact failed checksum verification");
+                Err(ApiError {
+                    status: 500,
+                    code: "checksum_mismatch".to_string(),
+                    message: "Stored artifact does not match its checksum".to_string(),
+                })
+            }
+            Err(e) => Err(internal_error(e)),
+        }
+    }
+
+    /// Scheme the client used: the forwarded proto behind a trusted proxy, else `http`.
+    fn request_scheme(&self, x_forwarded_proto: Option<&str>) -> String {
+        x_forwarded_proto
+            .filter(|_| self.config.trust_proxy)
+            .and_then(first_forwarded)
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_else(|| "http".to_string())
+    }
+
+    /// Base URL for absolute links in responses.
+    ///
+    /// `public_url` wins when set. Otherwise, behind a trusted proxy, the URL is rebuilt
+    /// from the forwarded proto and host so links point at the proxy rather than at the
+    /// plain-HTTP listener. Without either, links stay relative.
+    fn base_url(
+        &self,
+        host: Option<&str>,
+        x_forwarded_proto: Option<&str>,
+        x_forwarded_host: Option<&str>,
+    ) -> String {
+        if !self.config.public_url.is_empty() || !self.config.trust_proxy {
+            return self.config.public_url.clone();
+        }
+        match x_forwarded_host.and_then(first_forwarded).or(host) {
+            Some(host) => format!("{}://{}", self.request_scheme(x_forwarded_proto), host),
+            None => String::new(),
+        }
+    }
+
+    /// Reject a tarball download whose `Accept` header excludes `application/gzip`.
+    fn check_accept(&self, accept: Option<&str>) -> Result<(), ApiError> {
+        if self.config.strict_accept && !accepts(accept, "application/gzip") {
+            return Err(not_acceptable(
+                "Downloads are only served as application/gzip",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Record an audit entry. Failures are logged rather than failing the request.
+    async fn audit(&self, entry: AuditEntry) {
+        if let Err(e) = self.storage.append_audit(&entry).await {
+            warn!("Failed to write audit entry for {}: {}", entry.id, e);
+        }
+    }
 }
 
 fn internal_error(e: impl std::fmt::Display) -> ApiError {
@@ -42,6 +283,39 @@ fn not_found(msg: &str) -> ApiError {
     }
 }
 
+fn payload_too_large(msg: &str) -> ApiError {
+    ApiError {
+        status: 413,
+        code: "payload_too_large".to_string(),
+        message: msg.to_string(),
+    }
+}
+
+fn gone(msg: &str) -> ApiError {
+    ApiError {
+        status: 410,
+        code: "gone".to_string(),
+        message: msg.to_string(),
+    }
+}
+
+/// 404 for a version that exists but was registered without any artifacts.
+fn no_artifacts(id: &str, version: &str) -> ApiError {
+    ApiError {
+        status: 404,
+        code: "no_artifacts".to_string(),
+        message: format!("Plugin {} {} has no published artifacts", id, version),
+    }
+}
+
+fn bad_gateway(msg: &str) -> ApiError {
+    ApiError {
+        status: 502,
+        code: "bad_gateway".to_string(),
+        message: msg.to_string(),
+    }
+}
+
 fn bad_request(msg: &str) -> ApiError {
     ApiError {
         status: 400,
@@ -50,376 +324,5843 @@ fn bad_request(msg: &str) -> ApiError {
     }
 }
 
-/// Serve a file as a streaming gzip response.
-async fn serve_file_response(path: PathBuf) -> Result<axum::response::Response, ApiError> {
-    let file = File::open(&path).await.map_err(internal_error)?;
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+/// A well-formed request that can't be honored, e.g. dependencies no published version
+/// satisfies. `code` names the rule that failed; 400 stays for malformed syntax.
+fn unprocessable(code: &str, msg: &str) -> ApiError {
+    ApiError {
+        status: 422,
+        code: code.to_string(),
+        message: msg.to_string(),
+    }
+}
 
-    let filename = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("download.tar.gz");
+fn unauthorized(msg: &str) -> ApiError {
+    ApiError {
+        status: 401,
+        code: "unauthorized".to_string(),
+        message: msg.to_string(),
+    }
+}
 
-    axum::response::Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "application/gzip")
-        .header(
-            header::CONTENT_DISPOSITION,
-            format!("attachment; filename=\"{}\"", filename),
-        )
-        .body(body)
-        .map_err(internal_error)
+fn not_acceptable(msg: &str) -> ApiError {
+    ApiError {
+        status: 406,
+        code: "not_acceptable".to_string(),
+        message: msg.to_string(),
+    }
 }
 
-#[async_trait]
-impl IndexServiceHandler for AppState {
-    async fn get_index(&self) -> Result<RegistryIndex, ApiError> {
-        let index = self.storage.load_index().await.map_err(internal_error)?;
-        json_convert(&index)
+fn forbidden(msg: &str) -> ApiError {
+    ApiError {
+        status: 403,
+        code: "forbidden".to_string(),
+        message: msg.to_string(),
     }
 }
 
-#[async_trait]
-impl SearchServiceHandler for AppState {
-    async fn search(&self, query: SearchServiceSearchQuery) -> Result<SearchResults, ApiError> {
-        let index = self.storage.load_index().await.map_err(internal_error)?;
-        let query_lower = query.q.to_lowercase();
-        let kind = query.kind.as_deref().unwrap_or("all");
-
-        let packages = if kind == "all" || kind == "package" {
-            json_convert(
-                &index
-                    .packages
-                    .iter()
-                    .filter(|p| {
-                        p.id.to_lowercase().contains(&query_lower)
-                            || p.name.to_lowercase().contains(&query_lower)
-                            || p.description.to_lowercase().contains(&query_lower)
-                            || p.tags
-                                .iter()
-                                .any(|t| t.to_lowercase().contains(&query_lower))
-                    })
-                    .collect::<Vec<_>>(),
-            )?
-        } else {
-            vec![]
+/// Map a storage publish failure, surfacing the platform cap and overwrites as a 409.
+fn publish_error(e: anyhow::Error) -> ApiError {
+    if let Some(too_many) = e.downcast_ref::<TooManyPlatforms>() {
+        return ApiError {
+            status: 409,
+            code: "too_many_platforms".to_string(),
+            message: too_many.to_string(),
         };
+    }
+    if let Some(invalid) = e.downcast_ref::<InvalidId>() {
+        return bad_request(&invalid.to_string());
+    }
+    if let Some(invalid) = e.downcast_ref::<InvalidVersion>() {
+        return bad_request(&invalid.to_string());
+    }
+    if let Some(exists) = e.downcast_ref::<ArtifactExists>() {
+        return conflict(&format!("{}; pass overwrite=true to replace it", exists));
+    }
+    if e.downcast_ref::<WebUiExists>().is_some() {
+        return conflict("Version already has a web UI; pass allow_overwrite=true to replace it");
+    }
+    if let Some(unsatisfied) = e.downcast_ref::<UnsatisfiedDependencies>() {
+        return unprocessable("unsatisfied_dependencies", &unsatisfied.to_string());
+    }
+    if let Some(invalid) = e.downcast_ref::<InvalidVersionReq>() {
+        return bad_request(&invalid.to_string());
+    }
+    if let Some(invalid) = e.downcast_ref::<InvalidAssetName>() {
+        return bad_request(&invalid.to_string());
+    }
+    if let Some(exists) = e.downcast_ref::<AssetExists>() {
+        return conflict(&exists.to_string());
+    }
+    internal_error(e)
+}
 
-        let plugins = if kind == "all" || kind == "plugin" {
-            json_convert(
-                &index
-                    .plugins
-                    .iter()
-                    .filter(|p| {
-                        p.id.to_lowercase().contains(&query_lower)
-                            || p.name.to_lowercase().contains(&query_lower)
-                            || p.description.to_lowercase().contains(&query_lower)
-                            || p.tags
-                                .iter()
-                                .any(|t| t.to_lowercase().contains(&query_lower))
-                    })
-                    .collect::<Vec<_>>(),
-            )?
-        } else {
-            vec![]
+/// Read a batch upload, where each form field is one artifact named by its platform.
+///
+/// A body without a single part, a field that isn't named after a platform and a
+/// field that fails to read each get their own 400.
+async fn read_platform_fields(
+    body: &mut axum::extract::Multipart,
+) -> Result<Vec<(String, Vec<u8>)>, ApiError> {
+    let mut files = Vec::new();
+    loop {
+        let field = match body.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            // multer reports an empty or boundary-less body as an incomplete stream
+            Err(_) if files.is_empty() => {
+                return Err(bad_request(
+                    "No multipart body; upload one form field per platform",
+                ))
+            }
+            Err(e) => {
+                return Err(bad_request(&format!(
+                    "Failed to read multipart body: {}",
+                    e
+                )))
+            }
+        };
+        let platform = match field.name() {
+            None | Some("") => {
+                return Err(bad_request(
+                    "Multipart field has no name; name each file field after its platform",
+                ))
+            }
+            Some("file") => {
+                return Err(bad_request(
+                    "No file field named after a platform; \
+                     name each one like linux-x86_64 instead of \"file\"",
+                ))
+            }
+            Some(name) => name.to_string(),
         };
+        let data = field
+            .bytes()
+            .await
+            .map_err(|e| bad_request(&format!("Failed to read field {}: {}", platform, e)))?;
+        files.push((platform, data.to_vec()));
+    }
+    if files.is_empty() {
+        return Err(bad_request("Multipart body has no file fields"));
+    }
+    Ok(files)
+}
 
-        Ok(SearchResults { packages, plugins })
+/// Map a dependency resolution failure; unsatisfiable requirements and cycles are a 409.
+fn resolve_error(e: anyhow::Error) -> ApiError {
+    if let Some(unsatisfiable) = e.downcast_ref::<UnsatisfiableDependency>() {
+        return ApiError {
+            status: 409,
+            code: "unsatisfiable_dependency".to_string(),
+            message: unsatisfiable.to_string(),
+        };
+    }
+    if let Some(cycle) = e.downcast_ref::<DependencyCycle>() {
+        return ApiError {
+            status: 409,
+            code: "dependency_cycle".to_string(),
+            message: cycle.to_string(),
+        };
     }
+    internal_error(e)
 }
 
-#[async_trait]
-impl PackageServiceHandler for AppState {
-    async fn get_latest(&self, id: String) -> Result<PackageInfo, ApiError> {
-        let info = self
-            .storage
-            .get_package_latest(&id)
-            .await
-            .map_err(|_| not_found("Package not found"))?;
-        json_convert(&info)
+/// Parse the `dependencies` publish parameter, a JSON array of [`Dependency`], and
+/// check every version requirement before anything is written.
+fn parse_dependencies(
+    json: Option<&str>,
+) -> Result<Option<Vec<plugin_registry_core::Dependency>>, ApiError> {
+    let Some(json) = json else {
+        return Ok(None);
+    };
+    let dependencies: Vec<Dependency> = serde_json::from_str(json)
+        .map_err(|e| bad_request(&format!("Invalid dependencies: {}", e)))?;
+    let dependencies: Vec<_> = dependencies
+        .into_iter()
+        .map(|d| plugin_registry_core::Dependency {
+            id: d.id,
+            version_req: d.version_req,
+            optional: d.optional.unwrap_or(false),
+        })
+        .collect();
+    for dependency in &dependencies {
+        dependency
+            .validate()
+            .map_err(|e| bad_request(&e.to_string()))?;
     }
+    Ok(Some(dependencies))
+}
 
-    async fn get_version(&self, id: String, version: String) -> Result<PackageInfo, ApiError> {
-        let version = version.trim_end_matches(".json");
-        let info = self
-            .storage
-            .get_package_info(&id, version)
-            .await
-            .map_err(|_| not_found("Package version not found"))?;
-        json_convert(&info)
+fn conflict(msg: &str) -> ApiError {
+    ApiError {
+        status: 409,
+        code: "conflict".to_string(),
+        message: msg.to_string(),
     }
+}
 
-    async fn download(
-        &self,
-        id: String,
-        version: String,
-        platform: String,
-    ) -> Result<axum::response::Response, ApiError> {
-        let platform = platform.trim_end_matches(".tar.gz");
-        let path = self.storage.package_artifact_path(&id, &version, platform);
+/// Extract the caller's token from `Authorization: Bearer <token>` or `X-Api-Key`.
+fn request_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+}
 
-        if !path.exists() {
-            return Err(not_found("Package artifact not found"));
-        }
+/// First entry of a forwarded header; proxies chained together append theirs after it.
+fn first_forwarded(value: &str) -> Option<&str> {
+    value
+        .split(',')
+        .next()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
 
-        // Increment download counter (fire and forget)
-        let storage_root = self.storage.root().to_path_buf();
-        let id_clone = id.clone();
-        tokio::spawn(async move {
-            let storage = RegistryStorage::new(storage_root);
-            let _ = storage.increment_downloads("packages", &id_clone).await;
-        });
+/// Last entry of a forwarded header: the one the trusted proxy appended itself.
+/// Everything before it came from the client and can be forged.
+fn last_forwarded(value: &str) -> Option<&str> {
+    value
+        .rsplit(',')
+        .next()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
 
-        serve_file_response(path).await
+/// Reject publishes that reached the server over plain HTTP when `REGISTRY_FORCE_HTTPS` is set.
+async fn require_https(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<axum::response::Response, ApiError> {
+    let proto = request
+        .headers()
+        .get("x-forwarded-proto")
+        .and_then(|v| v.to_str().ok());
+    if state.config.force_https && state.request_scheme(proto) != "https" {
+        return Err(forbidden("Publishing requires HTTPS"));
     }
+    Ok(next.run(request).await)
 }
 
-#[async_trait]
-impl PackagePublishServiceHandler for AppState {
-    async fn publish(
-        &self,
-        id: String,
-        version: String,
-        platform: String,
-        query: PackagePublishServicePublishQuery,
-        body: Vec<u8>,
-    ) -> Result<PublishResponse, ApiError> {
-        if body.is_empty() {
-            return Err(bad_request("No file uploaded"));
-        }
-
-        self.storage
-            .publish_package(
-                &id,
-                &query.name,
-                query.description.as_deref().unwrap_or(""),
-                &version,
-                &platform,
-                &body,
-                query.author.as_deref().unwrap_or("unknown"),
-                vec![],
-            )
-            .await
-            .map_err(internal_error)?;
+/// The client address: the last `X-Forwarded-For` entry behind a trusted proxy,
+/// otherwise the peer address.
+fn client_ip(
+    state: &AppState,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: &HeaderMap,
+) -> Option<IpAddr> {
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| state.config.trust_proxy)
+        .and_then(last_forwarded)
+        .and_then(|ip| ip.parse::<IpAddr>().ok());
+    forwarded.or(peer.map(|info| info.0.ip()))
+}
 
-        Ok(PublishResponse {
-            status: "published".to_string(),
-            id,
-            version,
-            platform,
-        })
+/// Reject publish and admin requests from addresses outside `REGISTRY_PUBLISH_*_CIDRS`.
+///
+/// Requests whose client cannot be determined are rejected.
+async fn require_allowed_ip(
+    State(state): State<Arc<AppState>>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Result<axum::response::Response, ApiError> {
+    let rules = &state.config.publish_ips;
+    if rules.is_empty() {
+        return Ok(next.run(request).await);
+    }
+    match client_ip(&state, peer, request.headers()) {
+        Some(ip) if rules.permits(ip) => Ok(next.run(request).await),
+        _ => Err(forbidden("Client address not allowed")),
     }
 }
 
-#[async_trait]
-impl PluginServiceHandler for AppState {
-    async fn get_latest(&self, id: String) -> Result<PluginInfo, ApiError> {
-        let info = self
-            .storage
-            .get_plugin_latest(&id)
-            .await
-            .map_err(|_| not_found("Plugin not found"))?;
-        json_convert(&info)
+/// Answer 429 to clients sending more than `REGISTRY_TELEMETRY_RATE_LIMIT` reports a minute.
+async fn limit_telemetry(
+    State(state): State<Arc<AppState>>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Result<axum::response::Response, ApiError> {
+    let client = client_ip(&state, peer, request.headers());
+    if request.method() == axum::http::Method::POST && !state.telemetry_limiter.allow(client) {
+        return Err(ApiError {
+            status: 429,
+            code: "rate_limited".to_string(),
+            message: "Too many telemetry reports; try again later".to_string(),
+        });
     }
+    Ok(next.run(request).await)
+}
 
-    async fn get_version(&self, id: String, version: String) -> Result<PluginInfo, ApiError> {
-        let version = version.trim_end_matches(".json");
-        let info = self
-            .storage
-            .get_plugin_info(&id, version)
-            .await
-            .map_err(|_| not_found("Plugin version not found"))?;
-        json_convert(&info)
+/// Map a failed download from a publish source: 400 for the URL itself, 413 past the
+/// size cap, 502 when the source can't deliver.
+fn fetch_error(e: FetchError) -> ApiError {
+    match e {
+        FetchError::InvalidUrl(_) => bad_request(&e.to_string()),
+        FetchError::TooLarge(_) => payload_too_large(&e.to_string()),
+        FetchError::Unreachable(_) | FetchError::Status(_) => bad_gateway(&e.to_string()),
     }
+}
 
-    async fn download(
-        &self,
-        id: String,
-        version: String,
-        platform: String,
-    ) -> Result<axum::response::Response, ApiError> {
-        let platform = platform.trim_end_matches(".tar.gz");
-        let path = self.storage.plugin_artifact_path(&id, &version, platform);
+fn auth_error(e: AuthError) -> ApiError {
+    match e {
+        AuthError::Unauthenticated(msg) => unauthorized(&msg),
+        AuthError::Forbidden(msg) => forbidden(&msg),
+    }
+}
 
-        if !path.exists() {
-            return Err(not_found("Plugin artifact not found"));
-        }
+/// The `:id` path parameter of the matched route, or empty for routes without one.
+fn path_id(params: &Option<RawPathParams>) -> &str {
+    params
+        .iter()
+        .flat_map(|params| params.iter())
+        .find(|(name, _)| *name == "id")
+        .map_or("", |(_, value)| value)
+}
 
-        // Increment download counter
-        let storage_root = self.storage.root().to_path_buf();
-        let id_clone = id.clone();
-        tokio::spawn(async move {
-            let storage = RegistryStorage::new(storage_root);
-            let _ = storage.increment_downloads("plugins", &id_clone).await;
-        });
+/// Authorize publish and delete routes through the [`Authorizer`], and require admin
+/// rights for imports that set `published_at`.
+async fn require_api_key(
+    State(state): State<Arc<AppState>>,
+    params: Option<RawPathParams>,
+    mut request: Request,
+    next: Next,
+) -> Result<axum::response::Response, ApiError> {
+    let id = path_id(&params);
+    let token = request_token(request.headers());
+    let action = if request.method() == axum::http::Method::DELETE {
+        Action::Delete
+    } else {
+        Action::Publish
+    };
+    let identity = state
+        .authorizer
+        .authorize(action, id, token)
+        .await
+        .map_err(auth_error)?;
 
-        serve_file_response(path).await
+    // Backdated publishes are an import operation reserved for admins
+    let is_import = request
+        .uri()
+        .query()
+        .is_some_and(|q| q.split('&').any(|pair| pair.starts_with("published_at=")));
+    if is_import
+        && state
+            .authorizer
+            .authorize(Action::Admin, id, token)
+            .await
+            .is_err()
+    {
+        return Err(forbidden("published_at requires the admin key"));
     }
+
+    // Handlers record who published or removed what in the audit log
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
 }
 
-#[async_trait]
-impl PluginPublishServiceHandler for AppState {
-    async fn publish(
-        &self,
-        id: String,
-        version: String,
-        platform: String,
-        query: PluginPublishServicePublishQuery,
-        body: Vec<u8>,
-    ) -> Result<PublishResponse, ApiError> {
-        if body.is_empty() {
-            return Err(bad_request("No file uploaded"));
-        }
+/// Earliest `published_at` accepted on import (2000-01-01).
+const MIN_PUBLISHED_AT: u64 = 946_684_800;
 
-        let plugin_type = query.plugin_type.as_deref().unwrap_or("extension");
+/// Reject import timestamps before 2000 or more than a day in the future.
+fn validate_published_at(published_at: u64) -> Result<u64, ApiError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if published_at < MIN_PUBLISHED_AT || published_at > now + 86_400 {
+        return Err(unprocessable(
+            "implausible_published_at",
+            "published_at is not a plausible unix timestamp",
+        ));
+    }
+    Ok(published_at)
+}
 
-        self.storage
-            .publish_plugin(
-                &id,
-                &query.name,
-                query.description.as_deref().unwrap_or(""),
-                plugin_type,
-                &version,
-                &platform,
-                &body,
-                query.author.as_deref().unwrap_or("unknown"),
-                vec![],
-            )
-            .await
-            .map_err(internal_error)?;
+/// Require the admin key on admin routes.
+async fn require_admin_key(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<axum::response::Response, ApiError> {
+    let token = request_token(request.headers());
+    state
+        .authorizer
+        .authorize(Action::Admin, "", token)
+        .await
+        .map_err(auth_error)?;
+    Ok(next.run(request).await)
+}
 
-        Ok(PublishResponse {
-            status: "published".to_string(),
-            id,
-            version,
-            platform,
-        })
+/// Headers browsers may send on cross-origin requests to any route.
+const CORS_ALLOW_HEADERS: &str = "accept, authorization, content-type, x-api-key, x-request-id";
+
+/// Answer CORS preflights with the methods the target route actually supports.
+///
+/// The preflight is routed as a probe request; the router's 405 carries an `Allow` header listing the
+/// route's methods, which becomes `Access-Control-Allow-Methods`. Unknown paths stay 404.
+async fn cors_preflight(mut request: Request, next: Next) -> axum::response::Response {
+    let is_preflight = request.method() == axum::http::Method::OPTIONS
+        && request
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+    if !is_preflight {
+        return next.run(request).await;
     }
+
+    // The CORS layer answers every OPTIONS itself, so probe with a method no route handles
+    *request.method_mut() = axum::http::Method::TRACE;
+    let response = next.run(request).await;
+    let Some(allow) = response.headers().get(header::ALLOW).cloned() else {
+        return response;
+    };
+
+    (
+        StatusCode::NO_CONTENT,
+        [
+            (
+                header::ACCESS_CONTROL_ALLOW_ORIGIN,
+                HeaderValue::from_static("*"),
+            ),
+            (header::ACCESS_CONTROL_ALLOW_METHODS, allow),
+            (
+                header::ACCESS_CONTROL_ALLOW_HEADERS,
+                HeaderValue::from_static(CORS_ALLOW_HEADERS),
+            ),
+            (
+                header::ACCESS_CONTROL_MAX_AGE,
+                HeaderValue::from_static("86400"),
+            ),
+        ],
+    )
+        .into_response()
 }
 
-#[async_trait]
-impl PluginWebUiPublishServiceHandler for AppState {
-    async fn publish(
-        &self,
-        id: String,
-        version: String,
-        body: Vec<u8>,
-    ) -> Result<PublishResponse, ApiError> {
-        if body.is_empty() {
-            return Err(bad_request("Empty body — expected JavaScript content"));
+/// Count every response by status code for `/metrics`.
+async fn count_responses(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    let response = next.run(request).await;
+    state.metrics.record_response(response.status().as_u16());
+    response
+}
+
+/// Add baseline security headers, keeping any a handler already set.
+///
+/// HSTS is only sent on responses to HTTPS requests, as browsers ignore it over plain HTTP.
+async fn security_headers(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    if !state.config.security_headers {
+        return next.run(request).await;
+    }
+    let scheme = state.request_scheme(
+        request
+            .headers()
+            .get("x-forwarded-proto")
+            .and_then(|v| v.to_str().ok()),
+    );
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers
+        .entry(header::X_CONTENT_TYPE_OPTIONS)
+        .or_insert(HeaderValue::from_static("nosniff"));
+    headers
+        .entry(header::REFERRER_POLICY)
+        .or_insert(HeaderValue::from_static("no-referrer"));
+    if let Some(max_age) = state.config.hsts_max_age.filter(|_| scheme == "https") {
+        if let Ok(value) = HeaderValue::from_str(&format!("max-age={}", max_age)) {
+            headers
+                .entry(header::STRICT_TRANSPORT_SECURITY)
+                .or_insert(value);
         }
+    }
+    response
+}
 
-        self.storage
-            .publish_plugin_web_ui(&id, &version, &body)
+/// Check that an uploaded artifact is a complete, readable gzip stream.
+fn validate_gzip(data: &[u8]) -> Result<(), String> {
+    if data.is_empty() {
+        return Err("Empty file".to_string());
+    }
+    std::io::copy(&mut GzDecoder::new(data), &mut std::io::sink())
+        .map(|_| ())
+        .map_err(|e| format!("Invalid gzip: {}", e))
+}
+
+/// Locales to render descriptions in: `?lang=` wins over `Accept-Language`.
+fn preferred_locales(lang: Option<&str>, accept_language: Option<&str>) -> Vec<String> {
+    match lang.map(str::trim).filter(|l| !l.is_empty()) {
+        Some(lang) => vec![lang.to_string()],
+        None => accept_language
+            .map(parse_accept_language)
+            .unwrap_or_default(),
+    }
+}
+
+/// Whether an `Accept-Encoding` header allows gzip (`gzip` or `*`, not with `q=0`).
+fn accepts_gzip(accept_encoding: Option<&str>) -> bool {
+    accept_encoding.is_some_and(|value| {
+        value.split(',').any(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let coding = parts.next().unwrap_or("");
+            let excluded = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q <= 0.0)
+            });
+            !excluded && (coding.eq_ignore_ascii_case("gzip") || coding == "*")
+        })
+    })
+}
+
+/// `304 Not Modified` when the client's `If-None-Match` already covers `etag`.
+fn not_modified(if_none_match: Option<&str>, etag: &str) -> Option<axum::response::Response> {
+    if !if_none_match.is_some_and(|value| etag_matches(value, etag)) {
+        return None;
+    }
+    axum::response::Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, etag)
+        .body(Body::empty())
+        .ok()
+}
+
+/// JSON response with a weak ETag over its content, or `304` if the client already has it.
+fn json_with_etag(
+    value: &impl serde::Serialize,
+    if_none_match: Option<&str>,
+) -> Result<axum::response::Response, ApiError> {
+    let json = serde_json::to_vec(value).map_err(internal_error)?;
+    let etag = weak_content_etag(&json);
+    if let Some(response) = not_modified(if_none_match, &etag) {
+        return Ok(response);
+    }
+    axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ETAG, etag)
+        .body(Body::from(json))
+        .map_err(internal_error)
+}
+
+/// Reader that calls `on_sent` once its first chunk has been taken by the connection.
+///
+/// The body stream only reads again after handing the previous chunk to the client,
+/// so the second read marks a download that actually started. Bodies dropped before
+/// that (disconnects, aborted probes) never call it.
+struct SentNotifier<R, F> {
+    inner: R,
+    chunk_read: bool,
+    on_sent: Option<F>,
+}
+
+impl<R: AsyncRead + Unpin, F: FnOnce() + Unpin> AsyncRead for SentNotifier<R, F> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if self.chunk_read {
+            if let Some(on_sent) = self.on_sent.take() {
+                on_sent();
+            }
+        }
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if matches!(poll, Poll::Ready(Ok(()))) && buf.filled().len() > filled {
+            self.chunk_read = true;
+        }
+        poll
+    }
+}
+
+/// `Content-Disposition` name for an artifact: the `?filename=` override if anything of
+/// it survives sanitizing, else `{id}-{version}-{platform}.tar.gz`.
+fn download_filename(id: &str, version: &str, platform: &str, requested: Option<&str>) -> String {
+    requested
+        .and_then(sanitize_filename)
+        .or_else(|| sanitize_filename(&format!("{}-{}-{}.tar.gz", id, version, platform)))
+        .unwrap_or_else(|| "download.tar.gz".to_string())
+}
+
+/// Bytes of artifact data a response carries, for capacity planning. Also logged
+/// with the request as `served_bytes`.
+const SERVED_BYTES: &str = "x-served-bytes";
+
+/// Inclusive byte range of a file.
+#[derive(Debug, PartialEq, Eq)]
+struct ByteRange {
+    start: u64,
+    end: u64,
+}
+
+/// Parse a `Range` header against a file of `size` bytes.
+///
+/// Takes a single `bytes=start-end`, `bytes=start-` or suffix `bytes=-length` range; an
+/// end past the file is cut to its last byte. `None` if the header is malformed, lists
+/// several ranges or starts past the end of the file.
+fn parse_range(value: &str, size: u64) -> Option<ByteRange> {
+    let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let last = size.checked_sub(1)?;
+    let (start, end) = if start.is_empty() {
+        let length: u64 = end.parse().ok()?;
+        if length == 0 {
+            return None;
+        }
+        (size.saturating_sub(length), last)
+    } else if end.is_empty() {
+        (start.parse().ok()?, last)
+    } else {
+        (start.parse().ok()?, end.parse::<u64>().ok()?.min(last))
+    };
+    (start <= end).then_some(ByteRange { start, end })
+}
+
+/// `416` for a `Range` that can't be served, with the artifact size in `Content-Range`.
+fn range_not_satisfiable(size: u64) -> axum::response::Response {
+    let mut response = ApiError {
+        status: 416,
+        code: "range_not_satisfiable".to_string(),
+        message: "Requested range is malformed or outside the artifact".to_string(),
+    }
+    .into_response();
+    if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", size)) {
+        response.headers_mut().insert(header::CONTENT_RANGE, value);
+    }
+    response
+}
+
+/// Stream an artifact in `chunk_size` reads, calling `on_sent` once the client has
+/// started receiving it. The `Content-Type` follows the stored file's extension.
+///
+/// With a `range`, only that slice is sent as `206 Partial Content`. Resumed downloads
+/// don't call `on_sent`, so one download continued several times is counted once.
+///
+/// Larger chunks mean fewer reads and body frames per download; with a known
+/// `Content-Length` the body is also sent without chunked transfer encoding.
+async fn serve_file_response(
+    path: PathBuf,
+    filename: &str,
+    etag: &str,
+    range: Option<&str>,
+    chunk_size: usize,
+    on_sent: impl FnOnce() + Send + Unpin + 'static,
+) -> Result<axum::response::Response, ApiError> {
+    let content_type = content_type_for(&path.to_string_lossy());
+    let mut file = File::open(&path).await.map_err(internal_error)?;
+    let size = file.metadata().await.map_err(internal_error)?.len();
+    let range = match range.map(|value| parse_range(value, size)) {
+        None => None,
+        Some(Some(range)) => Some(range),
+        Some(None) => return Ok(range_not_satisfiable(size)),
+    };
+    let (start, length) = match &range {
+        Some(range) => (range.start, range.end - range.start + 1),
+        None => (0, size),
+    };
+    if start > 0 {
+        file.seek(std::io::SeekFrom::Start(start))
             .await
             .map_err(internal_error)?;
+    }
+    let stream = ReaderStream::with_capacity(
+        SentNotifier {
+            inner: file.take(length),
+            chunk_read: false,
+            on_sent: (start == 0).then_some(on_sent),
+        },
+        chunk_size,
+    );
+    let body = Body::from_stream(stream);
 
-        Ok(PublishResponse {
-            status: "published".to_string(),
-            id,
-            version,
-            platform: "web".to_string(),
-        })
+    let mut response = axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::ETAG, etag)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, length)
+        .header(SERVED_BYTES, length)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        );
+    if let Some(range) = &range {
+        response = response.status(StatusCode::PARTIAL_CONTENT).header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", range.start, range.end, size),
+        );
     }
+    response.body(body).map_err(internal_error)
 }
 
 #[async_trait]
-impl PluginWebUiServiceHandler for AppState {
-    async fn download(
+impl IndexServiceHandler for AppState {
+    async fn get_index(
         &self,
-        id: String,
-        version: String,
+        query: IndexServiceGetIndexQuery,
+        if_none_match: Option<String>,
+        accept_encoding: Option<String>,
+        accept_language: Option<String>,
     ) -> Result<axum::response::Response, ApiError> {
-        let path = self.storage.get_plugin_web_ui_path(&id, &version);
-        if !path.exists() {
-            return Err(not_found("Plugin web UI not found"));
+        // `updated_since` is inclusive, `since` exclusive
+        let from = match (query.updated_since, query.since) {
+            (Some(_), Some(_)) => {
+                return Err(bad_request("Pass either since or updated_since, not both"))
+            }
+            (updated_since, None) => updated_since,
+            (None, Some(since)) => Some(since.saturating_add(1)),
+        };
+        let mut index = match from {
+            Some(from) => self.storage.load_index_since(from).await,
+            None => self.storage.load_index().await,
+        }
+        .map_err(internal_error)?;
+        let cache_control = format!("public, max-age={}", self.config.index_max_age);
+        if query.since.is_some_and(|since| since >= index.updated_at) {
+            return axum::response::Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .header(header::CACHE_CONTROL, cache_control)
+                .body(Body::empty())
+                .map_err(internal_error);
+        }
+        let locales = preferred_locales(query.lang.as_deref(), accept_language.as_deref());
+        self.storage
+            .localize_index(&mut index, &locales)
+            .await
+            .map_err(internal_error)?;
+        let json = self
+            .storage
+            .index_json(&index)
+            .await
+            .map_err(internal_error)?;
+        let etag = content_etag(&json);
+
+        // Revalidation short-circuits before any compression work
+        if let Some(mut response) = not_modified(if_none_match.as_deref(), &etag) {
+            response.headers_mut().insert(
+                header::CACHE_CONTROL,
+                HeaderValue::from_str(&cache_control).map_err(internal_error)?,
+            );
+            return Ok(response);
         }
 
-        let file = File::open(&path).await.map_err(internal_error)?;
-        let stream = ReaderStream::new(file);
-        let body = Body::from_stream(stream);
+        let response = axum::response::Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ETAG, &etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .header(header::VARY, "accept-encoding, accept-language");
+        if !accepts_gzip(accept_encoding.as_deref()) {
+            return response.body(Body::from(json)).map_err(internal_error);
+        }
 
+        let gzip = self.index_gzip(&etag, &json).map_err(internal_error)?;
+        response
+            .header(header::CONTENT_ENCODING, "gzip")
+            .body(Body::from(gzip))
+            .map_err(internal_error)
+    }
+
+    async fn get_checksum(&self) -> Result<axum::response::Response, ApiError> {
+        // Same bytes as an unlocalized, unfiltered `index.json`
+        let index = self.storage.load_index().await.map_err(internal_error)?;
+        let json = self
+            .storage
+            .index_json(&index)
+            .await
+            .map_err(internal_error)?;
         axum::response::Response::builder()
             .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "application/javascript")
+            .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
             .header(
                 header::CACHE_CONTROL,
-                "public, max-age=31536000, immutable",
+                format!("public, max-age={}", self.config.index_max_age),
             )
-            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-            .body(body)
+            .body(Body::from(sha256_hex(&json)))
             .map_err(internal_error)
     }
+
+    async fn compare(&self, body: IndexCompareRequest) -> Result<IndexComparison, ApiError> {
+        let kind = match body.kind.as_deref().unwrap_or("plugin") {
+            "package" => "packages",
+            "plugin" => "plugins",
+            _ => return Err(bad_request("kind must be 'package' or 'plugin'")),
+        };
+        let local: Vec<LocalEntry> = body
+            .entries
+            .into_iter()
+            .map(|e| LocalEntry {
+                id: e.id,
+                latest_version: e.latest_version,
+            })
+            .collect();
+
+        let comparison = self
+            .storage
+            .compare_index(kind, &local)
+            .await
+            .map_err(internal_error)?;
+        json_convert(&comparison)
+    }
 }
 
-/// Convert core types to generated models via serde Value
-fn json_convert<T: serde::Serialize, U: serde::de::DeserializeOwned>(
-    val: &T,
-) -> Result<U, ApiError> {
-    serde_json::to_value(val)
-        .and_then(|v| serde_json::from_value(v))
-        .map_err(internal_error)
+#[async_trait]
+impl CapabilitiesServiceHandler for AppState {
+    async fn get(&self) -> Result<Capabilities, ApiError> {
+        Ok(Capabilities {
+            default_page_size: self.config.pages.default_page_size,
+            max_page_size: self.config.pages.max_page_size,
+        })
+    }
 }
 
-async fn health() -> Json<serde_json::Value> {
-    Json(serde_json::json!({
-        "status": "ok",
-        "service": "adi-plugin-registry",
-        "version": env!("CARGO_PKG_VERSION")
-    }))
+#[async_trait]
+impl StatsServiceHandler for AppState {
+    async fn get(&self) -> Result<RegistryStats, ApiError> {
+        let stats = self.storage.compute_stats().await.map_err(internal_error)?;
+        json_convert(&stats)
+    }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
+/// Longest description returned in search results; full text stays on the version info.
+const SEARCH_DESCRIPTION_CHARS: usize = 500;
 
-    let data_dir = if args.len() > 1 {
-        PathBuf::from(&args[1])
-    } else {
-        std::env::var("REGISTRY_DATA_DIR")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from("/data"))
+#[async_trait]
+impl SearchServiceHandler for AppState {
+    async fn search(
+        &self,
+        query: SearchServiceSearchQuery,
+        accept_language: Option<String>,
+        if_none_match: Option<String>,
+    ) -> Result<axum::response::Response, ApiError> {
+        let grouped = match query.group_by.as_deref() {
+            None => false,
+            Some("package") => true,
+            Some(_) => return Err(bad_request("group_by must be 'package'")),
+        };
+        let terms = search_terms(&query.q, self.config.max_search_terms);
+        let wanted_tags = tag_filter(query.tags.as_deref().unwrap_or_default());
+        let kind = query
+            .kind
+            .as_deref()
+            .unwrap_or(&self.config.default_search_kind);
+        let sort = SearchSort::parse(query.sort.as_deref());
+        let locales = preferred_locales(query.lang.as_deref(), accept_language.as_deref());
+        let page = self.config.pages.resolve(query.limit);
+        let offset = query.offset.unwrap_or(0);
+
+        // Derived from the storage revision and the normalized query, so revalidating
+        // doesn't run the search; the revision also moves with download counts
+        let revision = self.storage.revision().await;
+        let key = format!(
+            "{}|{:?}|{:?}|{}|{:?}|{}|{}|{}|{:?}",
+            revision, terms, wanted_tags, kind, sort, page.limit, offset, grouped, locales
+        );
+        let etag = weak_content_etag(key.as_bytes());
+        let cache_control = format!("public, max-age={}", self.config.index_max_age);
+        if let Some(mut response) = not_modified(if_none_match.as_deref(), &etag) {
+            response.headers_mut().insert(
+                header::CACHE_CONTROL,
+                HeaderValue::from_str(&cache_control).map_err(internal_error)?,
+            );
+            return Ok(response);
+        }
+
+        let mut index = self.storage.load_index().await.map_err(internal_error)?;
+        self.storage
+            .drop_fully_yanked(&mut index)
+            .await
+            .map_err(internal_error)?;
+        let descriptions = self
+            .storage
+            .plugin_descriptions()
+            .await
+            .map_err(internal_error)?;
+        let matches = |id: &str, name: &str, description: &str, tags: &[String]| {
+            let fields = [id, name, description]
+                .into_iter()
+                .chain(tags.iter().map(String::as_str));
+            matches_terms(fields, &terms) && has_all_tags(tags, &wanted_tags)
+        };
+        // Plugins match in every locale, whichever one the response is rendered in
+        index.plugins.retain(|p| {
+            let localized = descriptions.get(&p.id).into_iter().flat_map(|d| d.values());
+            let description = std::iter::once(&p.description)
+                .chain(localized)
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join("\n");
+            matches(&p.id, &p.name, &description, &p.tags)
+        });
+        self.storage
+            .localize_index(&mut index, &locales)
+            .await
+            .map_err(internal_error)?;
+
+        let mut packages = index.packages;
+        packages.retain(|p| matches(&p.id, &p.name, &p.description, &p.tags));
+        if kind != "all" && kind != "package" {
+            packages.clear();
+        }
+        let mut plugins = index.plugins;
+        if kind != "all" && kind != "plugin" {
+            plugins.clear();
+        }
+        sort.apply(&mut packages, &mut plugins);
+
+        // Each kind is paged on its own; `total` counts all matches of that kind
+        let total = SearchTotal {
+            packages: packages.len() as u32,
+            plugins: plugins.len() as u32,
+        };
+        let window = |len: usize| {
+            let start = (offset as usize).min(len);
+            start..(start + page.limit as usize).min(len)
+        };
+        let packages: Vec<_> = packages
+            .drain(window(packages.len()))
+            .map(|mut p| {
+                p.description = truncate_chars(&p.description, SEARCH_DESCRIPTION_CHARS);
+                p
+            })
+            .collect();
+        let plugins: Vec<_> = plugins
+            .drain(window(plugins.len()))
+            .map(|mut p| {
+                p.description = truncate_chars(&p.description, SEARCH_DESCRIPTION_CHARS);
+                p
+            })
+            .collect();
+        let packages = json_convert(&packages)?;
+
+        let json = if grouped {
+            let grouped = group_by_package(plugins);
+            serde_json::to_vec(&GroupedSearchResults {
+                packages,
+                groups: json_convert(&grouped.groups)?,
+                orphans: json_convert(&grouped.orphans)?,
+                total,
+                limit: page.limit,
+                offset,
+                clamped: page.clamped,
+            })
+        } else {
+            serde_json::to_vec(&SearchResults {
+                packages,
+                plugins: json_convert(&plugins)?,
+                total,
+                limit: page.limit,
+                offset,
+                clamped: page.clamped,
+            })
+        }
+        .map_err(internal_error)?;
+
+        axum::response::Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .header(header::VARY, "accept-language")
+            .body(Body::from(json))
+            .map_err(internal_error)
+    }
+}
+
+/// Narrow `platforms` to the requested build, if any; 404 when it isn't published.
+fn filter_platform(
+    platforms: &mut Vec<PlatformBuild>,
+    platform: Option<&str>,
+) -> Result<(), ApiError> {
+    let Some(platform) = platform else {
+        return Ok(());
     };
+    let platform = normalize_platform(platform);
+    platforms.retain(|p| p.platform == platform);
+    if platforms.is_empty() {
+        return Err(not_found(&format!(
+            "Platform {} is not published for this version",
+            platform
+        )));
+    }
+    Ok(())
+}
 
-    let port: u16 = std::env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(8080);
+/// Most ids one `/batch` request may ask for, so a single call stays a bounded amount of work.
+const MAX_BATCH_IDS: usize = 200;
 
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+fn check_batch_size(ids: &[String]) -> Result<(), ApiError> {
+    if ids.len() > MAX_BATCH_IDS {
+        return Err(bad_request(&format!(
+            "At most {} ids may be requested at once",
+            MAX_BATCH_IDS
+        )));
+    }
+    Ok(())
+}
 
-    tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(filter)
-        .init();
+#[async_trait]
+impl PackageServiceHandler for AppState {
+    async fn get_latest(
+        &self,
+        id: String,
+        query: PackageServiceGetLatestQuery,
+    ) -> Result<PackageInfo, ApiError> {
+        let info = self
+            .or_gone(
+                self.storage.get_package_latest(&id).await,
+                "packages",
+                &id,
+                "Package not found",
+            )
+            .await?;
+        let mut info: PackageInfo = json_convert(&info)?;
+        filter_platform(&mut info.platforms, query.platform.as_deref())?;
+        self.mark_corrupt("packages", &id, &info.version, &mut info.platforms)
+            .await;
+        info.yanked = self.yanked_flag("packages", &id, &info.version).await;
+        Ok(info)
+    }
 
-    info!("Starting Plugin Registry HTTP server");
-    info!("Data directory: {}", data_dir.display());
+    async fn get_latest_batch(
+        &self,
+        ids: Vec<String>,
+    ) -> Result<HashMap<String, PackageInfo>, ApiError> {
+        check_batch_size(&ids)?;
+        let infos = self
+            .storage
+            .get_packages_latest(&ids)
+            .await
+            .map_err(internal_error)?;
+        let mut batch = HashMap::with_capacity(infos.len());
+        for (id, info) in infos {
+            let mut info: PackageInfo = json_convert(&info)?;
+            self.mark_corrupt("packages", &id, &info.version, &mut info.platforms)
+                .await;
+            info.yanked = self.yanked_flag("packages", &id, &info.version).await;
+            batch.insert(id, info);
+        }
+        Ok(batch)
+    }
 
-    let storage = RegistryStorage::new(data_dir);
-    storage.init().await?;
+    async fn list_versions(&self, id: String) -> Result<Vec<String>, ApiError> {
+        let versions = self
+            .storage
+            .list_package_versions(&id)
+            .await
+            .map_err(internal_error)?;
+        if versions.is_empty() {
+            return Err(self.missing("packages", &id, "Package not found").await);
+        }
+        Ok(versions)
+    }
 
-    let state = Arc::new(AppState { storage });
+    async fn get_version(
+        &self,
+        id: String,
+        version: String,
+        query: PackageServiceGetVersionQuery,
+        if_none_match: Option<String>,
+    ) -> Result<axum::response::Response, ApiError> {
+        let version = strip_extension(&version, ".json")
+            .ok_or_else(|| not_found("Package version not found"))?;
+        let info = self
+            .or_gone(
+                self.storage.get_package_info(&id, version).await,
+                "packages",
+                &id,
+                "Package version not found",
+            )
+            .await?;
+        let mut info: PackageInfo = json_convert(&info)?;
+        filter_platform(&mut info.platforms, query.platform.as_deref())?;
+        self.mark_corrupt("packages", &id, version, &mut info.platforms)
+            .await;
+        info.yanked = self.yanked_flag("packages", &id, version).await;
+        json_with_etag(&info, if_none_match.as_deref())
+    }
 
-    let app = Router::new()
-        .route("/", get(health))
-        .route("/health", get(health))
-        .merge(create_router::<AppState>())
-        .layer(axum::extract::DefaultBodyLimit::max(100 * 1024 * 1024))
-        .layer(version_header_layer(
-            env!("CARGO_PKG_NAME"),
-            env!("CARGO_PKG_VERSION"),
-        ))
-        .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
+    async fn download(
+        &self,
+        id: String,
+        version: String,
+        platform: String,
+        query: PackageServiceDownloadQuery,
+        accept: Option<String>,
+        if_none_match: Option<String>,
+        range: Option<String>,
+    ) -> Result<axum::response::Response, ApiError> {
+        self.check_accept(accept.as_deref())?;
+        let platform = strip_extension(&platform, ".tar.gz")
+            .ok_or_else(|| not_found("Package artifact not found"))?;
+        let path = self.storage.package_artifact_path(&id, &version, platform);
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    info!("Listening on http://{}", addr);
+        if !path.exists() {
+            return Err(self
+                .missing("packages", &id, "Package artifact not found")
+                .await);
+        }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+        let metadata = tokio::fs::metadata(&path).await.map_err(internal_error)?;
+        let strong = query.verify.unwrap_or(false);
+        let checksum = if strong || self.config.verify_on_download {
+            let checksum = self
+                .build_checksum("packages", &id, &version, platform)
+                .await
+                .ok_or_else(|| not_found("Package artifact not found"))?;
+            Some(checksum)
+        } else {
+            None
+        };
+        let etag = match &checksum {
+            Some(checksum) if strong => strong_etag(checksum),
+            _ => weak_etag(&metadata),
+        };
+        if let Some(response) = not_modified(if_none_match.as_deref(), &etag) {
+            return Ok(response);
+        }
+        if let (true, Some(checksum)) = (self.config.verify_on_download, &checksum) {
+            self.verify_artifact(&path, checksum).await?;
+        }
 
-    Ok(())
+        let filename = download_filename(&id, &version, platform, query.filename.as_deref());
+        // Counted once streaming starts, so aborted requests don't inflate the total
+        let storage = self.storage.clone();
+        let metrics = self.metrics.clone();
+        let chunk_size = self.config.download_chunk_size;
+        let range = range.as_deref();
+        serve_file_response(path, &filename, &etag, range, chunk_size, move || {
+            metrics.record_download();
+            if storage.options().count_downloads {
+                tokio::spawn(async move {
+                    let _ = storage.increment_downloads("packages", &id).await;
+                });
+            }
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl PackagePublishServiceHandler for AppState {
+    async fn publish(
+        &self,
+        id: String,
+        version: String,
+        platform: String,
+        query: PackagePublishServicePublishQuery,
+        body: Vec<u8>,
+        identity: Identity,
+    ) -> Result<PublishResponse, ApiError> {
+        if body.is_empty() {
+            return Err(bad_request("No file uploaded"));
+        }
+
+        if query.dependencies.is_some() {
+            return Err(bad_request("Dependencies can only be declared for plugins"));
+        }
+
+        let platform = normalize_platform(&platform);
+
+        let author = query.author.as_deref().unwrap_or("unknown");
+
+        let published_at = query.published_at.map(validate_published_at).transpose()?;
+        self.check_signature(&body, query.signature.as_deref())?;
+
+        let description = query.description.as_deref().unwrap_or("");
+        let extras = PublishExtras {
+            signature: query.signature.clone(),
+            published_at,
+            ..Default::default()
+        };
+        self.storage
+            .store_package(
+                &id,
+                &query.name,
+                description,
+                &version,
+                &platform,
+                &body,
+                author,
+                vec![],
+                query.changelog.as_deref(),
+                query.overwrite.unwrap_or(false),
+                &extras,
+            )
+            .await
+            .map_err(publish_error)?;
+
+        let action = if published_at.is_some() {
+            "import"
+        } else {
+            "publish"
+        };
+        self.metrics.record_publish();
+        self.audit(AuditEntry::new(
+            action,
+            "package",
+            &id,
+            &version,
+            Some(&platform),
+            author,
+            &identity.name,
+        ))
+        .await;
+
+        Ok(PublishResponse {
+            status: "published".to_string(),
+            id,
+            version,
+            platform,
+        })
+    }
+}
+
+#[async_trait]
+impl PluginServiceHandler for AppState {
+    async fn get_latest(
+        &self,
+        id: String,
+        query: PluginServiceGetLatestQuery,
+    ) -> Result<PluginInfo, ApiError> {
+        let latest = match query.req.as_deref() {
+            None => self.storage.get_plugin_latest(&id).await,
+            Some(req) => {
+                let version = self
+                    .storage
+                    .latest_matching("plugins", &id, req)
+                    .await
+                    .map_err(|e| match e.downcast_ref::<InvalidVersionReq>() {
+                        Some(invalid) => bad_request(&invalid.to_string()),
+                        None => internal_error(e),
+                    })?
+                    .ok_or_else(|| {
+                        not_found(&format!("No published version of {} matches {}", id, req))
+                    })?;
+                self.storage.get_plugin_info(&id, &version).await
+            }
+        };
+        let info = self
+            .or_gone(latest, "plugins", &id, "Plugin not found")
+            .await?;
+        let mut info: PluginInfo = json_convert(&info)?;
+        filter_platform(&mut info.platforms, query.platform.as_deref())?;
+        self.mark_corrupt("plugins", &id, &info.version, &mut info.platforms)
+            .await;
+        info.changelog = self.plugin_changelog(&id, &info.version).await;
+        info.dependencies = self.plugin_dependencies(&id, &info.version).await;
+        info.assets = self.plugin_assets(&id, &info.version).await;
+        info.yanked = self.yanked_flag("plugins", &id, &info.version).await;
+        Ok(info)
+    }
+
+    async fn get_latest_batch(
+        &self,
+        ids: Vec<String>,
+    ) -> Result<HashMap<String, PluginInfo>, ApiError> {
+        check_batch_size(&ids)?;
+        let infos = self
+            .storage
+            .get_plugins_latest(&ids)
+            .await
+            .map_err(internal_error)?;
+        let mut batch = HashMap::with_capacity(infos.len());
+        for (id, info) in infos {
+            let mut info: PluginInfo = json_convert(&info)?;
+            self.mark_corrupt("plugins", &id, &info.version, &mut info.platforms)
+                .await;
+            info.changelog = self.plugin_changelog(&id, &info.version).await;
+            info.dependencies = self.plugin_dependencies(&id, &info.version).await;
+            info.assets = self.plugin_assets(&id, &info.version).await;
+            info.yanked = self.yanked_flag("plugins", &id, &info.version).await;
+            batch.insert(id, info);
+        }
+        Ok(batch)
+    }
+
+    async fn get_latest_version(
+        &self,
+        id: String,
+        if_none_match: Option<String>,
+    ) -> Result<axum::response::Response, ApiError> {
+        // Served straight from the index so polling never touches info.json
+        let index = self.storage.load_index().await.map_err(internal_error)?;
+        let version = index
+            .plugins
+            .into_iter()
+            .find(|p| p.id == id)
+            .map(|p| p.latest_version);
+        let Some(version) = version else {
+            return Err(self.missing("plugins", &id, "Plugin not found").await);
+        };
+        self.check_not_yanked(&id, &version).await?;
+
+        let etag = content_etag(version.as_bytes());
+        let cache_control = format!("public, max-age={}", self.config.index_max_age);
+        if let Some(mut response) = not_modified(if_none_match.as_deref(), &etag) {
+            response.headers_mut().insert(
+                header::CACHE_CONTROL,
+                HeaderValue::from_str(&cache_control).map_err(internal_error)?,
+            );
+            return Ok(response);
+        }
+
+        axum::response::Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .body(Body::from(version))
+            .map_err(internal_error)
+    }
+
+    async fn get_bundle(
+        &self,
+        id: String,
+        host: Option<String>,
+        x_forwarded_proto: Option<String>,
+        x_forwarded_host: Option<String>,
+    ) -> Result<PluginBundle, ApiError> {
+        let base_url = self.base_url(
+            host.as_deref(),
+            x_forwarded_proto.as_deref(),
+            x_forwarded_host.as_deref(),
+        );
+        let bundle = self
+            .or_gone(
+                self.storage.get_plugin_bundle(&id, &base_url).await,
+                "plugins",
+                &id,
+                "Plugin not found",
+            )
+            .await?;
+
+        let mut bundle = PluginBundle {
+            id: bundle.id,
+            name: bundle.name,
+            description: bundle.description,
+            plugin_type: bundle.plugin_type,
+            author: bundle.author,
+            version: bundle.version,
+            published_at: bundle.published_at,
+            platforms: bundle
+                .platforms
+                .into_iter()
+                .map(|build| PlatformBuild {
+                    platform: build.platform,
+                    download_url: build.download_url,
+                    size_bytes: build.size_bytes,
+                    checksum: build.checksum,
+                    signature: build.signature,
+                    corrupt: None,
+                })
+                .collect(),
+            web_ui_url: bundle.web_ui_url,
+            dependencies: bundle.dependencies.into_iter().collect(),
+        };
+        self.mark_corrupt("plugins", &id, &bundle.version, &mut bundle.platforms)
+            .await;
+        Ok(bundle)
+    }
+
+    async fn get_dependents(&self, id: String) -> Result<PluginDependents, ApiError> {
+        self.or_gone(
+            self.storage.get_plugin_latest(&id).await,
+            "plugins",
+            &id,
+            "Plugin not found",
+        )
+        .await?;
+        let dependents = self
+            .storage
+            .reverse_dependencies(&id)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(PluginDependents {
+            id,
+            dependents: dependents
+                .into_iter()
+                .map(|d| Dependent {
+                    id: d.id,
+                    version: d.version,
+                    requirement: d.requirement,
+                })
+                .collect(),
+        })
+    }
+
+    async fn list_versions(
+        &self,
+        id: String,
+        if_none_match: Option<String>,
+    ) -> Result<axum::response::Response, ApiError> {
+        let versions = self
+            .storage
+            .list_plugin_versions(&id)
+            .await
+            .map_err(internal_error)?;
+        if versions.is_empty() {
+            return Err(self.missing("plugins", &id, "Plugin not found").await);
+        }
+        let versions: Vec<PluginVersionEntry> = versions
+            .into_iter()
+            .map(|v| PluginVersionEntry {
+                version: v.version,
+                has_web_ui: v.has_web_ui,
+            })
+            .collect();
+
+        // The list is newest first, so the body hash also covers the latest version
+        let json = serde_json::to_vec(&versions).map_err(internal_error)?;
+        let etag = content_etag(&json);
+        let cache_control = format!("public, max-age={}", self.config.index_max_age);
+        if let Some(mut response) = not_modified(if_none_match.as_deref(), &etag) {
+            response.headers_mut().insert(
+                header::CACHE_CONTROL,
+                HeaderValue::from_str(&cache_control).map_err(internal_error)?,
+            );
+            return Ok(response);
+        }
+
+        axum::response::Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .body(Body::from(json))
+            .map_err(internal_error)
+    }
+
+    async fn get_platform_versions(
+        &self,
+        id: String,
+        platform: String,
+    ) -> Result<PlatformVersions, ApiError> {
+        self.or_gone(
+            self.storage.get_plugin_latest(&id).await,
+            "plugins",
+            &id,
+            "Plugin not found",
+        )
+        .await?;
+        let platform = normalize_platform(&platform);
+        let versions = self
+            .storage
+            .plugin_versions_with_platform(&id, &platform)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(PlatformVersions {
+            id,
+            platform,
+            versions,
+        })
+    }
+
+    async fn get_version(
+        &self,
+        id: String,
+        version: String,
+        query: PluginServiceGetVersionQuery,
+        if_none_match: Option<String>,
+    ) -> Result<axum::response::Response, ApiError> {
+        let version = strip_extension(&version, ".json")
+            .ok_or_else(|| not_found("Plugin version not found"))?;
+        let info = self
+            .or_gone(
+                self.storage.get_plugin_info(&id, version).await,
+                "plugins",
+                &id,
+                "Plugin version not found",
+            )
+            .await?;
+        let mut info: PluginInfo = json_convert(&info)?;
+        filter_platform(&mut info.platforms, query.platform.as_deref())?;
+        self.mark_corrupt("plugins", &id, version, &mut info.platforms)
+            .await;
+        info.changelog = self.plugin_changelog(&id, version).await;
+        info.dependencies = self.plugin_dependencies(&id, version).await;
+        info.assets = self.plugin_assets(&id, version).await;
+        info.yanked = self.yanked_flag("plugins", &id, version).await;
+        json_with_etag(&info, if_none_match.as_deref())
+    }
+
+    async fn get_raw_info(
+        &self,
+        id: String,
+        version: String,
+    ) -> Result<axum::response::Response, ApiError> {
+        let data = self
+            .or_gone(
+                self.storage.get_plugin_info_raw(&id, &version).await,
+                "plugins",
+                &id,
+                "Plugin version not found",
+            )
+            .await?;
+
+        axum::response::Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ETAG, content_etag(&data))
+            .body(Body::from(data))
+            .map_err(internal_error)
+    }
+
+    async fn list_platforms(&self, id: String, version: String) -> Result<Vec<String>, ApiError> {
+        let info = self
+            .or_gone(
+                self.storage.get_plugin_info(&id, &version).await,
+                "plugins",
+                &id,
+                "Plugin version not found",
+            )
+            .await?;
+        Ok(info.platforms.into_iter().map(|p| p.platform).collect())
+    }
+
+    async fn download_asset(
+        &self,
+        id: String,
+        version: String,
+        name: String,
+    ) -> Result<axum::response::Response, ApiError> {
+        let Some(path) = self.storage.asset_path("plugins", &id, &version, &name) else {
+            return Err(self.missing("plugins", &id, "Plugin asset not found").await);
+        };
+        let metadata = tokio::fs::metadata(&path).await.map_err(internal_error)?;
+        let chunk_size = self.config.download_chunk_size;
+        serve_file_response(path, &name, &weak_etag(&metadata), None, chunk_size, || {}).await
+    }
+
+    async fn resolve_dependencies(
+        &self,
+        id: String,
+        version: String,
+    ) -> Result<Vec<ResolvedDependency>, ApiError> {
+        self.or_gone(
+            self.storage.get_plugin_info(&id, &version).await,
+            "plugins",
+            &id,
+            "Plugin version not found",
+        )
+        .await?;
+        let resolved = self
+            .storage
+            .resolve_dependencies(&id, &version)
+            .await
+            .map_err(resolve_error)?;
+        Ok(resolved
+            .into_iter()
+            .map(|r| ResolvedDependency {
+                id: r.id,
+                version: r.version,
+            })
+            .collect())
+    }
+
+    async fn download(
+        &self,
+        id: String,
+        version: String,
+        platform: String,
+        query: PluginServiceDownloadQuery,
+        accept: Option<String>,
+        if_none_match: Option<String>,
+        range: Option<String>,
+    ) -> Result<axum::response::Response, ApiError> {
+        if let Some(platform) = strip_extension(&platform, ".tar.gz.sig") {
+            return self.download_signature(&id, &version, platform).await;
+        }
+        self.check_accept(accept.as_deref())?;
+        let platform = strip_extension(&platform, ".tar.gz")
+            .ok_or_else(|| not_found("Plugin artifact not found"))?;
+        if version == "latest" {
+            return self.redirect_to_latest(&id, platform, &query).await;
+        }
+        let path = self.storage.plugin_artifact_path(&id, &version, platform);
+
+        if !path.exists() {
+            return match self.storage.get_plugin_info(&id, &version).await {
+                Ok(info) if info.platforms.is_empty() => Err(no_artifacts(&id, &version)),
+                _ => Err(self
+                    .missing("plugins", &id, "Plugin artifact not found")
+                    .await),
+            };
+        }
+
+        let metadata = tokio::fs::metadata(&path).await.map_err(internal_error)?;
+        let strong = query.verify.unwrap_or(false);
+        let checksum = if strong || self.config.verify_on_download {
+            let checksum = self
+                .build_checksum("plugins", &id, &version, platform)
+                .await
+                .ok_or_else(|| not_found("Plugin artifact not found"))?;
+            Some(checksum)
+        } else {
+            None
+        };
+        let etag = match &checksum {
+            Some(checksum) if strong => strong_etag(checksum),
+            _ => weak_etag(&metadata),
+        };
+        if let Some(response) = not_modified(if_none_match.as_deref(), &etag) {
+            return Ok(response);
+        }
+        if let (true, Some(checksum)) = (self.config.verify_on_download, &checksum) {
+            self.verify_artifact(&path, checksum).await?;
+        }
+
+        let filename = download_filename(&id, &version, platform, query.filename.as_deref());
+        // Counted once streaming starts, so aborted requests don't inflate the total
+        let storage = self.storage.clone();
+        let metrics = self.metrics.clone();
+        let chunk_size = self.config.download_chunk_size;
+        let range = range.as_deref();
+        serve_file_response(path, &filename, &etag, range, chunk_size, move || {
+            metrics.record_download();
+            if storage.options().count_downloads {
+                tokio::spawn(async move {
+                    let _ = storage.increment_downloads("plugins", &id).await;
+                });
+            }
+        })
+        .await
+    }
+
+    async fn get_artifact_base64(
+        &self,
+        id: String,
+        version: String,
+        platform: String,
+    ) -> Result<ArtifactBase64, ApiError> {
+        let mut artifact = self
+            .or_gone(
+                self.storage
+                    .open_artifact_reader("plugins", &id, &version, &platform)
+                    .await,
+                "plugins",
+                &id,
+                "Plugin artifact not found",
+            )
+            .await?;
+        if artifact.size_bytes > self.config.max_base64_bytes {
+            return Err(payload_too_large(&format!(
+                "Artifact is {} bytes; base64.json serves at most {}",
+                artifact.size_bytes, self.config.max_base64_bytes
+            )));
+        }
+
+        let mut data = Vec::with_capacity(artifact.size_bytes as usize);
+        artifact
+            .reader
+            .read_to_end(&mut data)
+            .await
+            .map_err(internal_error)?;
+        Ok(ArtifactBase64 {
+            platform: normalize_platform(&platform),
+            size_bytes: data.len() as u64,
+            checksum: artifact.checksum,
+            data_base64: encode_base64(&data),
+        })
+    }
+}
+
+impl AppState {
+    /// 404 when the latest version of a plugin is yanked, which only happens once all are.
+    async fn check_not_yanked(&self, id: &str, latest: &str) -> Result<(), ApiError> {
+        let yanked = self
+            .storage
+            .is_yanked("plugins", id, latest)
+            .await
+            .map_err(internal_error)?;
+        if yanked {
+            return Err(not_found(&format!("Every version of {} is yanked", id)));
+        }
+        Ok(())
+    }
+
+    /// Redirect a `latest` download to the artifact's versioned URL, keeping the query.
+    ///
+    /// Only the redirect goes stale when a new version is published; what it points at
+    /// never changes, so caches can keep the artifact itself.
+    async fn redirect_to_latest(
+        &self,
+        id: &str,
+        platform: &str,
+        query: &PluginServiceDownloadQuery,
+    ) -> Result<axum::response::Response, ApiError> {
+        let index = self.storage.load_index().await.map_err(internal_error)?;
+        let latest = index
+            .plugins
+            .into_iter()
+            .find(|p| p.id == id)
+            .map(|p| p.latest_version);
+        let Some(latest) = latest else {
+            return Err(self.missing("plugins", id, "Plugin not found").await);
+        };
+        self.check_not_yanked(id, &latest).await?;
+        if !self
+            .storage
+            .plugin_artifact_path(id, &latest, platform)
+            .exists()
+        {
+            return Err(not_found(&format!(
+                "Latest version {} of {} has no {} artifact",
+                latest, id, platform
+            )));
+        }
+
+        let mut params = vec![];
+        if let Some(verify) = query.verify {
+            params.push(format!("verify={}", verify));
+        }
+        if let Some(filename) = query.filename.as_deref().and_then(sanitize_filename) {
+            params.push(format!("filename={}", filename.replace('+', "%2B")));
+        }
+        let mut location = format!("/v1/plugins/{}/{}/{}.tar.gz", id, latest, platform);
+        if !params.is_empty() {
+            location = format!("{}?{}", location, params.join("&"));
+        }
+        axum::response::Response::builder()
+            .status(StatusCode::FOUND)
+            .header(header::LOCATION, location)
+            .header(
+                header::CACHE_CONTROL,
+                format!("public, max-age={}", self.config.index_max_age),
+            )
+            .body(Body::empty())
+            .map_err(internal_error)
+    }
+}
+
+#[async_trait]
+impl PluginPublishServiceHandler for AppState {
+    async fn publish(
+        &self,
+        id: String,
+        version: String,
+        platform: String,
+        query: PluginPublishServicePublishQuery,
+        body: Vec<u8>,
+        identity: Identity,
+    ) -> Result<PublishResponse, ApiError> {
+        if body.is_empty() {
+            return Err(bad_request("No file uploaded"));
+        }
+
+        let platform = self
+            .publish_plugin_platform(&id, &version, &platform, &query, &body, &identity)
+            .await?;
+
+        Ok(PublishResponse {
+            status: "published".to_string(),
+            id,
+            version,
+            platform,
+        })
+    }
+
+    async fn publish_batch(
+        &self,
+        id: String,
+        version: String,
+        query: PluginPublishServicePublishBatchQuery,
+        mut body: axum::extract::Multipart,
+        identity: Identity,
+    ) -> Result<axum::response::Response, ApiError> {
+        let query = PluginPublishServicePublishQuery {
+            name: query.name,
+            description: query.description,
+            plugin_type: query.plugin_type,
+            author: query.author,
+            published_at: query.published_at,
+            overwrite: query.overwrite,
+            changelog: query.changelog,
+            dependencies: query.dependencies,
+            signature: query.signature,
+        };
+        if let Some(published_at) = query.published_at {
+            validate_published_at(published_at)?;
+        }
+        parse_dependencies(query.dependencies.as_deref())?;
+        if query.signature.is_some() {
+            return Err(bad_request(
+                "A signature covers one artifact; publish signed platforms one at a time",
+            ));
+        }
+
+        let files = read_platform_fields(&mut body).await?;
+        let response = self
+            .publish_plugin_batch(id, version, &query, files, &identity)
+            .await;
+        let status = if response.status == "published" {
+            StatusCode::CREATED
+        } else {
+            StatusCode::MULTI_STATUS
+        };
+        Ok((status, Json(response)).into_response())
+    }
+
+    async fn publish_from_url(
+        &self,
+        id: String,
+        version: String,
+        platform: String,
+        query: PluginPublishServicePublishFromUrlQuery,
+        body: UrlPublishRequest,
+        identity: Identity,
+    ) -> Result<UrlPublishResponse, ApiError> {
+        let query = PluginPublishServicePublishQuery {
+            name: query.name,
+            description: query.description,
+            plugin_type: query.plugin_type,
+            author: query.author,
+            published_at: query.published_at,
+            overwrite: query.overwrite,
+            changelog: query.changelog,
+            dependencies: query.dependencies,
+            signature: query.signature,
+        };
+        // Rejected before the download rather than after it
+        if let Some(published_at) = query.published_at {
+            validate_published_at(published_at)?;
+        }
+        parse_dependencies(query.dependencies.as_deref())?;
+
+        let data = self.fetcher.fetch(&body.url).await.map_err(fetch_error)?;
+        if data.is_empty() {
+            return Err(bad_gateway("Source returned an empty body"));
+        }
+        info!(%id, %version, url = %body.url, size = data.len(), "Fetched artifact to publish");
+
+        let platform = self
+            .publish_plugin_platform(&id, &version, &platform, &query, &data, &identity)
+            .await?;
+
+        Ok(UrlPublishResponse {
+            status: "published".to_string(),
+            id,
+            version,
+            platform,
+            size: data.len() as u64,
+            checksum: sha256_hex(&data),
+        })
+    }
+
+    async fn publish_asset(
+        &self,
+        id: String,
+        version: String,
+        name: String,
+        body: Vec<u8>,
+        identity: Identity,
+    ) -> Result<AssetMeta, ApiError> {
+        if body.is_empty() {
+            return Err(bad_request("Empty asset"));
+        }
+        self.or_gone(
+            self.storage.get_plugin_info(&id, &version).await,
+            "plugins",
+            &id,
+            "Plugin version not found",
+        )
+        .await?;
+        let asset = self
+            .storage
+            .publish_asset("plugins", &id, &version, &name, &body)
+            .await
+            .map_err(publish_error)?;
+
+        self.metrics.record_publish();
+        self.audit(AuditEntry::new(
+            "publish",
+            "plugin",
+            &id,
+            &version,
+            Some(&format!("assets/{}", name)),
+            "unknown",
+            &identity.name,
+        ))
+        .await;
+
+        Ok(AssetMeta {
+            name: asset.name,
+            size_bytes: asset.size_bytes,
+            checksum: asset.checksum,
+        })
+    }
+}
+
+impl AppState {
+    /// With `REGISTRY_PUBLISH_PUBKEY` set, refuse an artifact unless `signature`
+    /// verifies over it. Without a key, signatures are stored unchecked.
+    fn check_signature(&self, data: &[u8], signature: Option<&str>) -> Result<(), ApiError> {
+        let Some(key) = &self.config.publish_pubkey else {
+            return Ok(());
+        };
+        let signature = signature.ok_or_else(|| {
+            bad_request("Missing signature; this registry only accepts signed artifacts")
+        })?;
+        key.verify(data, signature)
+            .map_err(|e| bad_request(&e.to_string()))
+    }
+
+    /// Serve the detached signature stored for one plugin build.
+    async fn download_signature(
+        &self,
+        id: &str,
+        version: &str,
+        platform: &str,
+    ) -> Result<axum::response::Response, ApiError> {
+        let info = self
+            .or_gone(
+                self.storage.get_plugin_info(id, version).await,
+                "plugins",
+                id,
+                "Plugin version not found",
+            )
+            .await?;
+        let platform = normalize_platform(platform);
+        let signature = info
+            .platforms
+            .into_iter()
+            .find(|b| b.platform == platform)
+            .and_then(|build| build.signature)
+            .ok_or_else(|| not_found("Plugin artifact has no signature"))?;
+
+        axum::response::Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from(signature))
+            .map_err(internal_error)
+    }
+
+    /// Publish one plugin artifact and record it in the audit log; returns the
+    /// normalized platform.
+    async fn publish_plugin_platform(
+        &self,
+        id: &str,
+        version: &str,
+        platform: &str,
+        query: &PluginPublishServicePublishQuery,
+        body: &[u8],
+        identity: &Identity,
+    ) -> Result<String, ApiError> {
+        let platform = normalize_platform(platform);
+
+        let plugin_type = query.plugin_type.as_deref().unwrap_or("extension");
+        let author = query.author.as_deref().unwrap_or("unknown");
+
+        let published_at = query.published_at.map(validate_published_at).transpose()?;
+        let dependencies = parse_dependencies(query.dependencies.as_deref())?;
+        self.check_signature(body, query.signature.as_deref())?;
+
+        let description = query.description.as_deref().unwrap_or("");
+        // PluginInfo has no changelog field, so it goes in the version's meta.json
+        let extras = PublishExtras {
+            signature: query.signature.clone(),
+            published_at,
+            changelog: query.changelog.clone(),
+            dependencies,
+        };
+        self.storage
+            .store_plugin(
+                id,
+                &query.name,
+                description,
+                plugin_type,
+                version,
+                &platform,
+                body,
+                author,
+                vec![],
+                query.overwrite.unwrap_or(false),
+                &extras,
+            )
+            .await
+            .map_err(publish_error)?;
+
+        let action = if published_at.is_some() {
+            "import"
+        } else {
+            "publish"
+        };
+        self.metrics.record_publish();
+        self.audit(AuditEntry::new(
+            action,
+            "plugin",
+            id,
+            version,
+            Some(&platform),
+            author,
+            &identity.name,
+        ))
+        .await;
+
+        Ok(platform)
+    }
+
+    /// Publish several platforms of one version independently: platforms that succeed
+    /// stay published even when others fail, and every outcome is reported.
+    async fn publish_plugin_batch(
+        &self,
+        id: String,
+        version: String,
+        query: &PluginPublishServicePublishQuery,
+        files: Vec<(String, Vec<u8>)>,
+        identity: &Identity,
+    ) -> BatchPublishResponse {
+        let mut results = Vec::new();
+        for (platform, data) in files {
+            let outcome = match validate_gzip(&data) {
+                Ok(()) => self
+                    .publish_plugin_platform(&id, &version, &platform, query, &data, identity)
+                    .await
+                    .map_err(|e| e.message),
+                Err(error) => Err(error),
+            };
+            results.push(match outcome {
+                Ok(platform) => PlatformPublishResult {
+                    platform,
+                    status: "published".to_string(),
+                    error: None,
+                },
+                Err(error) => PlatformPublishResult {
+                    platform: normalize_platform(&platform),
+                    status: "failed".to_string(),
+                    error: Some(error),
+                },
+            });
+        }
+
+        let published = results.iter().filter(|r| r.status == "published").count();
+        let status = if published == results.len() {
+            "published"
+        } else if published == 0 {
+            "failed"
+        } else {
+            "partial"
+        };
+        BatchPublishResponse {
+            status: status.to_string(),
+            id,
+            version,
+            results,
+        }
+    }
+}
+
+#[async_trait]
+impl PluginMetaPublishServiceHandler for AppState {
+    async fn publish(
+        &self,
+        id: String,
+        version: String,
+        body: PluginMetaRequest,
+        identity: Identity,
+    ) -> Result<PublishResponse, ApiError> {
+        if body.name.is_empty() {
+            return Err(bad_request("Missing plugin name"));
+        }
+
+        let meta = PluginVersionMeta {
+            name: body.name,
+            description: body.description.unwrap_or_default(),
+            plugin_type: body.plugin_type.unwrap_or_else(|| "extension".to_string()),
+            author: body.author.unwrap_or_else(|| "unknown".to_string()),
+            tags: body.tags.unwrap_or_default(),
+            changelog: body.changelog,
+            dependencies: body.dependencies.unwrap_or_default().into_iter().collect(),
+            descriptions: body.descriptions.unwrap_or_default().into_iter().collect(),
+        };
+
+        self.storage
+            .publish_plugin_meta(&id, &version, &meta)
+            .await
+            .map_err(publish_error)?;
+
+        self.metrics.record_publish();
+        self.audit(AuditEntry::new(
+            "publish",
+            "plugin",
+            &id,
+            &version,
+            Some("meta"),
+            &meta.author,
+            &identity.name,
+        ))
+        .await;
+
+        Ok(PublishResponse {
+            status: "registered".to_string(),
+            id,
+            version,
+            platform: "meta".to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl PluginDeleteServiceHandler for AppState {
+    async fn delete(
+        &self,
+        id: String,
+        version: String,
+        identity: Identity,
+    ) -> Result<DeleteVersionResponse, ApiError> {
+        let deleted = self
+            .storage
+            .delete_plugin_version(&id, &version)
+            .await
+            .map_err(internal_error)?;
+        if !deleted {
+            return Err(not_found(&format!("Plugin {} {} not found", id, version)));
+        }
+
+        self.audit(AuditEntry::new(
+            "delete",
+            "plugin",
+            &id,
+            &version,
+            None,
+            "unknown",
+            &identity.name,
+        ))
+        .await;
+
+        let index = self.storage.load_index().await.map_err(internal_error)?;
+        let latest_version = index
+            .plugins
+            .into_iter()
+            .find(|p| p.id == id)
+            .map(|p| p.latest_version);
+        Ok(DeleteVersionResponse {
+            status: "deleted".to_string(),
+            id,
+            version,
+            latest_version,
+        })
+    }
+}
+
+#[async_trait]
+impl PluginYankServiceHandler for AppState {
+    async fn yank(
+        &self,
+        id: String,
+        version: String,
+        query: PluginYankServiceYankQuery,
+        identity: Identity,
+    ) -> Result<YankResponse, ApiError> {
+        let yanked = !query.undo.unwrap_or(false);
+        let (action, status) = if yanked {
+            ("yank", "yanked")
+        } else {
+            ("unyank", "unyanked")
+        };
+        let found = self
+            .storage
+            .set_yanked("plugins", &id, &version, yanked)
+            .await
+            .map_err(internal_error)?;
+        if !found {
+            return Err(not_found(&format!("Plugin {} {} not found", id, version)));
+        }
+
+        self.audit(AuditEntry::new(
+            action,
+            "plugin",
+            &id,
+            &version,
+            None,
+            "unknown",
+            &identity.name,
+        ))
+        .await;
+
+        let index = self.storage.load_index().await.map_err(internal_error)?;
+        let latest_version = index
+            .plugins
+            .into_iter()
+            .find(|p| p.id == id)
+            .map(|p| p.latest_version);
+        Ok(YankResponse {
+            status: status.to_string(),
+            id,
+            version,
+            yanked,
+            latest_version,
+        })
+    }
+}
+
+#[async_trait]
+impl PluginWebUiPublishServiceHandler for AppState {
+    async fn publish(
+        &self,
+        id: String,
+        version: String,
+        query: PluginWebUiPublishServicePublishQuery,
+        body: Vec<u8>,
+        identity: Identity,
+    ) -> Result<PublishResponse, ApiError> {
+        if body.is_empty() {
+            return Err(bad_request("Empty body — expected JavaScript content"));
+        }
+
+        if query.allow_overwrite.unwrap_or(false) {
+            self.storage
+                .replace_plugin_web_ui(&id, &version, &body)
+                .await
+        } else {
+            self.storage
+                .publish_plugin_web_ui(&id, &version, &body)
+                .await
+        }
+        .map_err(publish_error)?;
+
+        self.metrics.record_publish();
+        self.audit(AuditEntry::new(
+            "publish",
+            "plugin",
+            &id,
+            &version,
+            Some("web"),
+            "unknown",
+            &identity.name,
+        ))
+        .await;
+
+        Ok(PublishResponse {
+            status: "published".to_string(),
+            id,
+            version,
+            platform: "web".to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl PluginWebUiServiceHandler for AppState {
+    async fn download(
+        &self,
+        id: String,
+        version: String,
+    ) -> Result<axum::response::Response, ApiError> {
+        let path = self.storage.get_plugin_web_ui_path(&id, &version);
+        if !path.exists() {
+            return Err(not_found("Plugin web UI not found"));
+        }
+
+        let content_type = content_type_for(&path.to_string_lossy());
+        let file = File::open(&path).await.map_err(internal_error)?;
+        let stream = ReaderStream::new(file);
+        let body = Body::from_stream(stream);
+
+        axum::response::Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, content_type)
+            .header(
+                header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable",
+            )
+            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+            .body(body)
+            .map_err(internal_error)
+    }
+
+    async fn get_import_map(
+        &self,
+        host: Option<String>,
+        x_forwarded_proto: Option<String>,
+        x_forwarded_host: Option<String>,
+    ) -> Result<ImportMap, ApiError> {
+        let base_url = self.base_url(
+            host.as_deref(),
+            x_forwarded_proto.as_deref(),
+            x_forwarded_host.as_deref(),
+        );
+        let map = self
+            .storage
+            .get_import_map(&base_url)
+            .await
+            .map_err(internal_error)?;
+        Ok(ImportMap {
+            imports: map.imports.into_iter().collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl AdminKeyServiceHandler for AppState {
+    async fn create(&self, body: CreateApiKeyRequest) -> Result<ApiKeyCreated, ApiError> {
+        if body.label.is_empty() {
+            return Err(bad_request("Key label must not be empty"));
+        }
+
+        let key = format!("adi_{}", uuid::Uuid::new_v4().simple());
+        let entry = self
+            .keys
+            .create(&body.label, &key)
+            .await
+            .map_err(internal_error)?
+            .ok_or_else(|| conflict("Key label already exists"))?;
+
+        info!("Created API key '{}'", entry.label);
+
+        Ok(ApiKeyCreated {
+            label: entry.label,
+            key,
+            created_at: entry.created_at,
+        })
+    }
+
+    async fn revoke(&self, label: String) -> Result<RevokeApiKeyResponse, ApiError> {
+        let revoked = self.keys.revoke(&label).await.map_err(internal_error)?;
+        if !revoked {
+            return Err(not_found("API key not found"));
+        }
+
+        info!("Revoked API key '{}'", label);
+
+        Ok(RevokeApiKeyResponse {
+            status: "revoked".to_string(),
+            label,
+        })
+    }
+}
+
+#[async_trait]
+impl AdminAuditServiceHandler for AppState {
+    async fn list(&self, query: AdminAuditServiceListQuery) -> Result<AuditLog, ApiError> {
+        if let Some(kind) = query.kind.as_deref() {
+            if kind != "package" && kind != "plugin" {
+                return Err(bad_request("kind must be 'package' or 'plugin'"));
+            }
+        }
+
+        let filter = AuditFilter {
+            kind: query.kind,
+            id: query.id,
+            since: query.since,
+        };
+        let page = self.config.pages.resolve(query.limit);
+
+        let entries = self
+            .storage
+            .read_audit(&filter, page.limit as usize)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(AuditLog {
+            events: json_convert(&entries)?,
+            limit: page.limit,
+            clamped: page.clamped,
+        })
+    }
+}
+
+#[async_trait]
+impl AdminRepairServiceHandler for AppState {
+    async fn repair_package_links(&self) -> Result<RepairReport, ApiError> {
+        let corrected = self
+            .storage
+            .repair_package_links()
+            .await
+            .map_err(internal_error)?;
+        if corrected > 0 {
+            warn!(corrected, "Repaired drifted package plugin links");
+        }
+        Ok(RepairReport {
+            corrected: corrected as u32,
+        })
+    }
+}
+
+#[async_trait]
+impl TelemetryServiceHandler for AppState {
+    async fn report(
+        &self,
+        id: String,
+        version: String,
+        platform: String,
+        body: TelemetryReport,
+    ) -> Result<InstallTelemetry, ApiError> {
+        let outcome = match body.outcome.as_str() {
+            "success" => InstallOutcome::Success,
+            "failure" => InstallOutcome::Failure,
+            other => return Err(bad_request(&format!("Unknown outcome: {}", other))),
+        };
+        let platform = normalize_platform(&platform);
+        let info = self
+            .storage
+            .get_plugin_info(&id, &version)
+            .await
+            .map_err(|_| not_found("Plugin version not found"))?;
+        // Only published builds, so reports can't grow the file with made-up platforms
+        if !info.platforms.iter().any(|b| b.platform == platform) {
+            return Err(not_found("Plugin artifact not found"));
+        }
+        let telemetry = self
+            .storage
+            .record_install(&id, &version, &platform, outcome, body.error.as_deref())
+            .await
+            .map_err(internal_error)?;
+        json_convert(&telemetry)
+    }
+
+    async fn get(&self, id: String, version: String) -> Result<InstallTelemetry, ApiError> {
+        self.storage
+            .get_plugin_info(&id, &version)
+            .await
+            .map_err(|_| not_found("Plugin version not found"))?;
+        let telemetry = self
+            .storage
+            .install_telemetry(&id, &version)
+            .await
+            .map_err(internal_error)?;
+        json_convert(&telemetry)
+    }
+}
+
+/// Convert core types to generated models via serde Value
+fn json_convert<T: serde::Serialize, U: serde::de::DeserializeOwned>(
+    val: &T,
+) -> Result<U, ApiError> {
+    serde_json::to_value(val)
+        .and_then(|v| serde_json::from_value(v))
+        .map_err(internal_error)
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "ok",
+        "service": "adi-plugin-registry",
+        "version": env!("CARGO_PKG_VERSION")
+    }))
+}
+
+/// Readiness probe: 503 when the index can't be loaded, unlike the unconditional `/health`.
+async fn ready(State(state): State<Arc<AppState>>) -> Result<Json<serde_json::Value>, ApiError> {
+    state.storage.load_index().await.map_err(|e| ApiError {
+        status: 503,
+        code: "storage_unavailable".to_string(),
+        message: format!("{:#}", e),
+    })?;
+    Ok(Json(serde_json::json!({ "status": "ready" })))
+}
+
+/// Health plus index counts for dashboards; reads only the index, never the artifact tree.
+async fn status(State(state): State<Arc<AppState>>) -> Result<Json<serde_json::Value>, ApiError> {
+    let index = state.storage.load_index().await.map_err(internal_error)?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION"),
+        "packages": index.packages.len(),
+        "plugins": index.plugins.len(),
+        "index_revision": index.updated_at,
+        "uptime_seconds": state.started_at.elapsed().as_secs()
+    })))
+}
+
+/// Counters plus index totals in the Prometheus text format; the totals are read on
+/// each scrape.
+async fn prometheus_metrics(
+    State(state): State<Arc<AppState>>,
+) -> Result<axum::response::Response, ApiError> {
+    let stats = state
+        .storage
+        .compute_stats()
+        .await
+        .map_err(internal_error)?;
+    let body = state.metrics.render(stats.packages, stats.plugins);
+    axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )
+        .body(Body::from(body))
+        .map_err(internal_error)
+}
+
+/// Build the log subscriber: human-readable by default, one JSON object per line if `json`.
+fn subscriber(json: bool) -> Box<dyn tracing::Subscriber + Send + Sync> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let registry = tracing_subscriber::registry();
+
+    if json {
+        Box::new(
+            registry
+                .with(
+                    fmt::layer()
+                        .json()
+                        .flatten_event(true)
+                        .with_current_span(true),
+                )
+                .with(filter),
+        )
+    } else {
+        Box::new(registry.with(fmt::layer()).with(filter))
+    }
+}
+
+/// Request span carrying the fields our log pipeline indexes on.
+fn request_span(request: &axum::http::Request<Body>) -> tracing::Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or_else(|| request.uri().path());
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+
+    tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        route = %route,
+    )
+}
+
+/// Log completion of a request with its status, latency and any artifact bytes served.
+fn log_response(response: &axum::response::Response, latency: Duration, _span: &tracing::Span) {
+    let served_bytes = response
+        .headers()
+        .get(SERVED_BYTES)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    info!(
+        status = response.status().as_u16(),
+        latency_ms = latency.as_millis() as u64,
+        served_bytes,
+        "request completed"
+    );
+}
+
+/// Compress responses per `Accept-Encoding`, except artifacts that are already gzip.
+/// Responses that set their own `Content-Encoding`, like the cached gzip index, pass through.
+fn compression() -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new().compress_when(
+        DefaultPredicate::new().and(NotForContentType::const_new("application/gzip")),
+    )
+}
+
+/// Resolve on Ctrl-C or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        if let Ok(mut signal) =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        {
+            signal.recv().await;
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutting down");
+}
+
+/// Every route with its guards and the layers shared by all of them.
+fn router(state: Arc<AppState>) -> Router {
+    let publish_routes = Router::new()
+        .merge(package_publish_service_routes())
+        .merge(plugin_meta_publish_service_routes())
+        .merge(plugin_web_ui_publish_service_routes())
+        .merge(plugin_publish_service_routes())
+        .merge(plugin_delete_service_routes())
+        .merge(plugin_yank_service_routes())
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_api_key,
+        ))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_https))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_allowed_ip,
+        ));
+
+    let mut app = Router::new()
+        .route("/", get(health))
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .route("/v1/status.json", get(status))
+        .route("/metrics", get(prometheus_metrics))
+        .merge(index_service_routes())
+        .merge(capabilities_service_routes())
+        .merge(stats_service_routes())
+        .merge(search_service_routes())
+        .merge(package_service_routes())
+        .merge(plugin_service_routes())
+        .merge(plugin_web_ui_service_routes())
+        .merge(publish_routes);
+
+    // Admin routes only exist when an admin key is configured
+    if state.config.admin_key.is_some() {
+        let admin_routes = Router::new()
+            .merge(admin_key_service_routes())
+            .merge(admin_audit_service_routes())
+            .merge(admin_repair_service_routes())
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_admin_key,
+            ))
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_allowed_ip,
+            ));
+        app = app.merge(admin_routes);
+    }
+
+    if state.config.enable_telemetry {
+        let telemetry_routes = telemetry_service_routes().route_layer(
+            middleware::from_fn_with_state(state.clone(), limit_telemetry),
+        );
+        app = app.merge(telemetry_routes);
+    }
+
+    let app = app
+        .layer(axum::extract::DefaultBodyLimit::max(100 * 1024 * 1024))
+        .layer(compression())
+        .layer(version_header_layer(
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        ))
+        .layer(CorsLayer::permissive())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            security_headers,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            count_responses,
+        ))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_span)
+                .on_response(log_response),
+        )
+        .with_state(state);
+
+    // Wraps the whole router rather than each route, so preflights see the `Allow`
+    // header the router adds to its 405 responses
+    Router::new().fallback_service(middleware::from_fn(cors_preflight).layer(app))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut config = RegistryConfig::from_env()?;
+    if let Some(data_dir) = std::env::args().nth(1) {
+        config.data_dir = PathBuf::from(data_dir);
+    }
+
+    tracing::subscriber::set_global_default(subscriber(config.json_logs))?;
+
+    info!("Starting Plugin Registry HTTP server");
+    info!("Data directory: {}", config.data_dir.display());
+
+    let options = &config.storage;
+    if !options.count_downloads {
+        info!("Download counting disabled");
+    }
+    if let Some(delay) = options.index_flush_delay {
+        info!("Coalescing index writes every {:?}", delay);
+    }
+    if let Some(read_root) = &options.read_root {
+        info!("Falling back to read root {}", read_root.display());
+    }
+
+    let storage = Arc::new(RegistryStorage::with_options(
+        config.data_dir.clone(),
+        options.clone(),
+    ));
+    storage.init().await?;
+    storage.warm().await?;
+
+    // Optional background integrity scrub; artifacts are hashed one at a time with a
+    // short pause in between so request handling keeps priority
+    if let Some(interval) = config.scrub_interval {
+        info!("Scrubbing artifacts every {:?}", interval);
+        storage.spawn_scrubber(interval, Duration::from_millis(100));
+    }
+
+    let keys = KeyStore::load(storage.root().join("keys.json")).await?;
+    if keys.is_enabled() || !config.api_keys.is_empty() {
+        info!(
+            "Publish authentication enabled ({} keys, {} from REGISTRY_API_KEYS)",
+            keys.list().len() + config.api_keys.len(),
+            config.api_keys.len()
+        );
+    }
+
+    if config.force_https && !config.trust_proxy {
+        warn!("REGISTRY_FORCE_HTTPS without REGISTRY_TRUST_PROXY rejects every publish");
+    }
+
+    let port = config.port;
+    let keys = Arc::new(keys);
+    let mut authorizer: Arc<dyn Authorizer> = Arc::new(
+        TokenFileAuthorizer::new(keys.clone(), config.admin_key.clone())
+            .with_static_keys(config.api_keys.clone()),
+    );
+    let jwt = if let Some(secret) = &config.jwt_secret {
+        Some(JwtAuthorizer::hs256(secret.as_bytes()))
+    } else if let Some(path) = &config.jwt_public_key_file {
+        let pem = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Some(JwtAuthorizer::rs256_pem(&pem)?)
+    } else if let Some(url) = &config.jwt_jwks_url {
+        Some(JwtAuthorizer::jwks_url(url)?)
+    } else {
+        None
+    };
+    if let Some(mut jwt) = jwt {
+        jwt = jwt.with_fallback(authorizer);
+        if let Some(audience) = &config.jwt_audience {
+            jwt = jwt.with_audience(audience);
+        }
+        authorizer = Arc::new(jwt);
+    } else if config.jwt_audience.is_some() {
+        warn!("REGISTRY_JWT_AUDIENCE has no effect without a JWT key");
+    }
+    let telemetry_limiter = RateLimiter::new(config.telemetry_rate_limit, Duration::from_secs(60));
+    let fetcher = SourceFetcher::new(config.fetch_max_bytes, config.fetch_timeout)?;
+    let state = Arc::new(AppState {
+        storage: storage.clone(),
+        keys,
+        authorizer,
+        config,
+        started_at: std::time::Instant::now(),
+        index_gzip: Default::default(),
+        telemetry_limiter,
+        metrics: Arc::default(),
+        fetcher,
+    });
+
+    let app = router(state);
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    info!("Listening on http://{}", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+    // Persist any coalesced index changes before exiting
+    storage.flush_index().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pagination::PageLimits;
+    use plugin_registry_core::{PublishKey, StorageOptions};
+    use std::io::Read;
+    use tower::ServiceExt;
+
+    fn preflight(uri: &str) -> axum::http::Request<Body> {
+        axum::http::Request::builder()
+            .method("OPTIONS")
+            .uri(uri)
+            .header("origin", "https://app.example.com")
+            .header("access-control-request-method", "POST")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    async fn test_state(root: &std::path::Path) -> AppState {
+        let storage = Arc::new(RegistryStorage::new(root.to_path_buf()));
+        storage.init().await.unwrap();
+        let keys = Arc::new(KeyStore::load(root.join("keys.json")).await.unwrap());
+        AppState {
+            storage,
+            authorizer: Arc::new(TokenFileAuthorizer::new(keys.clone(), None)),
+            keys,
+            config: RegistryConfig::default(),
+            started_at: std::time::Instant::now(),
+            index_gzip: Default::default(),
+            telemetry_limiter: RateLimiter::new(30, Duration::from_secs(60)),
+            metrics: Arc::default(),
+            fetcher: SourceFetcher::allowing_http(1024 * 1024, Duration::from_secs(5)).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_limit_clamped_to_page_cap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = AppState {
+            config: RegistryConfig {
+                pages: PageLimits {
+                    default_page_size: 10,
+                    max_page_size: 50,
+                },
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        };
+
+        let log = AdminAuditServiceHandler::list(
+            &state,
+            AdminAuditServiceListQuery {
+                kind: None,
+                id: None,
+                since: None,
+                limit: Some(5000),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(log.limit, 50);
+        assert!(log.clamped);
+
+        let caps = CapabilitiesServiceHandler::get(&state).await.unwrap();
+        assert_eq!(caps.max_page_size, 50);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_gzip_index_not_modified() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let get_index = |if_none_match: Option<&str>| {
+            IndexServiceHandler::get_index(
+                &state,
+                IndexServiceGetIndexQuery {
+                    updated_since: None,
+                    since: None,
+                    lang: None,
+                },
+                if_none_match.map(str::to_string),
+                Some("gzip, deflate".to_string()),
+                None,
+            )
+        };
+
+        let response = get_index(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=60"
+        );
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = get_index(Some(&etag)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_index_localized_description() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let body: PluginMetaRequest = serde_json::from_value(serde_json::json!({
+            "name": "Tasks",
+            "description": "Task management",
+            "descriptions": { "fr": "Gestion des tâches", "de": "Aufgabenverwaltung" }
+        }))
+        .unwrap();
+        PluginMetaPublishServiceHandler::publish(
+            state.as_ref(),
+            "adi.tasks".to_string(),
+            "1.0.0".to_string(),
+            body,
+            Identity::new("test"),
+        )
+        .await
+        .unwrap();
+
+        let app = index_service_routes().with_state(state);
+        let description = |uri: &str, accept_language: Option<&str>| {
+            let mut request = axum::http::Request::builder().uri(uri);
+            if let Some(value) = accept_language {
+                request = request.header(header::ACCEPT_LANGUAGE, value);
+            }
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(request.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let index: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                index["plugins"][0]["description"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            }
+        };
+
+        assert_eq!(
+            description("/v1/index.json?lang=fr", None).await,
+            "Gestion des tâches"
+        );
+        assert_eq!(
+            description("/v1/index.json", Some("de-AT, fr;q=0.8")).await,
+            "Aufgabenverwaltung"
+        );
+        assert_eq!(
+            description("/v1/index.json?lang=fr", Some("de")).await,
+            "Gestion des tâches"
+        );
+        assert_eq!(
+            description("/v1/index.json?lang=es", None).await,
+            "Task management"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_publish_reports_each_platform() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"binary").unwrap();
+        let gzip = encoder.finish().unwrap();
+
+        let query = PluginPublishServicePublishQuery {
+            name: "Tasks".to_string(),
+            description: None,
+            plugin_type: None,
+            author: None,
+            published_at: None,
+            overwrite: None,
+            changelog: None,
+            dependencies: None,
+            signature: None,
+        };
+        let files = vec![
+            ("linux-x86_64".to_string(), gzip.clone()),
+            ("Darwin-AArch64".to_string(), b"not gzip".to_vec()),
+            ("windows-x86_64".to_string(), gzip),
+        ];
+        let response = state
+            .publish_plugin_batch(
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                &query,
+                files,
+                &Identity::new("ci"),
+            )
+            .await;
+
+        assert_eq!(response.status, "partial");
+        let outcomes: Vec<_> = response
+            .results
+            .iter()
+            .map(|r| (r.platform.as_str(), r.status.as_str(), r.error.is_some()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("linux-x86_64", "published", false),
+                ("darwin-aarch64", "failed", true),
+                ("windows-x86_64", "published", false),
+            ]
+        );
+
+        // The platforms that succeeded stay published
+        let info = state
+            .storage
+            .get_plugin_info("adi.tasks", "1.0.0")
+            .await
+            .unwrap();
+        assert_eq!(info.platforms.len(), 2);
+
+        // Each published platform is audited as the identity that sent the batch
+        let entries = state
+            .storage
+            .read_audit(&AuditFilter::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.actor == "ci"));
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_proto_builds_https_urls() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = AppState {
+            config: RegistryConfig {
+                trust_proxy: true,
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        };
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let bundle = PluginServiceHandler::get_bundle(
+            &state,
+            "adi.tasks".to_string(),
+            Some("registry:8080".to_string()),
+            Some("https".to_string()),
+            Some("plugins.example.com".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            bundle.platforms[0].download_url,
+            "https://plugins.example.com/v1/plugins/adi.tasks/1.0.0/linux-x86_64.tar.gz"
+        );
+
+        // Forwarded headers are ignored unless the proxy is trusted
+        let untrusted = test_state(tmp.path()).await;
+        assert_eq!(
+            untrusted.base_url(Some("registry:8080"), Some("https"), None),
+            ""
+        );
+        assert_eq!(untrusted.request_scheme(Some("https")), "http");
+        assert_eq!(state.request_scheme(Some("HTTPS, http")), "https");
+    }
+
+    #[tokio::test]
+    async fn test_web_ui_overwrite_conflicts_unless_allowed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let options = StorageOptions {
+            immutable_web_ui: true,
+            ..Default::default()
+        };
+        let storage = Arc::new(RegistryStorage::with_options(
+            tmp.path().to_path_buf(),
+            options,
+        ));
+        let state = AppState {
+            storage,
+            ..test_state(tmp.path()).await
+        };
+        let publish = |allow_overwrite| {
+            PluginWebUiPublishServiceHandler::publish(
+                &state,
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                PluginWebUiPublishServicePublishQuery { allow_overwrite },
+                b"export default {}".to_vec(),
+                Identity::new("test"),
+            )
+        };
+
+        publish(None).await.unwrap();
+        let err = publish(None).await.unwrap_err();
+        assert_eq!(err.status, 409);
+        publish(Some(true)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_map_route() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState {
+            config: RegistryConfig {
+                public_url: "https://plugins.example.com".to_string(),
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        });
+        state
+            .storage
+            .publish_plugin_web_ui("adi.tasks", "1.0.0", b"export default {}")
+            .await
+            .unwrap();
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        // A static segment must win over the `/:id/...` plugin routes
+        let app = Router::new()
+            .merge(plugin_service_routes())
+            .merge(plugin_web_ui_service_routes())
+            .with_state(state);
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/v1/plugins/importmap.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let map: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            map,
+            serde_json::json!({
+                "imports": {
+                    "adi.tasks": "https://plugins.example.com/v1/plugins/adi.tasks/1.0.0/web.js"
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_latest_version_plain_text() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        for version in ["1.0.0", "1.2.0", "1.1.0"] {
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let app = plugin_service_routes().with_state(state.clone());
+        let get = |uri: &str, if_none_match: Option<&str>| {
+            let mut request = axum::http::Request::builder().uri(uri);
+            if let Some(etag) = if_none_match {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = get("/v1/plugins/adi.tasks/latest.txt", None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=60"
+        );
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let index = state.storage.load_index().await.unwrap();
+        assert_eq!(body, index.plugins[0].latest_version.as_bytes());
+        assert_eq!(body, "1.2.0".as_bytes());
+
+        let response = get("/v1/plugins/adi.tasks/latest.txt", Some(&etag))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = get("/v1/plugins/adi.missing/latest.txt", None)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_security_headers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState {
+            config: RegistryConfig {
+                trust_proxy: true,
+                hsts_max_age: Some(31_536_000),
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        });
+        state
+            .storage
+            .publish_plugin_web_ui("adi.tasks", "1.0.0", b"export default {}")
+            .await
+            .unwrap();
+        let app = Router::new()
+            .route("/health", get(health))
+            .merge(plugin_web_ui_service_routes())
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                security_headers,
+            ))
+            .with_state(state);
+        let get = |uri: &str, proto: &str| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .header("x-forwarded-proto", proto)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/health", "http").await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(
+            response.headers()[header::X_CONTENT_TYPE_OPTIONS],
+            "nosniff"
+        );
+        assert_eq!(response.headers()[header::REFERRER_POLICY], "no-referrer");
+        assert!(response
+            .headers()
+            .get(header::STRICT_TRANSPORT_SECURITY)
+            .is_none());
+
+        // Handler headers survive alongside the added ones
+        let response = get("/v1/plugins/adi.tasks/1.0.0/web.js", "https")
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(
+            response.headers()[header::X_CONTENT_TYPE_OPTIONS],
+            "nosniff"
+        );
+        assert_eq!(
+            response.headers()[header::STRICT_TRANSPORT_SECURITY],
+            "max-age=31536000"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_platform_versions_route() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        for (version, platform) in [
+            ("1.0.0", "linux-x86_64"),
+            ("1.0.0", "windows-x86_64"),
+            ("1.1.0", "linux-x86_64"),
+        ] {
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    platform,
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let app = plugin_service_routes().with_state(state);
+        let get = |uri: &str| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/v1/plugins/adi.tasks/platform/windows-x86_64/versions.json")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let versions: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(versions["versions"], serde_json::json!(["1.0.0"]));
+
+        let response = get("/v1/plugins/adi.missing/platform/linux-x86_64/versions.json")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_aborted_download_not_counted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let download = || {
+            PluginServiceHandler::download(
+                &state,
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                "linux-x86_64.tar.gz".to_string(),
+                PluginServiceDownloadQuery {
+                    verify: None,
+                    filename: None,
+                },
+                None,
+                None,
+                None,
+            )
+        };
+        let downloads = || async {
+            let index = state.storage.load_index().await.unwrap();
+            index.plugins[0].downloads
+        };
+
+        // The client goes away before any of the body is read
+        drop(download().await.unwrap());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(downloads().await, 0);
+
+        let response = download().await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"binary");
+        for _ in 0..100 {
+            if downloads().await == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(downloads().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_preflight_lists_route_methods() {
+        let app = Router::new()
+            .route(
+                "/v1/publish/plugins/:id/:version/:platform",
+                axum::routing::post(|| async { StatusCode::CREATED }),
+            )
+            .route("/v1/index.json", get(|| async { "{}" }))
+            .layer(CorsLayer::permissive());
+        let app = middleware::from_fn(cors_preflight).layer(app);
+
+        let response = app
+            .clone()
+            .oneshot(preflight(
+                "/v1/publish/plugins/adi.tasks/1.0.0/linux-x86_64",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let methods = response.headers()[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("POST"));
+        assert!(!methods.contains("GET"));
+
+        let response = app
+            .clone()
+            .oneshot(preflight("/v1/index.json"))
+            .await
+            .unwrap();
+        let methods = response.headers()[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("GET"));
+        assert!(!methods.contains("POST"));
+
+        let response = app.oneshot(preflight("/v1/missing")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_subscriber_formats() {
+        for json in [false, true] {
+            tracing::subscriber::with_default(subscriber(json), || {
+                let span = tracing::info_span!("request", request_id = "abc", route = "/health");
+                let _guard = span.enter();
+                info!(status = 200, latency_ms = 1, "request completed");
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_counts_index_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        state
+            .storage
+            .publish_package(
+                "adi.suite",
+                "Suite",
+                "",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+        // Two platforms of one plugin still count as one entry
+        for (id, platform) in [
+            ("adi.tasks", "linux-x86_64"),
+            ("adi.tasks", "darwin-aarch64"),
+            ("adi.notes", "linux-x86_64"),
+        ] {
+            state
+                .storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    "1.0.0",
+                    platform,
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let index = state.storage.load_index().await.unwrap();
+        let app = Router::new()
+            .route("/v1/status.json", get(status))
+            .with_state(state);
+
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/v1/status.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["status"], "ok");
+        assert_eq!(status["packages"], 1);
+        assert_eq!(status["plugins"], 2);
+        assert_eq!(status["index_revision"], index.updated_at);
+        assert!(status["uptime_seconds"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_responses() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let app = Router::new()
+            .route("/metrics", get(prometheus_metrics))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                count_responses,
+            ))
+            .with_state(state);
+        let get_uri = |uri: &str| {
+            let request = axum::http::Request::builder()
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+
+        let response = get_uri("/missing").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        get_uri("/metrics").await.unwrap();
+        let response = get_uri("/metrics").await.unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        // The scrape being answered isn't counted yet
+        assert!(text.contains("registry_http_responses_total{code=\"200\"} 1\n"));
+        assert!(text.contains("registry_http_responses_total{code=\"404\"} 1\n"));
+        assert!(text.contains("registry_plugins 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_large_download_served_intact() {
+        // Not a multiple of any chunk size, so the last read is short
+        let artifact: Vec<u8> = (0..1_000_003u32).map(|i| (i % 251) as u8).collect();
+        for chunk_kb in [None, Some("1"), Some("256")] {
+            let tmp = tempfile::tempdir().unwrap();
+            let config = RegistryConfig::from_lookup(|name| {
+                (name == "REGISTRY_DOWNLOAD_CHUNK_KB")
+                    .then_some(chunk_kb)
+                    .flatten()
+                    .map(str::to_string)
+            })
+            .unwrap();
+            let state = Arc::new(AppState {
+                config,
+                ..test_state(tmp.path()).await
+            });
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    &artifact,
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+            let app = plugin_service_routes().with_state(state);
+            let download = |range: Option<&str>| {
+                let mut request = axum::http::Request::builder()
+                    .uri("/v1/plugins/adi.tasks/1.0.0/linux-x86_64.tar.gz");
+                if let Some(range) = range {
+                    request = request.header(header::RANGE, range);
+                }
+                let app = app.clone();
+                let request = request.body(Body::empty()).unwrap();
+                async move {
+                    let response = app.oneshot(request).await.unwrap();
+                    let length = response.headers()[header::CONTENT_LENGTH].clone();
+                    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                        .await
+                        .unwrap();
+                    (length, body)
+                }
+            };
+
+            let (length, body) = download(None).await;
+            assert_eq!(length, "1000003", "chunk {:?}", chunk_kb);
+            assert!(body[..] == artifact[..], "chunk {:?}", chunk_kb);
+            assert_eq!(sha256_hex(&body), sha256_hex(&artifact));
+
+            // A range crossing several chunk boundaries
+            let (length, body) = download(Some("bytes=1000-600000")).await;
+            assert_eq!(length, "599001", "chunk {:?}", chunk_kb);
+            assert!(body[..] == artifact[1000..=600000], "chunk {:?}", chunk_kb);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_filename() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let disposition = |filename: Option<&str>| {
+            let query = PluginServiceDownloadQuery {
+                verify: None,
+                filename: filename.map(str::to_string),
+            };
+            let state = &state;
+            async move {
+                let response = PluginServiceHandler::download(
+                    state,
+                    "adi.tasks".to_string(),
+                    "1.0.0".to_string(),
+                    "linux-x86_64.tar.gz".to_string(),
+                    query,
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+                response.headers()[header::CONTENT_DISPOSITION].clone()
+            }
+        };
+
+        assert_eq!(
+            disposition(None).await,
+            "attachment; filename=\"adi.tasks-1.0.0-linux-x86_64.tar.gz\""
+        );
+        assert_eq!(
+            disposition(Some("../my \"tasks\".tgz")).await,
+            "attachment; filename=\"_my__tasks_.tgz\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_served_bytes_header() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let artifact = vec![7u8; 70_000];
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                &artifact,
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let response = PluginServiceHandler::download(
+            &state,
+            "adi.tasks".to_string(),
+            "1.0.0".to_string(),
+            "linux-x86_64.tar.gz".to_string(),
+            PluginServiceDownloadQuery {
+                verify: None,
+                filename: None,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.headers()[SERVED_BYTES], "70000");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.len(), artifact.len());
+    }
+
+    #[test]
+    fn test_json_with_etag() {
+        let info = serde_json::json!({ "id": "adi.tasks", "version": "1.0.0" });
+        let response = json_with_etag(&info, None).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(etag.starts_with("W/\""));
+
+        let response = json_with_etag(&info, Some(&etag)).unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let changed = serde_json::json!({ "id": "adi.tasks", "version": "1.0.1" });
+        let response = json_with_etag(&changed, Some(&etag)).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let range = |start, end| Some(ByteRange { start, end });
+        assert_eq!(parse_range("bytes=0-99", 1000), range(0, 99));
+        assert_eq!(parse_range("bytes=900-", 1000), range(900, 999));
+        assert_eq!(parse_range("bytes=-100", 1000), range(900, 999));
+        assert_eq!(parse_range("bytes=990-2000", 1000), range(990, 999));
+        assert_eq!(parse_range("bytes=-5000", 1000), range(0, 999));
+        for invalid in [
+            "bytes=1000-",
+            "bytes=5-1",
+            "bytes=-0",
+            "bytes=0-1,5-9",
+            "items=0-1",
+            "bytes=a-b",
+        ] {
+            assert_eq!(parse_range(invalid, 1000), None, "{}", invalid);
+        }
+        assert_eq!(parse_range("bytes=0-", 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_download_range() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let artifact: Vec<u8> = (0..=255).collect();
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                &artifact,
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let download = |range: Option<&str>| {
+            PluginServiceHandler::download(
+                &state,
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                "linux-x86_64.tar.gz".to_string(),
+                PluginServiceDownloadQuery {
+                    verify: None,
+                    filename: None,
+                },
+                None,
+                None,
+                range.map(str::to_string),
+            )
+        };
+
+        let response = download(Some("bytes=100-149")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        let headers = response.headers();
+        assert_eq!(headers[header::CONTENT_RANGE], "bytes 100-149/256");
+        assert_eq!(headers[header::CONTENT_LENGTH], "50");
+        assert_eq!(headers[SERVED_BYTES], "50");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], &artifact[100..150]);
+
+        let response = download(Some("bytes=300-")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */256");
+
+        let response = download(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], &artifact[..]);
+    }
+
+    #[tokio::test]
+    async fn test_latest_download_redirects() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let builds = [
+            ("1.0.0", "darwin-aarch64", b"old".as_slice()),
+            ("1.0.0", "linux-x86_64", b"old".as_slice()),
+            ("1.1.0", "linux-x86_64", b"new".as_slice()),
+        ];
+        for (version, platform, data) in builds {
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    platform,
+                    data,
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let app = plugin_service_routes().with_state(Arc::new(state));
+        let get = |uri: String| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/v1/plugins/adi.tasks/latest/linux-x86_64.tar.gz?verify=true".into())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        let location = response.headers()[header::LOCATION].to_str().unwrap();
+        assert_eq!(
+            location,
+            "/v1/plugins/adi.tasks/1.1.0/linux-x86_64.tar.gz?verify=true"
+        );
+        let response = get(location.to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"new");
+
+        // Older versions' platforms aren't a fallback
+        let response = get("/v1/plugins/adi.tasks/latest/darwin-aarch64.tar.gz".into())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_publish_rejected_outside_allowed_cidrs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState {
+            config: RegistryConfig {
+                trust_proxy: true,
+                publish_ips: ip_filter::IpRules {
+                    allow: vec!["10.0.0.0/8".parse().unwrap()],
+                    deny: vec!["10.9.0.0/16".parse().unwrap()],
+                },
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        });
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"binary").unwrap();
+        let gzip = encoder.finish().unwrap();
+        let publish = |peer: [u8; 4], forwarded_for: Option<&str>| {
+            let app = plugin_publish_service_routes()
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    require_allowed_ip,
+                ))
+                .layer(axum::extract::connect_info::MockConnectInfo(
+                    SocketAddr::from((peer, 40000)),
+                ))
+                .layer(axum::Extension(Identity::new("test")))
+                .with_state(state.clone());
+            let mut request = axum::http::Request::builder()
+                .method("POST")
+                .uri("/v1/publish/plugins/adi.tasks/1.0.0/linux-x86_64?name=Tasks");
+            if let Some(ip) = forwarded_for {
+                request = request.header("x-forwarded-for", ip);
+            }
+            app.oneshot(request.body(Body::from(gzip.clone())).unwrap())
+        };
+
+        let response = publish([10, 9, 0, 5], None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = publish([192, 0, 2, 1], None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        // Behind a trusted proxy the address it appended decides, not the proxy's own
+        let response = publish([10, 1, 0, 1], Some("10.9.8.7")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        // Entries the client put in front of it are ignored
+        let response = publish([10, 1, 0, 1], Some("10.1.0.1, 10.9.8.7"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = publish([10, 1, 0, 1], Some("10.1.0.1, 192.0.2.1"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(state
+            .storage
+            .list_versions("plugins", "adi.tasks")
+            .await
+            .unwrap()
+            .is_empty());
+
+        let response = publish([10, 1, 0, 1], None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_report_counted_and_rate_limited() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState {
+            telemetry_limiter: RateLimiter::new(1, Duration::from_secs(60)),
+            ..test_state(tmp.path()).await
+        });
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let app = telemetry_service_routes()
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_telemetry,
+            ))
+            .with_state(state.clone());
+        let report = || {
+            let request = axum::http::Request::builder()
+                .method("POST")
+                .uri("/v1/plugins/adi.tasks/1.0.0/linux-x86_64/telemetry")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"outcome":"success"}"#))
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+
+        assert_eq!(report().await.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            report().await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        let telemetry = TelemetryServiceHandler::get(
+            state.as_ref(),
+            "adi.tasks".to_string(),
+            "1.0.0".to_string(),
+        )
+        .await
+        .unwrap();
+        let counts = &telemetry.platforms["linux-x86_64"];
+        assert_eq!((counts.success, counts.failure), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_id_is_gone() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        state
+            .storage
+            .record_deleted("plugins", "adi.tasks")
+            .await
+            .unwrap();
+
+        let err = PluginServiceHandler::get_latest_version(&state, "adi.tasks".to_string(), None)
+            .await
+            .unwrap_err();
+        assert_eq!((err.status, err.code.as_str()), (410, "gone"));
+        let err = PluginServiceHandler::download(
+            &state,
+            "adi.tasks".to_string(),
+            "1.0.0".to_string(),
+            "linux-x86_64.tar.gz".to_string(),
+            PluginServiceDownloadQuery {
+                verify: None,
+                filename: None,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, 410);
+
+        let err = PluginServiceHandler::get_latest_version(&state, "adi.never".to_string(), None)
+            .await
+            .unwrap_err();
+        assert_eq!((err.status, err.code.as_str()), (404, "not_found"));
+        // Tombstones are per kind
+        let err = PackageServiceHandler::get_latest(
+            &state,
+            "adi.tasks".to_string(),
+            PackageServiceGetLatestQuery { platform: None },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_default_search_kind() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = AppState {
+            config: RegistryConfig {
+                default_search_kind: "plugin".to_string(),
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        };
+        state
+            .storage
+            .publish_package(
+                "adi.suite",
+                "Suite",
+                "",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let response = SearchServiceHandler::search(
+            &state,
+            SearchServiceSearchQuery {
+                q: "suite".to_string(),
+                kind: None,
+                lang: None,
+                group_by: None,
+                limit: None,
+                offset: None,
+                sort: None,
+                tags: None,
+            },
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: SearchResults = serde_json::from_slice(&body).unwrap();
+        assert!(results.packages.is_empty());
+        assert!(results.plugins.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_artifact_base64_round_trips() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState {
+            config: RegistryConfig {
+                max_base64_bytes: 1024,
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        });
+        let small: Vec<u8> = (0..=255).collect();
+        for (version, data) in [("1.0.0", small.clone()), ("2.0.0", vec![0u8; 1025])] {
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    &data,
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let app = plugin_service_routes().with_state(state.clone());
+        let get = |uri: &str| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/v1/plugins/adi.tasks/1.0.0/linux-x86_64/base64.json")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let artifact: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let info = state
+            .storage
+            .get_plugin_info("adi.tasks", "1.0.0")
+            .await
+            .unwrap();
+        assert_eq!(artifact["platform"], "linux-x86_64");
+        assert_eq!(artifact["size_bytes"], 256);
+        assert_eq!(artifact["checksum"], info.platforms[0].checksum.as_str());
+        let data = artifact["data_base64"].as_str().unwrap();
+        assert_eq!(plugin_registry_core::decode_base64(data).unwrap(), small);
+
+        let response = get("/v1/plugins/adi.tasks/2.0.0/linux-x86_64/base64.json")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let response = get("/v1/plugins/adi.tasks/1.0.0/darwin-aarch64/base64.json")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_semantic_validation_is_unprocessable() {
+        let err = validate_published_at(1_000).unwrap_err();
+        assert_eq!(
+            (err.status, err.code.as_str()),
+            (422, "implausible_published_at")
+        );
+        assert_eq!(
+            validate_published_at(MIN_PUBLISHED_AT).unwrap(),
+            MIN_PUBLISHED_AT
+        );
+
+        let err = publish_error(
+            UnsatisfiedDependencies {
+                dependencies: vec!["adi.missing *".to_string()],
+            }
+            .into(),
+        );
+        assert_eq!(
+            (err.status, err.code.as_str()),
+            (422, "unsatisfied_dependencies")
+        );
+    }
+
+    #[test]
+    fn test_invalid_id_is_bad_request() {
+        let err = publish_error(
+            InvalidId {
+                id: "../etc".to_string(),
+            }
+            .into(),
+        );
+        assert_eq!((err.status, err.code.as_str()), (400, "bad_request"));
+    }
+
+    #[tokio::test]
+    async fn test_package_versions_route() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        for version in ["1.0.0", "2.0.0"] {
+            state
+                .storage
+                .publish_package(
+                    "adi.suite",
+                    "Suite",
+                    "",
+                    version,
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        let app = package_service_routes().with_state(state);
+        let get = |uri: &str| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/v1/packages/adi.suite/versions.json").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let versions: Vec<String> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(versions, ["2.0.0", "1.0.0"]);
+
+        let response = get("/v1/packages/adi.missing/versions.json").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_index_checksum_matches_served_index() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        for id in ["adi.tasks", "adi.agent"] {
+            state
+                .storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let app = index_service_routes().with_state(state);
+        let get = |uri: &str| {
+            let request = axum::http::Request::builder()
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            async {
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let index = get("/v1/index.json").await;
+        let checksum = get("/v1/index.sha256").await;
+        assert_eq!(checksum, sha256_hex(&index).as_bytes());
+        assert_eq!(get("/v1/index.sha256").await, checksum);
+    }
+
+    #[tokio::test]
+    async fn test_search_group_by() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = search_service_routes().with_state(Arc::new(test_state(tmp.path()).await));
+        let get = |uri: &str| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/v1/search?q=tasks&group_by=package").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: GroupedSearchResults = serde_json::from_slice(&body).unwrap();
+        assert!(results.groups.is_empty() && results.orphans.is_empty());
+
+        let response = get("/v1/search?q=tasks&group_by=author").await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_search_tag_filter() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let plugins = [
+            ("adi.tasks", vec!["Productivity", "cli"]),
+            ("adi.notes", vec!["productivity"]),
+            ("adi.theme", vec!["themes"]),
+        ];
+        for (id, tags) in plugins {
+            state
+                .storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    tags.into_iter().map(str::to_string).collect(),
+                )
+                .await
+                .unwrap();
+        }
+        let app = search_service_routes().with_state(Arc::new(state));
+        // Counted through `total`, with the page itself past the end
+        let matching = |query: &str| {
+            let request = axum::http::Request::builder()
+                .uri(format!("/v1/search?kind=plugin&offset=100&{}", query))
+                .body(Body::empty())
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<SearchResults>(&body)
+                    .unwrap()
+                    .total
+                    .plugins
+            }
+        };
+
+        assert_eq!(matching("q=&tags=productivity").await, 2);
+        assert_eq!(matching("q=&tags=PRODUCTIVITY,cli").await, 1);
+        assert_eq!(matching("q=notes&tags=productivity").await, 1);
+        assert_eq!(matching("q=theme&tags=productivity").await, 0);
+        assert_eq!(matching("q=adi&tags=").await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_search_revalidation() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        let app = search_service_routes().with_state(state.clone());
+        let search = |if_none_match: Option<String>| {
+            // Past the end of the results, so only `total` reflects the publish
+            let mut request =
+                axum::http::Request::builder().uri("/v1/search?q=tasks&kind=plugin&offset=10");
+            if let Some(etag) = if_none_match {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        let publish = |id: &'static str| {
+            state.storage.publish_plugin(
+                id,
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+        };
+
+        publish("adi.tasks").await.unwrap();
+        let response = search(None).await.unwrap();
+        assert!(response.headers().contains_key(header::CACHE_CONTROL));
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let response = search(Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // Most likely within the same second, so `updated_at` alone wouldn't change
+        publish("adi.tasks-sync").await.unwrap();
+        let response = search(Some(etag)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Download counts are part of the results too
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        state
+            .storage
+            .increment_downloads("plugins", "adi.tasks")
+            .await
+            .unwrap();
+        let response = search(Some(etag)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_search_offset_past_end() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        for id in ["adi.tasks", "adi.tasks-sync", "adi.tasks-ui"] {
+            state
+                .storage
+                .publish_plugin(
+                    id,
+                    "Tasks",
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let app = search_service_routes().with_state(Arc::new(state));
+
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/v1/search?q=tasks&kind=plugin&offset=5&limit=5000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: SearchResults = serde_json::from_slice(&body).unwrap();
+        assert!(results.plugins.is_empty());
+        assert_eq!((results.total.packages, results.total.plugins), (0, 3));
+        assert_eq!(
+            (results.limit, results.offset, results.clamped),
+            (1000, 5, true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_versions_route() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        for version in ["1.0.0", "1.1.0"] {
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        state
+            .storage
+            .publish_plugin_web_ui("adi.tasks", "1.0.0", b"export default {};")
+            .await
+            .unwrap();
+        let app = plugin_service_routes().with_state(state);
+        let get = |uri: &str| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/v1/plugins/adi.tasks/versions.json").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let versions: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            versions,
+            serde_json::json!([
+                { "version": "1.1.0", "has_web_ui": false },
+                { "version": "1.0.0", "has_web_ui": true },
+            ])
+        );
+
+        let response = get("/v1/plugins/adi.missing/versions.json").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_versions_etag_changes_on_publish() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let publish = |version: &'static str| {
+            state.storage.publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                version,
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+        };
+        let list = |if_none_match: Option<&str>| {
+            PluginServiceHandler::list_versions(
+                &state,
+                "adi.tasks".to_string(),
+                if_none_match.map(str::to_string),
+            )
+        };
+
+        publish("1.0.0").await.unwrap();
+        let response = list(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let response = list(Some(&etag)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        publish("1.1.0").await.unwrap();
+        let response = list(Some(&etag)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[header::ETAG], etag.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_verify_on_download_refuses_corrupt_artifact() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = AppState {
+            config: RegistryConfig {
+                verify_on_download: true,
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        };
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let download = || {
+            PluginServiceHandler::download(
+                &state,
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                "linux-x86_64.tar.gz".to_string(),
+                PluginServiceDownloadQuery {
+                    verify: None,
+                    filename: None,
+                },
+                None,
+                None,
+                None,
+            )
+        };
+
+        let response = download().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let path = state
+            .storage
+            .plugin_artifact_path("adi.tasks", "1.0.0", "linux-x86_64");
+        std::fs::write(&path, b"bin").unwrap();
+        let err = download().await.unwrap_err();
+        assert_eq!((err.status, err.code.as_str()), (500, "checksum_mismatch"));
+    }
+
+    /// Grants publishing only to tokens carrying a `role=publisher` claim.
+    struct ClaimAuthorizer;
+
+    #[async_trait]
+    impl Authorizer for ClaimAuthorizer {
+        async fn authorize(
+            &self,
+            action: Action,
+            id: &str,
+            token: Option<&str>,
+        ) -> Result<Identity, AuthError> {
+            let claims = token.ok_or_else(|| AuthError::Unauthenticated("No token".to_string()))?;
+            let allowed = match action {
+                Action::Publish => claims.contains("role=publisher") && id.starts_with("adi."),
+                Action::Delete | Action::Admin => false,
+            };
+            if !allowed {
+                return Err(AuthError::Forbidden(format!("{:?} denied", action)));
+            }
+            Ok(Identity::new("publisher"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_revoked_key_rejected_other_key_works() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let state = Arc::new(AppState {
+            authorizer: Arc::new(TokenFileAuthorizer::new(
+                state.keys.clone(),
+                Some("admin-secret".to_string()),
+            )),
+            config: RegistryConfig {
+                admin_key: Some("admin-secret".to_string()),
+                ..Default::default()
+            },
+            ..state
+        });
+        let app = router(state);
+        let send = |method: &str, uri: &str, token: &str, body: Body| {
+            let request = axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+        let publish = |token: &str| {
+            send(
+                "POST",
+                "/v1/publish/plugins/adi.tasks/1.0.0/linux-x86_64?name=Tasks&overwrite=true",
+                token,
+                Body::from("binary"),
+            )
+        };
+        let create = |label: &str| {
+            let body = serde_json::json!({ "label": label }).to_string();
+            let response = send("POST", "/v1/admin/keys", "admin-secret", Body::from(body));
+            async move {
+                let response = response.await.unwrap();
+                assert_eq!(response.status(), StatusCode::CREATED);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                created["key"].as_str().unwrap().to_string()
+            }
+        };
+
+        // Publishing stays open until the first key exists
+        let response = publish("anything").await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let ci = create("ci").await;
+        let release = create("release").await;
+        let response = publish(&ci).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = send("DELETE", "/v1/admin/keys/ci", "admin-secret", Body::empty())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = publish(&ci).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = publish(&release).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_custom_authorizer_guards_publish() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState {
+            authorizer: Arc::new(ClaimAuthorizer),
+            ..test_state(tmp.path()).await
+        });
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"binary").unwrap();
+        let gzip = encoder.finish().unwrap();
+        let publish = |id: &str, token: Option<&str>| {
+            let app = plugin_publish_service_routes()
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    require_api_key,
+                ))
+                .with_state(state.clone());
+            let uri = format!("/v1/publish/plugins/{}/1.0.0/linux-x86_64?name=Tasks", id);
+            let mut request = axum::http::Request::builder().method("POST").uri(uri);
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            app.oneshot(request.body(Body::from(gzip.clone())).unwrap())
+        };
+
+        let response = publish("adi.tasks", None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = publish("adi.tasks", Some("sub=ci;role=reader"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = publish("acme.tasks", Some("sub=ci;role=publisher"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = publish("adi.tasks", Some("sub=ci;role=publisher"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let entries = state
+            .storage
+            .read_audit(&AuditFilter::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].author, "unknown");
+        assert_eq!(entries[0].actor, "publisher");
+    }
+
+    #[tokio::test]
+    async fn test_yank_plugin_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        for id in ["adi.tasks", "adi.notes"] {
+            state
+                .storage
+                .publish_plugin(
+                    id,
+                    id,
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let app = plugin_service_routes()
+            .merge(plugin_yank_service_routes().layer(axum::Extension(Identity::new("test"))))
+            .merge(search_service_routes())
+            .with_state(Arc::new(state));
+        let send = |method: &str, uri: &str| {
+            let request = axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, body)
+            }
+        };
+
+        let (status, body) = send("POST", "/v1/plugins/adi.tasks/1.0.0/yank").await;
+        assert_eq!(status, StatusCode::OK);
+        let yanked: YankResponse = serde_json::from_slice(&body).unwrap();
+        assert!(yanked.yanked);
+        let (status, _) = send("POST", "/v1/plugins/adi.tasks/2.0.0/yank").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Its only version is yanked, so it no longer resolves or shows up in search
+        let (status, _) = send("GET", "/v1/plugins/adi.tasks/latest.txt").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) = send("GET", "/v1/search?q=adi&kind=plugin&offset=5").await;
+        let results: SearchResults = serde_json::from_slice(&body).unwrap();
+        assert_eq!(results.total.plugins, 1);
+        // ...but pinned downloads keep working
+        let uri = "/v1/plugins/adi.tasks/1.0.0/linux-x86_64.tar.gz";
+        assert_eq!(send("GET", uri).await.0, StatusCode::OK);
+
+        let (status, _) = send("POST", "/v1/plugins/adi.tasks/1.0.0/yank?undo=true").await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = send("GET", "/v1/plugins/adi.tasks/latest.txt").await;
+        assert_eq!((status, &body[..]), (StatusCode::OK, &b"1.0.0"[..]));
+    }
+
+    #[tokio::test]
+    async fn test_delete_plugin_version_route() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        for version in ["1.0.0", "1.1.0"] {
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    b"bin",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let delete = |state: Arc<AppState>, version: &str| {
+            // Shares its path with the public version lookup, which stays unguarded
+            let guard = middleware::from_fn_with_state(state.clone(), require_api_key);
+            let app = plugin_service_routes()
+                .merge(plugin_delete_service_routes().route_layer(guard))
+                .with_state(state);
+            let request = axum::http::Request::builder()
+                .method("DELETE")
+                .uri(format!("/v1/plugins/adi.tasks/{}", version))
+                .header("authorization", "Bearer sub=ci;role=publisher")
+                .body(Body::empty())
+                .unwrap();
+            app.oneshot(request)
+        };
+
+        let response = delete(state.clone(), "1.1.0").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deleted: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(deleted["latestVersion"], "1.0.0");
+        let response = delete(state.clone(), "1.1.0").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Deleting is its own action, not implied by publish rights
+        let guarded = Arc::new(AppState {
+            authorizer: Arc::new(ClaimAuthorizer),
+            ..test_state(tmp.path()).await
+        });
+        let response = delete(guarded, "1.0.0").await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_republish_needs_overwrite() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let publish = |platform: &str, overwrite: Option<bool>| {
+            PackagePublishServiceHandler::publish(
+                &state,
+                "adi.suite".to_string(),
+                "1.0.0".to_string(),
+                platform.to_string(),
+                PackagePublishServicePublishQuery {
+                    name: "Suite".to_string(),
+                    description: None,
+                    plugin_type: None,
+                    author: None,
+                    published_at: None,
+                    overwrite,
+                    changelog: None,
+                    dependencies: None,
+                    signature: None,
+                },
+                b"binary".to_vec(),
+                Identity::new("test"),
+            )
+        };
+
+        publish("linux-x86_64", None).await.unwrap();
+        let err = publish("linux-x86_64", None).await.unwrap_err();
+        assert_eq!((err.status, err.code.as_str()), (409, "conflict"));
+        publish("linux-x86_64", Some(true)).await.unwrap();
+        publish("darwin-aarch64", None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_publish_with_changelog() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let publish = |platform: &str, changelog: Option<&str>| {
+            PackagePublishServiceHandler::publish(
+                &state,
+                "adi.suite".to_string(),
+                "1.0.0".to_string(),
+                platform.to_string(),
+                PackagePublishServicePublishQuery {
+                    name: "Suite".to_string(),
+                    description: None,
+                    plugin_type: None,
+                    author: None,
+                    published_at: None,
+                    overwrite: None,
+                    changelog: changelog.map(str::to_string),
+                    dependencies: None,
+                    signature: None,
+                },
+                b"binary".to_vec(),
+                Identity::new("test"),
+            )
+        };
+        let changelog = || async {
+            let response = PackageServiceHandler::get_version(
+                &state,
+                "adi.suite".to_string(),
+                "1.0.0.json".to_string(),
+                PackageServiceGetVersionQuery { platform: None },
+                None,
+            )
+            .await
+            .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let info: PackageInfo = serde_json::from_slice(&body).unwrap();
+            info.changelog
+        };
+
+        publish("linux-x86_64", Some("Adds recurring tasks"))
+            .await
+            .unwrap();
+        assert_eq!(changelog().await.as_deref(), Some("Adds recurring tasks"));
+
+        // Adding a platform without one keeps the version's changelog
+        publish("darwin-aarch64", None).await.unwrap();
+        assert_eq!(changelog().await.as_deref(), Some("Adds recurring tasks"));
+    }
+
+    #[tokio::test]
+    async fn test_publish_plugin_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let publish = |version: &str, dependencies: &str| {
+            PluginPublishServiceHandler::publish(
+                &state,
+                "adi.tasks".to_string(),
+                version.to_string(),
+                "linux-x86_64".to_string(),
+                PluginPublishServicePublishQuery {
+                    name: "Tasks".to_string(),
+                    description: None,
+                    plugin_type: None,
+                    author: None,
+                    published_at: None,
+                    overwrite: None,
+                    changelog: None,
+                    dependencies: Some(dependencies.to_string()),
+                    signature: None,
+                },
+                b"binary".to_vec(),
+                Identity::new("test"),
+            )
+        };
+
+        publish(
+            "1.0.0",
+            r#"[{"id": "adi.core", "versionReq": "^1.2"},
+                {"id": "adi.theme", "versionReq": ">=0.3, <0.5", "optional": true}]"#,
+        )
+        .await
+        .unwrap();
+        let response = PluginServiceHandler::get_version(
+            &state,
+            "adi.tasks".to_string(),
+            "1.0.0.json".to_string(),
+            PluginServiceGetVersionQuery { platform: None },
+            None,
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let info: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            info["dependencies"],
+            serde_json::json!([
+                {"id": "adi.core", "versionReq": "^1.2", "optional": false},
+                {"id": "adi.theme", "versionReq": ">=0.3, <0.5", "optional": true},
+            ])
+        );
+
+        // An invalid requirement is refused before the artifact is stored
+        let err = publish(
+            "1.1.0",
+            r#"[{"id": "adi.core", "versionReq": "one point two"}]"#,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, 400);
+        assert!(!state
+            .storage
+            .plugin_artifact_path("adi.tasks", "1.1.0", "linux-x86_64")
+            .exists());
+        let err = publish("1.1.0", "adi.core@^1.2").await.unwrap_err();
+        assert_eq!(err.status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_dependencies_share_one_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let options = StorageOptions {
+            validate_dependencies: true,
+            ..Default::default()
+        };
+        let storage = Arc::new(RegistryStorage::with_options(
+            tmp.path().to_path_buf(),
+            options,
+        ));
+        let state = AppState {
+            storage,
+            ..test_state(tmp.path()).await
+        };
+        let publish = |id: &str, dependencies: &str| {
+            PluginPublishServiceHandler::publish(
+                &state,
+                id.to_string(),
+                "1.0.0".to_string(),
+                "linux-x86_64".to_string(),
+                PluginPublishServicePublishQuery {
+                    name: id.to_string(),
+                    description: None,
+                    plugin_type: None,
+                    author: None,
+                    published_at: None,
+                    overwrite: None,
+                    changelog: None,
+                    dependencies: Some(dependencies.to_string()),
+                    signature: None,
+                },
+                b"binary".to_vec(),
+                Identity::new("test"),
+            )
+        };
+
+        // Unsatisfied dependencies are refused before the artifact is stored
+        let err = publish("adi.tasks", r#"[{"id": "adi.core", "versionReq": "^1"}]"#)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, 422);
+        assert!(!state
+            .storage
+            .plugin_artifact_path("adi.tasks", "1.0.0", "linux-x86_64")
+            .exists());
+
+        publish("adi.core", "[]").await.unwrap();
+        publish("adi.tasks", r#"[{"id": "adi.core", "versionReq": "^1"}]"#)
+            .await
+            .unwrap();
+        let dependents = PluginServiceHandler::get_dependents(&state, "adi.core".to_string())
+            .await
+            .unwrap();
+        let ids: Vec<_> = dependents
+            .dependents
+            .iter()
+            .map(|d| d.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["adi.tasks"]);
+
+        // Dependencies given as plugin metadata are resolved like any other
+        PluginMetaPublishServiceHandler::publish(
+            &state,
+            "adi.notes".to_string(),
+            "1.0.0".to_string(),
+            PluginMetaRequest {
+                name: "Notes".to_string(),
+                description: None,
+                plugin_type: None,
+                author: None,
+                tags: None,
+                changelog: None,
+                dependencies: Some(HashMap::from([("adi.tasks".to_string(), "^1".to_string())])),
+                descriptions: None,
+            },
+            Identity::new("test"),
+        )
+        .await
+        .unwrap();
+        let resolved: Vec<_> = PluginServiceHandler::resolve_dependencies(
+            &state,
+            "adi.notes".to_string(),
+            "1.0.0".to_string(),
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+        assert_eq!(resolved, vec!["adi.notes", "adi.tasks", "adi.core"]);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_dependencies_route() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let publish = |id: &'static str, dependency: Option<(&'static str, &'static str)>| {
+            let storage = state.storage.clone();
+            async move {
+                storage
+                    .publish_plugin(
+                        id,
+                        id,
+                        "",
+                        "core",
+                        "1.0.0",
+                        "linux-x86_64",
+                        b"binary",
+                        "ADI Team",
+                        vec![],
+                    )
+                    .await
+                    .unwrap();
+                let dependencies: Vec<_> = dependency
+                    .into_iter()
+                    .map(|(id, version_req)| plugin_registry_core::Dependency {
+                        id: id.to_string(),
+                        version_req: version_req.to_string(),
+                        optional: false,
+                    })
+                    .collect();
+                storage
+                    .set_plugin_dependencies(id, "1.0.0", &dependencies)
+                    .await
+                    .unwrap();
+            }
+        };
+        let resolve = |id: &str, version: &str| {
+            PluginServiceHandler::resolve_dependencies(&state, id.to_string(), version.to_string())
+        };
+
+        publish("adi.core", None).await;
+        publish("adi.tasks", Some(("adi.core", "^1"))).await;
+        publish("adi.notes", Some(("adi.core", "^2"))).await;
+        publish("adi.loop", Some(("adi.loop", "^1"))).await;
+
+        let resolved: Vec<_> = resolve("adi.tasks", "1.0.0")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.id, r.version))
+            .collect();
+        assert_eq!(
+            resolved,
+            [
+                ("adi.tasks".to_string(), "1.0.0".to_string()),
+                ("adi.core".to_string(), "1.0.0".to_string()),
+            ]
+        );
+
+        let err = resolve("adi.notes", "1.0.0").await.unwrap_err();
+        assert_eq!(
+            (err.status, err.code.as_str()),
+            (409, "unsatisfiable_dependency")
+        );
+        let err = resolve("adi.loop", "1.0.0").await.unwrap_err();
+        assert_eq!((err.status, err.code.as_str()), (409, "dependency_cycle"));
+        let err = resolve("adi.tasks", "2.0.0").await.unwrap_err();
+        assert_eq!(err.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_batch_publish_rejects_malformed_multipart() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        let publish = |body: &'static str| {
+            let app = plugin_publish_service_routes()
+                .layer(axum::Extension(Identity::new("test")))
+                .with_state(state.clone());
+            let request = axum::http::Request::builder()
+                .method("POST")
+                .uri("/v1/publish/plugins/adi.tasks/1.0.0?name=Tasks")
+                .header("content-type", "multipart/form-data; boundary=XYZ")
+                .body(Body::from(body))
+                .unwrap();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                (status, error["message"].as_str().unwrap().to_string())
+            }
+        };
+
+        let (status, message) = publish("").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.starts_with("No multipart body"), "{}", message);
+
+        let (status, message) = publish(
+            "--XYZ\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"tasks.tar.gz\"\r\n\
+             \r\n\
+             binary\r\n\
+             --XYZ--\r\n",
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.contains("instead of \"file\""), "{}", message);
+
+        // Nothing was published
+        assert!(state
+            .storage
+            .list_versions("plugins", "adi.tasks")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_publish_from_url() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"binary").unwrap();
+        let gzip = encoder.finish().unwrap();
+        let source = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tasks.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(gzip.clone()))
+            .mount(&source)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/broken.tar.gz"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&source)
+            .await;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        let publish = |state: Arc<AppState>, version: &str, url: String| {
+            let app = plugin_publish_service_routes()
+                .layer(axum::Extension(Identity::new("test")))
+                .with_state(state);
+            let request = axum::http::Request::builder()
+                .method("POST")
+                .uri(format!(
+                    "/v1/publish/plugins/adi.tasks/{}/linux-x86_64/from-url?name=Tasks",
+                    version
+                ))
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::json!({ "url": url }).to_string()))
+                .unwrap();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                )
+            }
+        };
+
+        let url = format!("{}/tasks.tar.gz", source.uri());
+        let (status, body) = publish(state.clone(), "1.0.0", url.clone()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["size"], gzip.len());
+        let info = state
+            .storage
+            .get_plugin_info("adi.tasks", "1.0.0")
+            .await
+            .unwrap();
+        assert_eq!(body["checksum"], info.platforms[0].checksum.as_str());
+        let path = state
+            .storage
+            .plugin_artifact_path("adi.tasks", "1.0.0", "linux-x86_64");
+        assert_eq!(std::fs::read(path).unwrap(), gzip);
+
+        let broken = format!("{}/broken.tar.gz", source.uri());
+        let (status, _) = publish(state.clone(), "1.0.1", broken).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        let (status, _) = publish(state.clone(), "1.0.1", "ftp://example.com/a".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let capped = Arc::new(AppState {
+            fetcher: SourceFetcher::allowing_http(8, Duration::from_secs(5)).unwrap(),
+            ..test_state(tmp.path()).await
+        });
+        let (status, _) = publish(capped, "1.0.1", url).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        // Nothing is written for a failed fetch
+        assert!(state
+            .storage
+            .get_plugin_info("adi.tasks", "1.0.1")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_publish_and_serve_signature() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        let publish = |platform: &str, signature: Option<&str>| {
+            PluginPublishServiceHandler::publish(
+                &state,
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                platform.to_string(),
+                PluginPublishServicePublishQuery {
+                    name: "Tasks".to_string(),
+                    description: None,
+                    plugin_type: None,
+                    author: None,
+                    published_at: None,
+                    overwrite: None,
+                    changelog: None,
+                    dependencies: None,
+                    signature: signature.map(str::to_string),
+                },
+                b"binary".to_vec(),
+                Identity::new("test"),
+            )
+        };
+        let download = |file: &str| {
+            PluginServiceHandler::download(
+                &state,
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                file.to_string(),
+                PluginServiceDownloadQuery {
+                    verify: None,
+                    filename: None,
+                },
+                None,
+                None,
+                None,
+            )
+        };
+
+        publish("linux-x86_64", Some("-----BEGIN SIGNATURE-----"))
+            .await
+            .unwrap();
+        publish("darwin-aarch64", None).await.unwrap();
+
+        let info = state
+            .storage
+            .get_plugin_info("adi.tasks", "1.0.0")
+            .await
+            .unwrap();
+        let signed = info
+            .platforms
+            .iter()
+            .find(|b| b.platform == "linux-x86_64")
+            .unwrap();
+        assert_eq!(
+            signed.signature.as_deref(),
+            Some("-----BEGIN SIGNATURE-----")
+        );
+
+        let response = download("linux-x86_64.tar.gz.sig").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"-----BEGIN SIGNATURE-----");
+
+        // Unsigned builds and unknown platforms have nothing to serve
+        let err = download("darwin-aarch64.tar.gz.sig").await.unwrap_err();
+        assert_eq!(err.status, 404);
+        let err = download("windows-x86_64.tar.gz.sig").await.unwrap_err();
+        assert_eq!(err.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_publish_key_verifies_signatures() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = AppState {
+            config: RegistryConfig {
+                publish_pubkey: PublishKey::parse("11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo="),
+                ..Default::default()
+            },
+            ..test_state(tmp.path()).await
+        };
+        // Signed with the RFC 8032 test vector 1 key over the SHA-256 of b"fake binary"
+        let signature = "729TE5A1iorgJE3oA8ED00LPCdcSpG8O32KP/1a1BDPre1BOzmzNBhzWpbSeutayo22qUSaxwoffi8taL3QzDQ==";
+        let publish = |body: &[u8], signature: Option<&str>| {
+            PackagePublishServiceHandler::publish(
+                &state,
+                "adi.suite".to_string(),
+                "1.0.0".to_string(),
+                "linux-x86_64".to_string(),
+                PackagePublishServicePublishQuery {
+                    name: "Suite".to_string(),
+                    description: None,
+                    plugin_type: None,
+                    author: None,
+                    published_at: None,
+                    overwrite: None,
+                    changelog: None,
+                    dependencies: None,
+                    signature: signature.map(str::to_string),
+                },
+                body.to_vec(),
+                Identity::new("test"),
+            )
+        };
+
+        let err = publish(b"fake binary", None).await.unwrap_err();
+        assert_eq!(err.status, 400);
+        let err = publish(b"tampered binary", Some(signature))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, 400);
+        assert!(!state
+            .storage
+            .package_artifact_path("adi.suite", "1.0.0", "linux-x86_64")
+            .exists());
+
+        publish(b"fake binary", Some(signature)).await.unwrap();
+        let info = state
+            .storage
+            .get_package_info("adi.suite", "1.0.0")
+            .await
+            .unwrap();
+        assert_eq!(info.platforms[0].signature.as_deref(), Some(signature));
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_batch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        for (id, version) in [
+            ("adi.tasks", "1.0.0"),
+            ("adi.tasks", "1.1.0"),
+            ("adi.core", "2.0.0"),
+        ] {
+            PluginPublishServiceHandler::publish(
+                &state,
+                id.to_string(),
+                version.to_string(),
+                "linux-x86_64".to_string(),
+                PluginPublishServicePublishQuery {
+                    name: "Plugin".to_string(),
+                    description: None,
+                    plugin_type: None,
+                    author: None,
+                    published_at: None,
+                    overwrite: None,
+                    changelog: None,
+                    dependencies: None,
+                    signature: None,
+                },
+                b"binary".to_vec(),
+                Identity::new("test"),
+            )
+            .await
+            .unwrap();
+        }
+
+        let batch = PluginServiceHandler::get_latest_batch(
+            &state,
+            vec![
+                "adi.tasks".to_string(),
+                "adi.core".to_string(),
+                "adi.missing".to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch["adi.tasks"].version, "1.1.0");
+        assert_eq!(batch["adi.core"].version, "2.0.0");
+
+        let too_many = (0..=MAX_BATCH_IDS).map(|i| format!("adi.p{}", i)).collect();
+        let err = PluginServiceHandler::get_latest_batch(&state, too_many)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_compresses_json_but_not_artifacts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                &b"binary".repeat(100),
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let app = Router::new()
+            .merge(index_service_routes())
+            .merge(plugin_service_routes())
+            .layer(compression())
+            .with_state(state);
+        let get = |uri: &str, accept_encoding: &str| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .header(header::ACCEPT_ENCODING, accept_encoding)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        // The index gzips itself; the layer must not compress it a second time
+        let response = get("/v1/index.json", "gzip").await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut json = String::new();
+        GzDecoder::new(&body[..]).read_to_string(&mut json).unwrap();
+        assert!(json.contains("adi.tasks"));
+
+        let response = get("/v1/index.json", "deflate").await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "deflate");
+
+        let response = get("/v1/plugins/adi.tasks/latest.json", "gzip")
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+        let response = get("/v1/plugins/adi.tasks/1.0.0/linux-x86_64.tar.gz", "gzip")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.len(), 600);
+    }
+
+    #[tokio::test]
+    async fn test_index_since() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        for id in ["adi.tasks", "adi.notes"] {
+            state
+                .storage
+                .publish_plugin(
+                    id,
+                    "Plugin",
+                    "",
+                    "core",
+                    "1.0.0",
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        // Spread the two entries out in time
+        std::fs::write(
+            tmp.path().join("updated.json"),
+            r#"{"plugins": {"adi.tasks": 100, "adi.notes": 200}}"#,
+        )
+        .unwrap();
+        let get_index = |since: u64| {
+            IndexServiceHandler::get_index(
+                &state,
+                IndexServiceGetIndexQuery {
+                    updated_since: None,
+                    since: Some(since),
+                    lang: None,
+                },
+                None,
+                None,
+                None,
+            )
+        };
+
+        let response = get_index(100).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let index: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let ids: Vec<_> = index["plugins"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["adi.notes"]);
+
+        let updated_at = index["updated_at"].as_u64().unwrap();
+        let response = get_index(updated_at).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let err = IndexServiceHandler::get_index(
+            &state,
+            IndexServiceGetIndexQuery {
+                updated_since: Some(100),
+                since: Some(100),
+                lang: None,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_ready_fails_on_corrupt_index() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = Arc::new(test_state(tmp.path()).await);
+        let app = Router::new()
+            .route("/health", get(health))
+            .route("/ready", get(ready))
+            .with_state(state);
+        let get = |uri: &str| {
+            app.clone().oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/ready").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        std::fs::write(tmp.path().join("index.json"), "not json").unwrap();
+        let _ = std::fs::remove_file(tmp.path().join("index.json.bak"));
+        let response = get("/ready").await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "storage_unavailable");
+
+        // Liveness doesn't depend on storage
+        let response = get("/health").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_platform_filter() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        for platform in ["linux-x86_64", "darwin-aarch64"] {
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    "1.0.0",
+                    platform,
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let latest = |platform: Option<&str>| {
+            PluginServiceHandler::get_latest(
+                &state,
+                "adi.tasks".to_string(),
+                PluginServiceGetLatestQuery {
+                    platform: platform.map(str::to_string),
+                    req: None,
+                },
+            )
+        };
+
+        assert_eq!(latest(None).await.unwrap().platforms.len(), 2);
+        let info = latest(Some("Linux-X86_64")).await.unwrap();
+        let platforms: Vec<_> = info.platforms.iter().map(|p| p.platform.as_str()).collect();
+        assert_eq!(platforms, vec!["linux-x86_64"]);
+
+        let err = latest(Some("windows-x86_64")).await.unwrap_err();
+        assert_eq!(err.status, 404);
+        let err = PluginServiceHandler::get_version(
+            &state,
+            "adi.tasks".to_string(),
+            "1.0.0.json".to_string(),
+            PluginServiceGetVersionQuery {
+                platform: Some("windows-x86_64".to_string()),
+            },
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, 404);
+
+        let mut platforms = PluginServiceHandler::list_platforms(
+            &state,
+            "adi.tasks".to_string(),
+            "1.0.0".to_string(),
+        )
+        .await
+        .unwrap();
+        platforms.sort();
+        assert_eq!(platforms, vec!["darwin-aarch64", "linux-x86_64"]);
+    }
+
+    #[tokio::test]
+    async fn test_latest_matching_requirement() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        for version in ["1.2.0", "1.4.0", "2.0.0"] {
+            state
+                .storage
+                .publish_plugin(
+                    "adi.tasks",
+                    "Tasks",
+                    "",
+                    "core",
+                    version,
+                    "linux-x86_64",
+                    b"binary",
+                    "ADI Team",
+                    vec![],
+                )
+                .await
+                .unwrap();
+        }
+        let latest = |req: &str| {
+            PluginServiceHandler::get_latest(
+                &state,
+                "adi.tasks".to_string(),
+                PluginServiceGetLatestQuery {
+                    platform: None,
+                    req: Some(req.to_string()),
+                },
+            )
+        };
+
+        assert_eq!(latest("^1.2").await.unwrap().version, "1.4.0");
+        assert_eq!(latest("~1.2").await.unwrap().version, "1.2.0");
+        assert_eq!(latest("=2.0.0").await.unwrap().version, "2.0.0");
+        assert_eq!(latest("^3").await.unwrap_err().status, 404);
+        assert_eq!(latest("one point x").await.unwrap_err().status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_publish_and_download_assets() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = test_state(tmp.path()).await;
+        state
+            .storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "",
+                "core",
+                "1.0.0",
+                "linux-x86_64",
+                b"binary",
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+        let publish = |name: &str, data: &[u8]| {
+            PluginPublishServiceHandler::publish_asset(
+                &state,
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                name.to_string(),
+                data.to_vec(),
+                Identity::new("test"),
+            )
+        };
+        let download = |name: &str| {
+            PluginServiceHandler::download_asset(
+                &state,
+                "adi.tasks".to_string(),
+                "1.0.0".to_string(),
+                name.to_string(),
+            )
+        };
+
+        let asset = publish("schema.json", b"{\"type\": \"object\"}")
+            .await
+            .unwrap();
+        assert_eq!(asset.size_bytes, 18);
+        publish("icon.png", b"png").await.unwrap();
+        assert_eq!(publish("icon.png", b"png").await.unwrap_err().status, 409);
+        assert_eq!(
+            publish("../icon.png", b"png").await.unwrap_err().status,
+            400
+        );
+
+        let info = PluginServiceHandler::get_latest(
+            &state,
+            "adi.tasks".to_string(),
+            PluginServiceGetLatestQuery {
+                platform: None,
+                req: None,
+            },
+        )
+        .await
+        .unwrap();
+        let names: Vec<_> = info.assets.unwrap().into_iter().map(|a| a.name).collect();
+        assert_eq!(names, vec!["icon.png", "schema.json"]);
+        assert_eq!(info.platforms.len(), 1);
+
+        let response = download("schema.json").await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"{\"type\": \"object\"}");
+        assert_eq!(download("missing.txt").await.unwrap_err().status, 404);
+    }
 }
@@ -0,0 +1,135 @@
+//! Process-wide counters, rendered in the Prometheus text exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// Counters since startup. Index-derived gauges are passed in at render time.
+#[derive(Default)]
+pub struct Metrics {
+    publishes: AtomicU64,
+    downloads: AtomicU64,
+    /// Responses by status code; a code's first response takes the write lock once.
+    responses: RwLock<BTreeMap<u16, AtomicU64>>,
+}
+
+impl Metrics {
+    /// Count one successful publish of an artifact, metadata or web UI.
+    pub fn record_publish(&self) {
+        self.publishes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count one artifact download that started streaming.
+    pub fn record_download(&self) {
+        self.downloads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_response(&self, status: u16) {
+        if let Some(count) = self.responses.read().unwrap().get(&status) {
+            count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.responses
+            .write()
+            .unwrap()
+            .entry(status)
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render every metric, with the current package and plugin counts as gauges.
+    pub fn render(&self, packages: u64, plugins: u64) -> String {
+        let responses: Vec<_> = self
+            .responses
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(status, count)| {
+                (
+                    format!("{{code=\"{}\"}}", status),
+                    count.load(Ordering::Relaxed),
+                )
+            })
+            .collect();
+
+        let mut out = String::new();
+        let single = |value: u64| vec![(String::new(), value)];
+        write_metric(
+            &mut out,
+            ("registry_publishes_total", "counter"),
+            "Successful publishes of artifacts, metadata and web UIs.",
+            single(self.publishes.load(Ordering::Relaxed)),
+        );
+        write_metric(
+            &mut out,
+            ("registry_downloads_total", "counter"),
+            "Artifact downloads served.",
+            single(self.downloads.load(Ordering::Relaxed)),
+        );
+        write_metric(
+            &mut out,
+            ("registry_http_responses_total", "counter"),
+            "HTTP responses by status code.",
+            responses,
+        );
+        write_metric(
+            &mut out,
+            ("registry_packages", "gauge"),
+            "Packages in the index.",
+            single(packages),
+        );
+        write_metric(
+            &mut out,
+            ("registry_plugins", "gauge"),
+            "Plugins in the index.",
+            single(plugins),
+        );
+        out
+    }
+}
+
+/// One metric's `HELP` and `TYPE` lines, then a line per `(labels, value)` sample.
+fn write_metric(
+    out: &mut String,
+    (name, kind): (&str, &str),
+    help: &str,
+    samples: Vec<(String, u64)>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_exposition_format() {
+        let metrics = Metrics::default();
+        metrics.record_publish();
+        metrics.record_download();
+        metrics.record_download();
+        for status in [200, 404, 200] {
+            metrics.record_response(status);
+        }
+
+        let text = metrics.render(1, 3);
+        let samples: Vec<&str> = text.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            samples,
+            [
+                "registry_publishes_total 1",
+                "registry_downloads_total 2",
+                "registry_http_responses_total{code=\"200\"} 2",
+                "registry_http_responses_total{code=\"404\"} 1",
+                "registry_packages 1",
+                "registry_plugins 3",
+            ]
+        );
+        assert!(text.contains("# TYPE registry_plugins gauge\n"));
+    }
+}
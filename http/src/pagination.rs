@@ -0,0 +1,84 @@
+//! Page size limits shared by every paginated endpoint.
+
+/// Default and maximum page sizes, advertised via `/v1/capabilities.json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageLimits {
+    pub default_page_size: u32,
+    pub max_page_size: u32,
+}
+
+impl Default for PageLimits {
+    fn default() -> Self {
+        Self {
+            default_page_size: 100,
+            max_page_size: 1000,
+        }
+    }
+}
+
+/// The page size actually used for a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSize {
+    pub limit: u32,
+    /// The requested limit exceeded the cap and was reduced.
+    pub clamped: bool,
+}
+
+impl PageLimits {
+    /// Resolve a requested limit, clamping (rather than rejecting) anything over the cap.
+    pub fn resolve(&self, requested: Option<u32>) -> PageSize {
+        match requested {
+            None | Some(0) => PageSize {
+                limit: self.default_page_size,
+                clamped: false,
+            },
+            Some(n) if n > self.max_page_size => PageSize {
+                limit: self.max_page_size,
+                clamped: true,
+            },
+            Some(n) => PageSize {
+                limit: n,
+                clamped: false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_when_unset() {
+        let limits = PageLimits::default();
+        assert_eq!(
+            limits.resolve(None),
+            PageSize {
+                limit: 100,
+                clamped: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_over_cap_is_clamped() {
+        let limits = PageLimits {
+            default_page_size: 10,
+            max_page_size: 50,
+        };
+        assert_eq!(
+            limits.resolve(Some(500)),
+            PageSize {
+                limit: 50,
+                clamped: true
+            }
+        );
+        assert_eq!(
+            limits.resolve(Some(50)),
+            PageSize {
+                limit: 50,
+                clamped: false
+            }
+        );
+    }
+}
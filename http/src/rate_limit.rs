@@ -0,0 +1,63 @@
+//! Fixed-window request limits per client address.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Allows each client `limit` requests per `window`.
+///
+/// Clients whose address is unknown share one budget.
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    hits: Mutex<HashMap<Option<IpAddr>, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Mutex::default(),
+        }
+    }
+
+    /// Count a request from `client`; `false` once its budget for the window is spent.
+    pub fn allow(&self, client: Option<IpAddr>) -> bool {
+        self.allow_at(client, Instant::now())
+    }
+
+    fn allow_at(&self, client: Option<IpAddr>, now: Instant) -> bool {
+        let mut hits = self.hits.lock().unwrap();
+        // Forget finished windows so the map stays bounded by recent clients
+        hits.retain(|_, (started, _)| now.duration_since(*started) < self.window);
+        let (_, count) = hits.entry(client).or_insert((now, 0));
+        if *count >= self.limit {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_resets_after_window() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let (a, b) = (
+            Some("10.0.0.1".parse().unwrap()),
+            Some("10.0.0.2".parse().unwrap()),
+        );
+        let start = Instant::now();
+
+        assert!(limiter.allow_at(a, start));
+        assert!(limiter.allow_at(a, start));
+        assert!(!limiter.allow_at(a, start + Duration::from_secs(1)));
+        assert!(limiter.allow_at(b, start + Duration::from_secs(1)));
+        assert!(limiter.allow_at(a, start + Duration::from_secs(61)));
+    }
+}
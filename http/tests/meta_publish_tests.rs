@@ -0,0 +1,150 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::IntoResponse;
+use axum::Router;
+use http_body_util::BodyExt;
+use plugin_registry_core::{strip_extension, PluginVersionMeta, RegistryStorage};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+async fn setup() -> (RegistryStorage, tempfile::TempDir) {
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = RegistryStorage::new(tmp.path().to_path_buf());
+    storage.init().await.unwrap();
+    storage
+        .publish_plugin(
+            "adi.tasks",
+            "Tasks",
+            "Task management",
+            "core",
+            "1.0.0",
+            "linux-x86_64",
+            b"fake binary",
+            "ADI Team",
+            vec![],
+        )
+        .await
+        .unwrap();
+    (storage, tmp)
+}
+
+fn build_app(storage: RegistryStorage) -> Router {
+    use axum::extract::{Path, State};
+    use axum::routing::{get, post};
+
+    let storage = Arc::new(storage);
+
+    let publish_meta = |State(s): State<Arc<RegistryStorage>>,
+                        Path((id, version)): Path<(String, String)>,
+                        axum::Json(meta): axum::Json<PluginVersionMeta>| async move {
+        s.publish_plugin_meta(&id, &version, &meta).await.unwrap();
+        StatusCode::CREATED
+    };
+
+    let get_latest = |State(s): State<Arc<RegistryStorage>>, Path(id): Path<String>| async move {
+        match s.get_plugin_latest(&id).await {
+            Ok(info) => axum::Json(serde_json::to_value(&info).unwrap()).into_response(),
+            Err(_) => StatusCode::NOT_FOUND.into_response(),
+        }
+    };
+
+    let get_version = |State(s): State<Arc<RegistryStorage>>,
+                       Path((id, version)): Path<(String, String)>| async move {
+        let version = strip_extension(&version, ".json").unwrap();
+        match s.get_plugin_info(&id, version).await {
+            Ok(info) => axum::Json(serde_json::to_value(&info).unwrap()).into_response(),
+            Err(_) => StatusCode::NOT_FOUND.into_response(),
+        }
+    };
+
+    let download =
+        |State(s): State<Arc<RegistryStorage>>,
+         Path((id, version, platform)): Path<(String, String, String)>| async move {
+            let platform = strip_extension(&platform, ".tar.gz").unwrap();
+            if let Ok(data) = tokio::fs::read(s.plugin_artifact_path(&id, &version, platform)).await
+            {
+                return data.into_response();
+            }
+            let code = match s.get_plugin_info(&id, &version).await {
+                Ok(info) if info.platforms.is_empty() => "no_artifacts",
+                _ => "not_found",
+            };
+            (
+                StatusCode::NOT_FOUND,
+                axum::Json(serde_json::json!({ "code": code })),
+            )
+                .into_response()
+        };
+
+    Router::new()
+        .route("/v1/plugins/:id/latest.json", get(get_latest))
+        .route("/v1/plugins/:id/:version", get(get_version))
+        .route("/v1/plugins/:id/:version/:platform", get(download))
+        .route("/v1/plugins/:id/:version/meta", post(publish_meta))
+        .with_state(storage)
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+    (status, json)
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_metadata_only_version() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+
+    let meta = serde_json::json!({
+        "name": "Tasks",
+        "description": "Task management",
+        "plugin_type": "core",
+        "author": "ADI Team",
+        "changelog": "Adds recurring tasks",
+        "dependencies": { "adi.core": "^1.0" }
+    });
+    let (status, _) = send(
+        &app,
+        Request::builder()
+            .method("POST")
+            .uri("/v1/plugins/adi.tasks/2.0.0/meta")
+            .header("content-type", "application/json")
+            .body(Body::from(meta.to_string()))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, info) = send(&app, get("/v1/plugins/adi.tasks/2.0.0.json")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(info["version"], "2.0.0");
+    assert_eq!(info["platforms"].as_array().unwrap().len(), 0);
+
+    let (status, error) = send(&app, get("/v1/plugins/adi.tasks/2.0.0/linux-x86_64.tar.gz")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["code"], "no_artifacts");
+
+    // Without the artifact-less latest policy, latest keeps pointing at the built version
+    let (_, latest) = send(&app, get("/v1/plugins/adi.tasks/latest.json")).await;
+    assert_eq!(latest["version"], "1.0.0");
+}
+
+#[tokio::test]
+async fn test_missing_platform_still_plain_not_found() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+
+    let (status, error) = send(
+        &app,
+        get("/v1/plugins/adi.tasks/1.0.0/darwin-aarch64.tar.gz"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["code"], "not_found");
+}
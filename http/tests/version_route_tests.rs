@@ -0,0 +1,250 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::IntoResponse;
+use axum::Router;
+use http_body_util::BodyExt;
+use plugin_registry_core::{accepts, etag_matches, strip_extension, weak_etag, RegistryStorage};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+async fn setup() -> (RegistryStorage, tempfile::TempDir) {
+    let tmp = tempfile::tempdir().unwrap();
+    let storage = RegistryStorage::new(tmp.path().to_path_buf());
+    storage.init().await.unwrap();
+    for version in ["1.0.0", "1.0.0+build", "2024.01.15"] {
+        storage
+            .publish_plugin(
+                "adi.tasks",
+                "Tasks",
+                "Task management",
+                "core",
+                version,
+                "linux-x86_64",
+                version.as_bytes(),
+                "ADI Team",
+                vec![],
+            )
+            .await
+            .unwrap();
+    }
+    (storage, tmp)
+}
+
+fn build_app(storage: RegistryStorage) -> Router {
+    use axum::extract::{Path, State};
+    use axum::routing::get;
+
+    let storage = Arc::new(storage);
+
+    let get_latest = |State(s): State<Arc<RegistryStorage>>, Path(id): Path<String>| async move {
+        match s.get_plugin_latest(&id).await {
+            Ok(info) => axum::Json(serde_json::to_value(&info).unwrap()).into_response(),
+            Err(_) => StatusCode::NOT_FOUND.into_response(),
+        }
+    };
+
+    let get_version = |State(s): State<Arc<RegistryStorage>>,
+                       Path((id, version)): Path<(String, String)>| async move {
+        let Some(version) = strip_extension(&version, ".json") else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        match s.get_plugin_info(&id, version).await {
+            Ok(info) => axum::Json(serde_json::to_value(&info).unwrap()).into_response(),
+            Err(_) => StatusCode::NOT_FOUND.into_response(),
+        }
+    };
+
+    let download = |State(s): State<Arc<RegistryStorage>>,
+                    Path((id, version, platform)): Path<(String, String, String)>,
+                    headers: axum::http::HeaderMap| async move {
+        let accept = headers.get("accept").and_then(|v| v.to_str().ok());
+        if !accepts(accept, "application/gzip") {
+            return StatusCode::NOT_ACCEPTABLE.into_response();
+        }
+        let Some(platform) = strip_extension(&platform, ".tar.gz") else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let path = s.plugin_artifact_path(&id, &version, platform);
+        let Ok(metadata) = tokio::fs::metadata(&path).await else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let etag = weak_etag(&metadata);
+        let if_none_match = headers.get("if-none-match").and_then(|v| v.to_str().ok());
+        if if_none_match.is_some_and(|value| etag_matches(value, &etag)) {
+            return (StatusCode::NOT_MODIFIED, [("etag", etag)]).into_response();
+        }
+        match tokio::fs::read(path).await {
+            Ok(data) => ([("etag", etag)], data).into_response(),
+            Err(_) => StatusCode::NOT_FOUND.into_response(),
+        }
+    };
+
+    Router::new()
+        .route("/v1/plugins/:id/latest.json", get(get_latest))
+        .route("/v1/plugins/:id/:version", get(get_version))
+        .route("/v1/plugins/:id/:version/:platform", get(download))
+        .route("/v1/plugins/:id/:version/web.js", get(|| async { "web" }))
+        .with_state(storage)
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, body.to_vec())
+}
+
+async fn get_version_field(app: &Router, uri: &str) -> String {
+    let (status, body) = get(app, uri).await;
+    assert_eq!(status, StatusCode::OK, "GET {}", uri);
+    let info: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    info["version"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_get_plain_version() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+    assert_eq!(
+        get_version_field(&app, "/v1/plugins/adi.tasks/1.0.0.json").await,
+        "1.0.0"
+    );
+}
+
+#[tokio::test]
+async fn test_get_version_with_build_metadata() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+    assert_eq!(
+        get_version_field(&app, "/v1/plugins/adi.tasks/1.0.0+build.json").await,
+        "1.0.0+build"
+    );
+}
+
+#[tokio::test]
+async fn test_get_dotted_version() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+    assert_eq!(
+        get_version_field(&app, "/v1/plugins/adi.tasks/2024.01.15.json").await,
+        "2024.01.15"
+    );
+}
+
+#[tokio::test]
+async fn test_latest_not_shadowed_by_version_route() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+    assert_eq!(
+        get_version_field(&app, "/v1/plugins/adi.tasks/latest.json").await,
+        "2024.01.15"
+    );
+}
+
+#[tokio::test]
+async fn test_version_without_extension_not_found() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+    let (status, _) = get(&app, "/v1/plugins/adi.tasks/1.0.0").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_download_dotted_version() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+    let (status, body) = get(&app, "/v1/plugins/adi.tasks/2024.01.15/linux-x86_64.tar.gz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, b"2024.01.15");
+
+    let (status, body) = get(&app, "/v1/plugins/adi.tasks/1.0.0/web.js").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, b"web");
+}
+
+#[tokio::test]
+async fn test_download_not_acceptable() {
+    let (storage, _tmp) = setup().await;
+    let app = build_app(storage);
+
+    let request = |accept: &str| {
+        Request::builder()
+            .uri("/v1/plugins/adi.tasks/1.0.0/linux-x86_64.tar.gz")
+            .header("accept", accept)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request("application/json"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+
+    let response = app.oneshot(request("*/*")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_download_revalidates_with_weak_etag() {
+    let (storage, _tmp) = setup().await;
+    let path = storage.plugin_artifact_path("adi.tasks", "1.0.0", "linux-x86_64");
+    let app = build_app(storage);
+    let uri = "/v1/plugins/adi.tasks/1.0.0/linux-x86_64.tar.gz";
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert!(etag.starts_with("W/\""));
+
+    // Same size and mtime but different bytes: a 304 proves the body was never hashed.
+    let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+    std::fs::write(&path, b"9.9.9").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(uri)
+                .header("if-none-match", &etag)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(response
+        .into_body()
+        .collect()
+        .await
+        .unwrap()
+        .to_bytes()
+        .is_empty());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(uri)
+                .header("if-none-match", "W/\"0-0\"")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
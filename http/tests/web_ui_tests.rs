@@ -3,7 +3,7 @@ use axum::http::{Request, StatusCode};
 use axum::response::IntoResponse;
 use axum::Router;
 use http_body_util::BodyExt;
-use plugin_registry_core::RegistryStorage;
+use plugin_registry_core::{content_etag, RegistryStorage};
 use std::sync::Arc;
 use tower::ServiceExt;
 
@@ -90,6 +90,14 @@ fn build_app(storage: RegistryStorage) -> Router {
         }
     };
 
+    let get_raw_info = |State(s): State<Arc<RegistryStorage>>,
+                        Path((id, version)): Path<(String, String)>| async move {
+        match s.get_plugin_info_raw(&id, &version).await {
+            Ok(data) => ([(header::ETAG, content_etag(&data))], data).into_response(),
+            Err(_) => StatusCode::NOT_FOUND.into_response(),
+        }
+    };
+
     Router::new()
         .route(
             "/v1/publish/plugins/:id/:version/web",
@@ -97,6 +105,7 @@ fn build_app(storage: RegistryStorage) -> Router {
         )
         .route("/v1/plugins/:id/:version/web.js", get(download_web))
         .route("/v1/plugins/:id/latest.json", get(get_plugin_info))
+        .route("/v1/plugins/:id/:version/info.raw.json", get(get_raw_info))
         .with_state(storage)
 }
 
@@ -285,3 +294,46 @@ async fn test_plugin_info_has_web_ui_url() {
     assert_eq!(web_ui["entry_url"], "/v1/plugins/adi.tasks/1.0.0/web.js");
     assert_eq!(web_ui["size_bytes"], js.len() as u64);
 }
+
+#[tokio::test]
+async fn test_raw_info_is_byte_exact() {
+    let (storage, tmp) = setup().await;
+    storage
+        .publish_plugin_web_ui("adi.tasks", "1.0.0", b"js")
+        .await
+        .unwrap();
+    let on_disk = std::fs::read(tmp.path().join("plugins/adi.tasks/1.0.0/info.json")).unwrap();
+
+    let app = build_app(storage);
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/v1/plugins/adi.tasks/1.0.0/info.raw.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("etag").unwrap().to_str().unwrap(),
+        content_etag(&on_disk)
+    );
+    let raw = response_bytes(response).await;
+    assert_eq!(raw, on_disk);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/v1/plugins/adi.tasks/latest.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let augmented = response_bytes(response).await;
+    assert_ne!(augmented, raw);
+    let augmented: serde_json::Value = serde_json::from_slice(&augmented).unwrap();
+    assert!(augmented["web_ui"].is_object());
+}